@@ -0,0 +1,108 @@
+//! Shared memory encoding: the binary codec against the JSON encoding it replaced.
+//!
+//! Run with `cargo bench --bench storage`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use envsafe_cli::storage::{access, codec, EnvStorage, SegmentKey, SharedEnvData};
+use std::collections::HashMap;
+
+const SIZES: [usize; 3] = [10, 100, 1_000];
+
+fn snapshot(size: usize) -> SharedEnvData {
+    let variables: HashMap<String, String> = (0..size)
+        .map(|i| {
+            (
+                format!("SERVICE_{}_URL", i),
+                format!("https://{}.internal:{}", i, 8000 + i),
+            )
+        })
+        .collect();
+
+    SharedEnvData {
+        version: 1,
+        project_id: "bench".to_string(),
+        environment: "development".to_string(),
+        variables,
+        last_updated: chrono::Utc::now().to_rfc3339(),
+        etag: Some("\"1\"".to_string()),
+    }
+}
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+    for size in SIZES {
+        let data = snapshot(size);
+        group.bench_with_input(BenchmarkId::new("json", size), &data, |b, data| {
+            b.iter(|| serde_json::to_vec(black_box(data)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("binary", size), &data, |b, data| {
+            b.iter(|| codec::encode(black_box(data)))
+        });
+    }
+    group.finish();
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    for size in SIZES {
+        let data = snapshot(size);
+        let json = serde_json::to_vec(&data).unwrap();
+        let binary = codec::encode(&data);
+
+        group.bench_with_input(BenchmarkId::new("json", size), &json, |b, json| {
+            b.iter(|| serde_json::from_slice::<SharedEnvData>(black_box(json)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("binary", size), &binary, |b, binary| {
+            b.iter(|| codec::decode(black_box(binary)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// What `access::get_env` used to do (parse everything, then look up) against the index
+fn get_one(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_one");
+    for size in SIZES {
+        let data = snapshot(size);
+        let json = serde_json::to_vec(&data).unwrap();
+        let binary = codec::encode(&data);
+        let name = format!("SERVICE_{}_URL", size / 2);
+
+        group.bench_with_input(BenchmarkId::new("json", size), &json, |b, json| {
+            b.iter(|| {
+                let data: SharedEnvData = serde_json::from_slice(black_box(json)).unwrap();
+                data.variables.get(&name).cloned()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("binary", size), &binary, |b, binary| {
+            b.iter(|| codec::lookup(black_box(binary), &name).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Full path through shared memory, including the copy out of the segment and decryption
+fn shared_memory(c: &mut Criterion) {
+    let mut group = c.benchmark_group("shared_memory");
+    for size in SIZES {
+        let key = SegmentKey::new("bench-workspace", &format!("bench-{}", size), "development");
+        EnvStorage::open(&key)
+            .unwrap()
+            .write(&snapshot(size))
+            .unwrap();
+        let name = format!("SERVICE_{}_URL", size / 2);
+
+        group.bench_function(BenchmarkId::new("get_env", size), |b| {
+            b.iter(|| access::get_env(&key, &name).unwrap())
+        });
+        group.bench_function(BenchmarkId::new("get_env_vars", size), |b| {
+            b.iter(|| access::get_env_vars(&key).unwrap())
+        });
+
+        EnvStorage::remove(&key).unwrap();
+    }
+    group.finish();
+}
+
+criterion_group!(benches, encode, decode, get_one, shared_memory);
+criterion_main!(benches);
@@ -0,0 +1,205 @@
+//! Encryption of the environments cached in shared memory.
+//!
+//! Payloads are sealed with AES-256-GCM under a key generated once per login session. The
+//! key lives in a 0600 file in the user's runtime directory (`$XDG_RUNTIME_DIR`, emptied on
+//! logout), or in the config directory where there is none. Each payload is bound to its
+//! segment name, so a snapshot copied into another segment does not decrypt.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const KEY_FILE: &str = "shm.key";
+const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+
+static SESSION_KEY: OnceLock<SessionKey> = OnceLock::new();
+
+pub struct SessionKey(Key<Aes256Gcm>);
+
+impl SessionKey {
+    /// Key of the current session, created on first use
+    pub fn current() -> Result<&'static Self> {
+        if let Some(key) = SESSION_KEY.get() {
+            return Ok(key);
+        }
+        let key = Self::load_or_create(&Self::path()?)?;
+        Ok(SESSION_KEY.get_or_init(|| key))
+    }
+
+    pub fn path() -> Result<PathBuf> {
+        let dir = match dirs::runtime_dir() {
+            Some(dir) => dir,
+            None => dirs::config_dir()
+                .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?,
+        };
+        Ok(dir.join("envsafe-cli").join(KEY_FILE))
+    }
+
+    pub fn load_or_create(path: &Path) -> Result<Self> {
+        if let Some(key) = Self::load(path)? {
+            return Ok(key);
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write the key aside and link it into place, so concurrent processes agree on one key
+        let key = Aes256Gcm::generate_key(OsRng);
+        let pending = path.with_extension(format!("key.{}", std::process::id()));
+        write_private(&pending, key.as_slice())?;
+        let linked = fs::hard_link(&pending, path);
+        fs::remove_file(&pending)?;
+
+        match linked {
+            Ok(()) => Ok(Self(key)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Self::load(path)?
+                .ok_or_else(|| anyhow::anyhow!("Could not read {}", path.display())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        check_private(path)?;
+
+        let bytes = fs::read(path)?;
+        if bytes.len() != KEY_SIZE {
+            anyhow::bail!("{} is not a valid shared memory key", path.display());
+        }
+        Ok(Some(Self(*Key::<Aes256Gcm>::from_slice(&bytes))))
+    }
+
+    /// `nonce | ciphertext`, authenticated together with `segment`
+    pub fn seal(&self, plaintext: &[u8], segment: &str) -> Result<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(OsRng);
+        let ciphertext = Aes256Gcm::new(&self.0)
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: segment.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt shared memory payload"))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// `None` if the payload was sealed under another key or for another segment
+    pub fn open(&self, sealed: &[u8], segment: &str) -> Option<Vec<u8>> {
+        if sealed.len() < NONCE_SIZE {
+            return None;
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_SIZE);
+        Aes256Gcm::new(&self.0)
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: segment.as_bytes(),
+                },
+            )
+            .ok()
+    }
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, bytes: &[u8]) -> Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(bytes)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, bytes: &[u8]) -> Result<()> {
+    Ok(fs::write(path, bytes)?)
+}
+
+/// Refuse a key file another user owns or could read
+#[cfg(unix)]
+fn check_private(path: &Path) -> Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = fs::metadata(path)?;
+    if metadata.uid() != unsafe { libc::geteuid() } {
+        anyhow::bail!("{} belongs to another user", path.display());
+    }
+    if metadata.mode() & 0o077 != 0 {
+        anyhow::bail!(
+            "{} is accessible to other users. Delete it or run: chmod 600 {}",
+            path.display(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_private(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_seal_and_open() {
+        let dir = TempDir::new().unwrap();
+        let key = SessionKey::load_or_create(&dir.path().join(KEY_FILE)).unwrap();
+
+        let sealed = key.seal(b"SECRET=value", "envsafe_a").unwrap();
+        assert!(!sealed.windows(6).any(|w| w == b"SECRET"));
+        assert_eq!(key.open(&sealed, "envsafe_a").unwrap(), b"SECRET=value");
+
+        // Bound to its segment
+        assert!(key.open(&sealed, "envsafe_b").is_none());
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(key.open(&tampered, "envsafe_a").is_none());
+    }
+
+    #[test]
+    fn test_key_is_reused_and_private() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session").join(KEY_FILE);
+
+        let first = SessionKey::load_or_create(&path).unwrap();
+        let second = SessionKey::load_or_create(&path).unwrap();
+        let sealed = first.seal(b"payload", "segment").unwrap();
+        assert_eq!(second.open(&sealed, "segment").unwrap(), b"payload");
+
+        // Another session's key cannot read it
+        let other = SessionKey::load_or_create(&dir.path().join("other.key")).unwrap();
+        assert!(other.open(&sealed, "segment").is_none());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+            assert!(SessionKey::load_or_create(&path).is_err());
+        }
+    }
+}
@@ -0,0 +1,262 @@
+//! Binary encoding of a cached environment.
+//!
+//! ```text
+//! magic "ESB1" | version u64 | project, environment, last_updated, etag (str) | count u32
+//! index: count x (key offset u32, key len u32, value offset u32, value len u32), sorted by key
+//! data: keys and values, offsets relative to the start of the data
+//! ```
+//!
+//! Strings are a u32 length followed by UTF-8 bytes, an absent etag has length `u32::MAX`.
+//! All integers are little endian. The sorted index lets `lookup` binary-search one key
+//! without decoding the rest of the map.
+
+use crate::SharedEnvData;
+use anyhow::Result;
+use std::collections::HashMap;
+
+const MAGIC: &[u8; 4] = b"ESB1";
+const ENTRY_SIZE: usize = 16;
+const NO_STRING: u32 = u32::MAX;
+
+/// Whether `bytes` use this encoding, as opposed to an older one
+pub fn is_encoded(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+pub fn encode(data: &SharedEnvData) -> Vec<u8> {
+    let mut entries: Vec<(&String, &String)> = data.variables.iter().collect();
+    entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+
+    let data_size: usize = entries.iter().map(|(k, v)| k.len() + v.len()).sum();
+    let mut out = Vec::with_capacity(128 + entries.len() * ENTRY_SIZE + data_size);
+
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&data.version.to_le_bytes());
+    put_str(&mut out, Some(&data.project_id));
+    put_str(&mut out, Some(&data.environment));
+    put_str(&mut out, Some(&data.last_updated));
+    put_str(&mut out, data.etag.as_deref());
+    put_u32(&mut out, entries.len());
+
+    let mut offset = 0;
+    for (key, value) in &entries {
+        put_u32(&mut out, offset);
+        put_u32(&mut out, key.len());
+        put_u32(&mut out, offset + key.len());
+        put_u32(&mut out, value.len());
+        offset += key.len() + value.len();
+    }
+    for (key, value) in &entries {
+        out.extend_from_slice(key.as_bytes());
+        out.extend_from_slice(value.as_bytes());
+    }
+
+    out
+}
+
+pub fn decode(bytes: &[u8]) -> Result<SharedEnvData> {
+    let view = View::parse(bytes)?;
+    let mut variables = HashMap::with_capacity(view.count);
+    for i in 0..view.count {
+        let (key, value) = view.entry(i)?;
+        variables.insert(key.to_string(), value.to_string());
+    }
+
+    Ok(SharedEnvData {
+        version: view.version,
+        project_id: view.project_id.to_string(),
+        environment: view.environment.to_string(),
+        variables,
+        last_updated: view.last_updated.to_string(),
+        etag: view.etag.map(str::to_string),
+    })
+}
+
+/// Version of the snapshot, without reading the variables
+pub fn version(bytes: &[u8]) -> Result<u64> {
+    Ok(View::parse(bytes)?.version)
+}
+
+/// Value of one variable, found by binary search over the index
+pub fn lookup(bytes: &[u8], name: &str) -> Result<Option<String>> {
+    let view = View::parse(bytes)?;
+    let (mut low, mut high) = (0, view.count);
+
+    while low < high {
+        let mid = (low + high) / 2;
+        let key = view.key(mid)?;
+        match key.as_bytes().cmp(name.as_bytes()) {
+            std::cmp::Ordering::Equal => return Ok(Some(view.entry(mid)?.1.to_string())),
+            std::cmp::Ordering::Less => low = mid + 1,
+            std::cmp::Ordering::Greater => high = mid,
+        }
+    }
+    Ok(None)
+}
+
+/// Borrowed view over an encoded snapshot
+struct View<'a> {
+    version: u64,
+    project_id: &'a str,
+    environment: &'a str,
+    last_updated: &'a str,
+    etag: Option<&'a str>,
+    count: usize,
+    index: &'a [u8],
+    data: &'a [u8],
+}
+
+impl<'a> View<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.take(4)? != MAGIC {
+            anyhow::bail!("Unknown shared memory encoding");
+        }
+
+        let version = u64::from_le_bytes(reader.take(8)?.try_into()?);
+        let project_id = reader.string()?.unwrap_or_default();
+        let environment = reader.string()?.unwrap_or_default();
+        let last_updated = reader.string()?.unwrap_or_default();
+        let etag = reader.string()?;
+        let count = reader.u32()? as usize;
+        let index = reader.take(count.checked_mul(ENTRY_SIZE).ok_or_else(corrupt)?)?;
+
+        Ok(Self {
+            version,
+            project_id,
+            environment,
+            last_updated,
+            etag,
+            count,
+            index,
+            data: &bytes[reader.pos..],
+        })
+    }
+
+    fn key(&self, i: usize) -> Result<&'a str> {
+        let entry = &self.index[i * ENTRY_SIZE..(i + 1) * ENTRY_SIZE];
+        self.slice(u32_at(entry, 0), u32_at(entry, 4))
+    }
+
+    fn entry(&self, i: usize) -> Result<(&'a str, &'a str)> {
+        let entry = &self.index[i * ENTRY_SIZE..(i + 1) * ENTRY_SIZE];
+        Ok((
+            self.slice(u32_at(entry, 0), u32_at(entry, 4))?,
+            self.slice(u32_at(entry, 8), u32_at(entry, 12))?,
+        ))
+    }
+
+    fn slice(&self, offset: u32, len: u32) -> Result<&'a str> {
+        let start = offset as usize;
+        let end = start.checked_add(len as usize).ok_or_else(corrupt)?;
+        let bytes = self.data.get(start..end).ok_or_else(corrupt)?;
+        Ok(std::str::from_utf8(bytes)?)
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(len).ok_or_else(corrupt)?;
+        let bytes = self.bytes.get(self.pos..end).ok_or_else(corrupt)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn string(&mut self) -> Result<Option<&'a str>> {
+        match self.u32()? {
+            NO_STRING => Ok(None),
+            len => Ok(Some(std::str::from_utf8(self.take(len as usize)?)?)),
+        }
+    }
+}
+
+fn put_u32(out: &mut Vec<u8>, value: usize) {
+    out.extend_from_slice(&(value as u32).to_le_bytes());
+}
+
+fn put_str(out: &mut Vec<u8>, value: Option<&str>) {
+    match value {
+        Some(s) => {
+            put_u32(out, s.len());
+            out.extend_from_slice(s.as_bytes());
+        }
+        None => put_u32(out, NO_STRING as usize),
+    }
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn corrupt() -> anyhow::Error {
+    anyhow::anyhow!("Shared memory payload is truncated")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> SharedEnvData {
+        let variables = (0..100)
+            .map(|i| (format!("KEY_{:03}", i), format!("value-{}", i)))
+            .chain([("EMPTY".to_string(), String::new())])
+            .chain([("UNICODE".to_string(), "héllo ✓".to_string())])
+            .collect();
+
+        SharedEnvData {
+            version: 42,
+            project_id: "api".to_string(),
+            environment: "staging".to_string(),
+            variables,
+            last_updated: "2026-01-01T00:00:00+00:00".to_string(),
+            etag: Some("\"v7\"".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data = sample();
+        let decoded = decode(&encode(&data)).unwrap();
+
+        assert_eq!(decoded.version, 42);
+        assert_eq!(decoded.project_id, "api");
+        assert_eq!(decoded.environment, "staging");
+        assert_eq!(decoded.etag.as_deref(), Some("\"v7\""));
+        assert_eq!(decoded.variables, data.variables);
+
+        let mut no_etag = data;
+        no_etag.etag = None;
+        assert_eq!(decode(&encode(&no_etag)).unwrap().etag, None);
+    }
+
+    #[test]
+    fn test_lookup_and_version() {
+        let bytes = encode(&sample());
+
+        assert_eq!(version(&bytes).unwrap(), 42);
+        assert_eq!(lookup(&bytes, "KEY_000").unwrap().unwrap(), "value-0");
+        assert_eq!(lookup(&bytes, "KEY_099").unwrap().unwrap(), "value-99");
+        assert_eq!(lookup(&bytes, "EMPTY").unwrap().unwrap(), "");
+        assert_eq!(lookup(&bytes, "UNICODE").unwrap().unwrap(), "héllo ✓");
+        assert_eq!(lookup(&bytes, "KEY_100").unwrap(), None);
+        assert_eq!(lookup(&bytes, "").unwrap(), None);
+    }
+
+    #[test]
+    fn test_rejects_truncated_and_foreign_payloads() {
+        let bytes = encode(&sample());
+        assert!(decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(decode(&bytes[..20]).is_err());
+        assert!(decode(b"{\"version\":1}").is_err());
+        assert!(lookup(&bytes[..bytes.len() / 2], "UNICODE").is_err());
+    }
+}
@@ -0,0 +1,323 @@
+//! Read the environment variables the EnvSafe CLI caches in shared memory, from any Rust
+//! process of the same user, and follow them as `envsafe watch` hot-reloads them.
+//!
+//! ```no_run
+//! use envsafe_client::Client;
+//! use std::time::Duration;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! // In a process started by `envsafe run`; otherwise use `Client::open`
+//! let client = Client::from_env()?;
+//!
+//! let database_url = client.require("DATABASE_URL")?;
+//! let port: u16 = client.get_parsed("PORT")?.unwrap_or(3000);
+//! let debug = client.get_bool("DEBUG")?.unwrap_or(false);
+//!
+//! let _subscription = client.on_change(Duration::from_secs(1), |snapshot| {
+//!     println!("now at version {}", snapshot.version);
+//! });
+//! # Ok(())
+//! # }
+//! ```
+
+mod cipher;
+pub mod codec;
+pub mod segment;
+pub mod seqlock;
+
+pub use cipher::SessionKey;
+pub use segment::{SegmentKey, SharedEnvData, SEGMENT_ENV_VAR};
+
+use anyhow::Result;
+use shared_memory::Shmem;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Long-lived handle on one cached environment.
+///
+/// The segment is opened on first use and reopened when the CLI replaces it, so a client
+/// can be created before anything is cached and kept for the life of the process.
+pub struct Client {
+    segment: String,
+    mapping: Mutex<Option<Mapping>>,
+}
+
+/// A mapped segment, only ever accessed through the seqlock's atomics and copies
+struct Mapping(Shmem);
+
+// SAFETY: the mapping stays valid until dropped and no reference into it is handed out
+unsafe impl Send for Mapping {}
+
+impl Client {
+    /// Client for an environment of the current user
+    pub fn open(workspace: &str, project: &str, environment: &str) -> Self {
+        Self::for_key(&SegmentKey::new(workspace, project, environment))
+    }
+
+    pub fn for_key(key: &SegmentKey) -> Self {
+        Self::for_segment(key.os_id())
+    }
+
+    /// Client for a segment name, as found in `ENVSAFE_SHM_SEGMENT`
+    pub fn for_segment(segment: impl Into<String>) -> Self {
+        Self {
+            segment: segment.into(),
+            mapping: Mutex::new(None),
+        }
+    }
+
+    /// Client for the environment `envsafe run` started this process with
+    pub fn from_env() -> Result<Self> {
+        match std::env::var(SEGMENT_ENV_VAR) {
+            Ok(segment) if !segment.is_empty() => Ok(Self::for_segment(segment)),
+            _ => anyhow::bail!(
+                "{} is not set. Start the process with 'envsafe run' or use Client::open",
+                SEGMENT_ENV_VAR
+            ),
+        }
+    }
+
+    pub fn segment(&self) -> &str {
+        &self.segment
+    }
+
+    /// Version of the cached snapshot, 0 if nothing is cached
+    pub fn version(&self) -> Result<u64> {
+        match self.payload()? {
+            Some(payload) => codec::version(&payload),
+            None => Ok(0),
+        }
+    }
+
+    /// Everything cached, `None` if nothing is
+    pub fn snapshot(&self) -> Result<Option<SharedEnvData>> {
+        match self.payload()? {
+            Some(payload) => Ok(Some(codec::decode(&payload)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// All variables, empty if nothing is cached
+    pub fn all(&self) -> Result<HashMap<String, String>> {
+        Ok(self
+            .snapshot()?
+            .map(|data| data.variables)
+            .unwrap_or_default())
+    }
+
+    /// Value of one variable, without decoding the others
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        match self.payload()? {
+            Some(payload) => codec::lookup(&payload, key),
+            None => Ok(None),
+        }
+    }
+
+    /// Value of a variable that must be set
+    pub fn require(&self, key: &str) -> Result<String> {
+        self.get(key)?
+            .ok_or_else(|| anyhow::anyhow!("Variable '{}' is not set", key))
+    }
+
+    /// Value parsed with `FromStr`, e.g. `client.get_parsed::<u16>("PORT")`
+    pub fn get_parsed<T>(&self, key: &str) -> Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        match self.get(key)? {
+            Some(value) => value
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|e| anyhow::anyhow!("Variable '{}' is not valid: {}", key, e)),
+            None => Ok(None),
+        }
+    }
+
+    /// `true`/`false`, `1`/`0`, `yes`/`no` or `on`/`off`, in any case
+    pub fn get_bool(&self, key: &str) -> Result<Option<bool>> {
+        match self.get(key)? {
+            Some(value) => match value.trim().to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" | "on" => Ok(Some(true)),
+                "false" | "0" | "no" | "off" | "" => Ok(Some(false)),
+                _ => anyhow::bail!("Variable '{}' is not a boolean: '{}'", key, value),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Call `callback` with the new snapshot whenever the cached version changes, checking
+    /// every `interval` from a background thread. Stops when the subscription is dropped.
+    pub fn on_change<F>(&self, interval: Duration, mut callback: F) -> Subscription
+    where
+        F: FnMut(&SharedEnvData) + Send + 'static,
+    {
+        let client = Client::for_segment(self.segment.clone());
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        // Read before spawning, so a change made right after subscribing is not missed
+        let mut seen = self.version().unwrap_or(0);
+
+        let thread = std::thread::spawn(move || {
+            while !stopped.load(Ordering::Acquire) {
+                std::thread::park_timeout(interval);
+
+                // Errors are transient (segment being replaced, cache cleared): retry next tick
+                let Ok(version) = client.version() else {
+                    continue;
+                };
+                if version == seen {
+                    continue;
+                }
+                if let Ok(Some(snapshot)) = client.snapshot() {
+                    seen = snapshot.version;
+                    callback(&snapshot);
+                }
+            }
+        });
+
+        Subscription {
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    fn payload(&self) -> Result<Option<Vec<u8>>> {
+        let mut mapping = self.mapping.lock().unwrap_or_else(|e| e.into_inner());
+
+        let stale = match mapping.as_ref() {
+            Some(Mapping(shmem)) => segment::region(shmem).is_retired(),
+            None => true,
+        };
+        if stale {
+            *mapping = segment::open_segment(&self.segment)?.map(Mapping);
+        }
+
+        match mapping.as_ref() {
+            Some(Mapping(shmem)) => segment::read_payload(shmem, &self.segment),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Keeps an `on_change` callback running
+pub struct Subscription {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Subscription {
+    /// Stop calling the callback and wait for the background thread to finish
+    pub fn cancel(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    /// Stand-in for the CLI's writer
+    fn publish(segment: &str, version: u64, variables: &[(&str, &str)]) {
+        let data = SharedEnvData {
+            version,
+            project_id: "client-test".to_string(),
+            environment: "development".to_string(),
+            variables: variables
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            last_updated: chrono::Utc::now().to_rfc3339(),
+            etag: None,
+        };
+        let sealed = SessionKey::current()
+            .unwrap()
+            .seal(&codec::encode(&data), segment)
+            .unwrap();
+        let shmem = segment::create_or_open(segment, 4096).unwrap();
+        segment::region(&shmem).write(&sealed).unwrap();
+    }
+
+    fn test_segment(name: &str) -> String {
+        format!("envsafe_client_test_{}_{}", name, std::process::id())
+    }
+
+    #[test]
+    fn test_reads_and_typed_getters() {
+        let segment = test_segment("getters");
+        let client = Client::for_segment(&segment);
+
+        // Nothing cached yet
+        assert_eq!(client.version().unwrap(), 0);
+        assert_eq!(client.get("PORT").unwrap(), None);
+        assert!(client.all().unwrap().is_empty());
+
+        publish(
+            &segment,
+            1,
+            &[("PORT", "8080"), ("DEBUG", "On"), ("NAME", "api")],
+        );
+
+        assert_eq!(client.version().unwrap(), 1);
+        assert_eq!(client.get("NAME").unwrap().as_deref(), Some("api"));
+        assert_eq!(client.get_parsed::<u16>("PORT").unwrap(), Some(8080));
+        assert_eq!(client.get_bool("DEBUG").unwrap(), Some(true));
+        assert!(client.get_parsed::<u16>("NAME").is_err());
+        assert!(client.get_bool("PORT").is_err());
+        assert!(client.require("MISSING").is_err());
+        assert_eq!(client.all().unwrap().len(), 3);
+
+        segment::unlink(&segment).unwrap();
+    }
+
+    #[test]
+    fn test_on_change_follows_updates() {
+        let segment = test_segment("subscription");
+        publish(&segment, 1, &[("FLAG", "old")]);
+
+        let client = Client::for_segment(&segment);
+        let (sender, receiver) = mpsc::channel();
+        let subscription = client.on_change(Duration::from_millis(10), move |snapshot| {
+            let _ = sender.send((snapshot.version, snapshot.variables["FLAG"].clone()));
+        });
+
+        publish(&segment, 2, &[("FLAG", "new")]);
+        let (version, flag) = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(version, 2);
+        assert_eq!(flag, "new");
+
+        subscription.cancel();
+        publish(&segment, 3, &[("FLAG", "ignored")]);
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+
+        segment::unlink(&segment).unwrap();
+    }
+
+    #[test]
+    fn test_from_env_requires_segment() {
+        // Only meaningful when the test itself is not started by `envsafe run`
+        if std::env::var(SEGMENT_ENV_VAR).is_err() {
+            assert!(Client::from_env().is_err());
+        }
+    }
+}
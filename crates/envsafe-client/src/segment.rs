@@ -0,0 +1,187 @@
+//! Naming, opening and reading the shared memory segments the CLI writes to.
+//!
+//! Low level: services should use [`Client`](crate::Client). The CLI builds its writer on
+//! top of these.
+
+use crate::cipher::SessionKey;
+use crate::{codec, seqlock};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared_memory::{Shmem, ShmemConf};
+use std::collections::HashMap;
+
+pub use seqlock::segment_size;
+
+/// Environment variable through which `envsafe run` tells child processes which segment
+/// holds their variables
+pub const SEGMENT_ENV_VAR: &str = "ENVSAFE_SHM_SEGMENT";
+
+/// One cached environment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedEnvData {
+    pub version: u64,
+    pub project_id: String,
+    pub environment: String,
+    pub variables: HashMap<String, String>,
+    pub last_updated: String,
+    /// Server version (ETag) the variables were fetched at, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+}
+
+impl SharedEnvData {
+    /// Time since the snapshot was written; `None` if its timestamp is unreadable
+    pub fn age(&self) -> Option<std::time::Duration> {
+        let updated = chrono::DateTime::parse_from_rfc3339(&self.last_updated).ok()?;
+        (chrono::Utc::now() - updated.with_timezone(&chrono::Utc))
+            .to_std()
+            .ok()
+            .or(Some(std::time::Duration::ZERO))
+    }
+}
+
+/// Identifies the shared memory segment caching one environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentKey {
+    pub user: String,
+    pub workspace: String,
+    pub project: String,
+    pub environment: String,
+}
+
+impl SegmentKey {
+    /// Key for the current user
+    pub fn new(workspace: &str, project: &str, environment: &str) -> Self {
+        Self {
+            user: current_user(),
+            workspace: workspace.to_string(),
+            project: project.to_string(),
+            environment: environment.to_string(),
+        }
+    }
+
+    /// Name of the segment, stable across processes and builds
+    pub fn os_id(&self) -> String {
+        let parts = [
+            self.user.as_str(),
+            self.workspace.as_str(),
+            self.project.as_str(),
+            self.environment.as_str(),
+        ];
+        format!("envsafe_{}", short_hash(&parts.join("\0")))
+    }
+}
+
+pub fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "default".to_string())
+}
+
+pub fn short_hash(value: &str) -> String {
+    hex::encode(&Sha256::digest(value.as_bytes())[..8])
+}
+
+/// Create a segment for payloads of up to `capacity` bytes, or open the existing one
+pub fn create_or_open(os_id: &str, capacity: usize) -> Result<Shmem> {
+    let size = segment_size(capacity);
+    let mut shmem = match ShmemConf::new().size(size).os_id(os_id).create() {
+        Ok(m) => m,
+        Err(_) => {
+            // If creation fails (likely because it exists), try to open it
+            ShmemConf::new().os_id(os_id).open()?
+        }
+    };
+
+    // Keep the segment once this process exits, `unlink` deletes it
+    shmem.set_owner(false);
+    check_owner(os_id)?;
+    Ok(shmem)
+}
+
+/// Open an existing segment, `None` if there is none
+pub fn open_segment(os_id: &str) -> Result<Option<Shmem>> {
+    match ShmemConf::new().os_id(os_id).open() {
+        Ok(shmem) => {
+            check_owner(os_id)?;
+            Ok(Some(shmem))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Delete a segment. Returns `false` if there was none.
+pub fn unlink(os_id: &str) -> Result<bool> {
+    match open_segment(os_id)? {
+        Some(mut shmem) => {
+            // The owner unlinks the segment when dropped
+            shmem.set_owner(true);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Refuse segments created by another user, whatever their content, and make sure
+/// ours are only accessible to us (0600) regardless of the umask they were created with
+#[cfg(unix)]
+fn check_owner(os_id: &str) -> Result<()> {
+    let name = std::ffi::CString::new(os_id)?;
+
+    unsafe {
+        let fd = libc::shm_open(name.as_ptr(), libc::O_RDONLY, 0);
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        let mut stat: libc::stat = std::mem::zeroed();
+        let result = if libc::fstat(fd, &mut stat) != 0 {
+            Err(std::io::Error::last_os_error().into())
+        } else if stat.st_uid != libc::geteuid() {
+            Err(anyhow::anyhow!(
+                "Shared memory segment {} belongs to another user",
+                os_id
+            ))
+        } else if stat.st_mode & 0o077 != 0 && libc::fchmod(fd, 0o600) != 0 {
+            Err(std::io::Error::last_os_error().into())
+        } else {
+            Ok(())
+        };
+
+        libc::close(fd);
+        result
+    }
+}
+
+#[cfg(not(unix))]
+fn check_owner(_os_id: &str) -> Result<()> {
+    Ok(())
+}
+
+pub fn region(shmem: &Shmem) -> seqlock::Region<'_> {
+    // Mappings are page aligned and at least one header long
+    unsafe { seqlock::Region::new(shmem.as_ptr(), shmem.len()) }
+}
+
+/// Decrypted, encoded payload of the latest snapshot in `shmem`. A snapshot written in
+/// another session, or by a release using another encoding, reads as nothing cached.
+pub fn read_payload(shmem: &Shmem, os_id: &str) -> Result<Option<Vec<u8>>> {
+    let current = region(shmem);
+    let sealed = if current.is_retired() {
+        // Replaced by a larger segment since this handle was opened
+        match open_segment(os_id)? {
+            Some(shmem) => region(&shmem).read()?,
+            None => None,
+        }
+    } else {
+        current.read()?
+    };
+
+    let Some(sealed) = sealed else {
+        return Ok(None);
+    };
+    Ok(SessionKey::current()?
+        .open(&sealed, os_id)
+        .filter(|payload| codec::is_encoded(payload)))
+}
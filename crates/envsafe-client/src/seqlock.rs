@@ -0,0 +1,358 @@
+//! Segment layout that readers and writers in different processes can share safely.
+//!
+//! ```text
+//! 0   magic          u64   "ENVSAFE\0"
+//! 8   format version u32
+//! 12  active buffer  u32   0 or 1
+//! 16  sequence       u64   odd while a writer holds the segment
+//! 24  retired        u64   set once the segment was replaced by a larger one
+//! 32  length, checksum of buffer 0   u64, u64
+//! 48  length, checksum of buffer 1   u64, u64
+//! 64  buffer 0 | buffer 1
+//! ```
+//!
+//! A writer takes the segment by making the sequence odd, fills the inactive buffer,
+//! publishes its length and checksum and only then flips the active buffer. Readers copy
+//! the active buffer and keep the copy only if the sequence did not move meanwhile, so a
+//! payload is never observed half-written.
+
+use anyhow::Result;
+use std::marker::PhantomData;
+use std::sync::atomic::{fence, AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+pub const MAGIC: u64 = u64::from_le_bytes(*b"ENVSAFE\0");
+pub const FORMAT_VERSION: u32 = 1;
+pub const HEADER_SIZE: usize = 64;
+
+const MAGIC_OFFSET: usize = 0;
+const VERSION_OFFSET: usize = 8;
+const ACTIVE_OFFSET: usize = 12;
+const SEQUENCE_OFFSET: usize = 16;
+const RETIRED_OFFSET: usize = 24;
+const BUFFER_META_OFFSET: usize = 32;
+
+/// How long a writer waits for another one before assuming it died mid-write
+const STALE_WRITER: Duration = Duration::from_secs(2);
+/// Reads retried because a writer kept overtaking us, before giving up
+const READ_ATTEMPTS: usize = 100_000;
+
+/// Size of a segment holding payloads of up to `capacity` bytes
+pub const fn segment_size(capacity: usize) -> usize {
+    HEADER_SIZE + 2 * capacity
+}
+
+/// A mapped segment using the layout above
+pub struct Region<'a> {
+    ptr: *mut u8,
+    len: usize,
+    _mapping: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> Region<'a> {
+    /// # Safety
+    ///
+    /// `ptr` must be 8-byte aligned and point to `len` bytes of memory that stay
+    /// mapped for `'a`, at least `HEADER_SIZE` long.
+    pub unsafe fn new(ptr: *mut u8, len: usize) -> Self {
+        assert!(len >= HEADER_SIZE && (ptr as usize).is_multiple_of(8));
+        Self {
+            ptr,
+            len,
+            _mapping: PhantomData,
+        }
+    }
+
+    /// Largest payload the segment can hold
+    pub fn capacity(&self) -> usize {
+        (self.len - HEADER_SIZE) / 2
+    }
+
+    /// Latest complete payload, or `None` if nothing was written yet
+    pub fn read(&self) -> Result<Option<Vec<u8>>> {
+        let sequence = self.u64_at(SEQUENCE_OFFSET);
+
+        for _ in 0..READ_ATTEMPTS {
+            let before = sequence.load(Ordering::Acquire);
+            let snapshot = self.snapshot();
+            fence(Ordering::Acquire);
+
+            if sequence.load(Ordering::Relaxed) == before {
+                return snapshot;
+            }
+            std::thread::yield_now();
+        }
+
+        anyhow::bail!("Shared memory is being rewritten too often to be read")
+    }
+
+    /// Replace the payload
+    pub fn write(&self, payload: &[u8]) -> Result<()> {
+        let sequence = self.lock();
+        let result = self.publish(payload);
+        self.unlock(sequence);
+        result
+    }
+
+    /// Read-modify-write the payload without another writer interleaving
+    pub fn update(&self, f: impl FnOnce(Option<Vec<u8>>) -> Result<Vec<u8>>) -> Result<()> {
+        let sequence = self.lock();
+        let result = self
+            .snapshot()
+            .and_then(f)
+            .and_then(|next| self.publish(&next));
+        self.unlock(sequence);
+        result
+    }
+
+    /// Mark the segment as replaced, so handles still mapping it know to reopen it by name
+    pub fn retire(&self) {
+        let sequence = self.lock();
+        self.u64_at(RETIRED_OFFSET).store(1, Ordering::Release);
+        self.unlock(sequence);
+    }
+
+    pub fn is_retired(&self) -> bool {
+        self.u64_at(RETIRED_OFFSET).load(Ordering::Acquire) != 0
+    }
+
+    /// Copy of the active buffer. Only consistent while holding the lock or if the
+    /// sequence is unchanged afterwards.
+    fn snapshot(&self) -> Result<Option<Vec<u8>>> {
+        if self.u64_at(MAGIC_OFFSET).load(Ordering::Acquire) != MAGIC
+            || self.u32_at(VERSION_OFFSET).load(Ordering::Acquire) != FORMAT_VERSION
+        {
+            return Ok(None);
+        }
+
+        let active = self.u32_at(ACTIVE_OFFSET).load(Ordering::Acquire) as usize & 1;
+        let (len, checksum) = self.buffer_meta(active);
+        let len = len.load(Ordering::Acquire) as usize;
+        let checksum = checksum.load(Ordering::Acquire);
+
+        if len == 0 {
+            return Ok(None);
+        }
+        if len > self.capacity() {
+            anyhow::bail!("Shared memory payload is corrupt");
+        }
+
+        let mut payload = vec![0u8; len];
+        unsafe {
+            std::ptr::copy_nonoverlapping(self.buffer(active), payload.as_mut_ptr(), len);
+        }
+
+        if fnv1a(&payload) != checksum {
+            anyhow::bail!("Shared memory payload is corrupt");
+        }
+        Ok(Some(payload))
+    }
+
+    /// Fill the inactive buffer and make it the active one. Requires the lock.
+    fn publish(&self, payload: &[u8]) -> Result<()> {
+        if payload.len() > self.capacity() {
+            anyhow::bail!("Data too large for shared memory");
+        }
+
+        if self.u64_at(MAGIC_OFFSET).load(Ordering::Acquire) != MAGIC
+            || self.u32_at(VERSION_OFFSET).load(Ordering::Acquire) != FORMAT_VERSION
+        {
+            // Fresh segment, or one written by an older release
+            for buffer in 0..2 {
+                let (len, checksum) = self.buffer_meta(buffer);
+                len.store(0, Ordering::Relaxed);
+                checksum.store(0, Ordering::Relaxed);
+            }
+            self.u32_at(ACTIVE_OFFSET).store(0, Ordering::Relaxed);
+            self.u64_at(RETIRED_OFFSET).store(0, Ordering::Relaxed);
+            self.u32_at(VERSION_OFFSET)
+                .store(FORMAT_VERSION, Ordering::Release);
+            self.u64_at(MAGIC_OFFSET).store(MAGIC, Ordering::Release);
+        }
+
+        let next = 1 - (self.u32_at(ACTIVE_OFFSET).load(Ordering::Acquire) as usize & 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(payload.as_ptr(), self.buffer(next), payload.len());
+        }
+
+        let (len, checksum) = self.buffer_meta(next);
+        len.store(payload.len() as u64, Ordering::Release);
+        checksum.store(fnv1a(payload), Ordering::Release);
+        self.u32_at(ACTIVE_OFFSET)
+            .store(next as u32, Ordering::Release);
+        Ok(())
+    }
+
+    /// Make the sequence odd. Returns the odd value to pass to `unlock`.
+    fn lock(&self) -> u64 {
+        let sequence = self.u64_at(SEQUENCE_OFFSET);
+        let started = Instant::now();
+
+        loop {
+            let current = sequence.load(Ordering::Acquire);
+            let next = if current.is_multiple_of(2) {
+                current + 1
+            } else if started.elapsed() > STALE_WRITER {
+                // The holder died mid-write: its half-written buffer is never active, take over
+                current + 2
+            } else {
+                std::thread::yield_now();
+                continue;
+            };
+
+            if sequence
+                .compare_exchange_weak(current, next, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+            {
+                return next;
+            }
+        }
+    }
+
+    fn unlock(&self, locked: u64) {
+        self.u64_at(SEQUENCE_OFFSET)
+            .store(locked + 1, Ordering::Release);
+    }
+
+    fn u64_at(&self, offset: usize) -> &AtomicU64 {
+        unsafe { &*(self.ptr.add(offset) as *const AtomicU64) }
+    }
+
+    fn u32_at(&self, offset: usize) -> &AtomicU32 {
+        unsafe { &*(self.ptr.add(offset) as *const AtomicU32) }
+    }
+
+    fn buffer_meta(&self, buffer: usize) -> (&AtomicU64, &AtomicU64) {
+        let offset = BUFFER_META_OFFSET + buffer * 16;
+        (self.u64_at(offset), self.u64_at(offset + 8))
+    }
+
+    fn buffer(&self, buffer: usize) -> *mut u8 {
+        unsafe { self.ptr.add(HEADER_SIZE + buffer * self.capacity()) }
+    }
+}
+
+/// 64-bit FNV-1a, enough to catch torn or stale bytes
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Heap memory standing in for a mapping, 8-byte aligned
+    struct Memory(Vec<u64>);
+
+    impl Memory {
+        fn new(capacity: usize) -> Self {
+            Memory(vec![0; segment_size(capacity).div_ceil(8)])
+        }
+
+        fn region(&self) -> Region<'_> {
+            unsafe { Region::new(self.0.as_ptr() as *mut u8, self.0.len() * 8) }
+        }
+    }
+
+    #[test]
+    fn test_round_trip_and_update() {
+        let memory = Memory::new(64);
+        let region = memory.region();
+
+        assert_eq!(region.read().unwrap(), None);
+
+        region.write(b"first").unwrap();
+        region.write(b"second").unwrap();
+        assert_eq!(region.read().unwrap().unwrap(), b"second");
+
+        region
+            .update(|current| {
+                let mut next = current.unwrap();
+                next.extend_from_slice(b"+more");
+                Ok(next)
+            })
+            .unwrap();
+        assert_eq!(region.read().unwrap().unwrap(), b"second+more");
+
+        region.write(b"").unwrap();
+        assert_eq!(region.read().unwrap(), None);
+    }
+
+    #[test]
+    fn test_rejects_oversized_payload() {
+        let memory = Memory::new(8);
+        let region = memory.region();
+        assert!(region.write(&[1; 9]).is_err());
+    }
+
+    #[test]
+    fn test_detects_corruption() {
+        let mut memory = Memory::new(64);
+        memory.region().write(b"payload").unwrap();
+
+        // Flip a byte of the active buffer behind the writer's back
+        let active = memory
+            .region()
+            .u32_at(ACTIVE_OFFSET)
+            .load(Ordering::Relaxed) as usize;
+        let offset = HEADER_SIZE + active * memory.region().capacity();
+        let bytes = unsafe {
+            std::slice::from_raw_parts_mut(memory.0.as_mut_ptr() as *mut u8, memory.0.len() * 8)
+        };
+        bytes[offset] ^= 0xff;
+
+        assert!(memory.region().read().is_err());
+    }
+
+    #[test]
+    fn test_takes_over_from_dead_writer() {
+        let memory = Memory::new(64);
+        let region = memory.region();
+        region.write(b"before crash").unwrap();
+
+        // A writer that never unlocks
+        region.lock();
+        assert_eq!(region.read().unwrap().unwrap(), b"before crash");
+
+        region.write(b"after crash").unwrap();
+        assert_eq!(region.read().unwrap().unwrap(), b"after crash");
+    }
+
+    #[test]
+    fn test_concurrent_threads_never_see_torn_payloads() {
+        let memory = Arc::new(Memory::new(4096));
+
+        let writers: Vec<_> = (0..2)
+            .map(|writer| {
+                let memory = Arc::clone(&memory);
+                std::thread::spawn(move || {
+                    for i in 0..2_000u32 {
+                        let byte = (writer * 100 + i % 100) as u8;
+                        let len = 1 + (i as usize * 37) % 4096;
+                        memory.region().write(&vec![byte; len]).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let memory = Arc::clone(&memory);
+                std::thread::spawn(move || {
+                    for _ in 0..5_000 {
+                        if let Some(payload) = memory.region().read().unwrap() {
+                            assert!(payload.iter().all(|b| *b == payload[0]));
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for handle in writers.into_iter().chain(readers) {
+            handle.join().unwrap();
+        }
+    }
+}
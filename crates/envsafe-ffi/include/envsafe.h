@@ -0,0 +1,80 @@
+/* Reader for EnvSafe variables cached in shared memory. Generated by cbindgen from src/lib.rs, do not edit. */
+
+#ifndef ENVSAFE_H
+#define ENVSAFE_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Handle on one cached environment
+typedef struct EnvsafeClient EnvsafeClient;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Open the environment in segment `segment`, or the one named by `ENVSAFE_SHM_SEGMENT`
+// (set by `envsafe run`) when `segment` is NULL. Release with `envsafe_close`.
+//
+// # Safety
+//
+// `segment` must be NULL or a NUL-terminated string.
+struct EnvsafeClient *envsafe_open(const char *segment);
+
+// Open an environment of the current user by workspace, project and environment name
+//
+// # Safety
+//
+// All arguments must be NUL-terminated strings.
+struct EnvsafeClient *envsafe_open_environment(const char *workspace,
+                                               const char *project,
+                                               const char *environment);
+
+// # Safety
+//
+// `client` must be NULL or a handle from `envsafe_open*` not closed yet.
+void envsafe_close(struct EnvsafeClient *client);
+
+// Value of `key`, or NULL if it is not set (`envsafe_last_error` is then NULL) or on error.
+// Release with `envsafe_free`.
+//
+// # Safety
+//
+// `client` must be a live handle and `key` a NUL-terminated string.
+char *envsafe_get(const struct EnvsafeClient *client, const char *key);
+
+// Version of the cached snapshot; 0 if nothing is cached or on error. Poll it to detect
+// hot reloads.
+//
+// # Safety
+//
+// `client` must be a live handle.
+uint64_t envsafe_version(const struct EnvsafeClient *client);
+
+// The whole snapshot as JSON (`version`, `project_id`, `environment`, `variables`,
+// `last_updated`), or NULL if nothing is cached or on error. Release with `envsafe_free`.
+//
+// # Safety
+//
+// `client` must be a live handle.
+char *envsafe_snapshot_json(const struct EnvsafeClient *client);
+
+// Release a string returned by this library
+//
+// # Safety
+//
+// `s` must be NULL or a string returned by this library, not freed yet.
+void envsafe_free(char *s);
+
+// Description of the last error on this thread, or NULL. Valid until the next call into
+// the library on the same thread; do not free.
+const char *envsafe_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ENVSAFE_H */
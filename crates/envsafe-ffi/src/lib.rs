@@ -0,0 +1,217 @@
+//! C ABI over [`envsafe_client`], for services in languages that can load a shared library
+//! (Python `ctypes`, Node `ffi-napi`, Go `cgo`, ...). The declarations are in
+//! `include/envsafe.h`.
+//!
+//! Strings returned by the library are owned by the caller and released with
+//! [`envsafe_free`]. Functions returning `NULL` or `0` on failure leave a description of the
+//! error for [`envsafe_last_error`].
+
+use envsafe_client::Client;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+/// Handle on one cached environment
+pub struct EnvsafeClient(Client);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(message: impl ToString) {
+    let message = CString::new(message.to_string().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+fn clear_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+}
+
+/// `None` for a NULL pointer or invalid UTF-8
+unsafe fn to_str<'a>(s: *const c_char, what: &str) -> Option<&'a str> {
+    if s.is_null() {
+        set_error(format!("{} is NULL", what));
+        return None;
+    }
+    match CStr::from_ptr(s).to_str() {
+        Ok(s) => Some(s),
+        Err(_) => {
+            set_error(format!("{} is not valid UTF-8", what));
+            None
+        }
+    }
+}
+
+fn into_c_string(s: String) -> *mut c_char {
+    match CString::new(s) {
+        Ok(s) => s.into_raw(),
+        Err(_) => {
+            set_error("Value contains a NUL byte");
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Open the environment in segment `segment`, or the one named by `ENVSAFE_SHM_SEGMENT`
+/// (set by `envsafe run`) when `segment` is NULL. Release with `envsafe_close`.
+///
+/// # Safety
+///
+/// `segment` must be NULL or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn envsafe_open(segment: *const c_char) -> *mut EnvsafeClient {
+    clear_error();
+    let client = if segment.is_null() {
+        match Client::from_env() {
+            Ok(client) => client,
+            Err(e) => {
+                set_error(e);
+                return ptr::null_mut();
+            }
+        }
+    } else {
+        match to_str(segment, "segment") {
+            Some(segment) => Client::for_segment(segment),
+            None => return ptr::null_mut(),
+        }
+    };
+    Box::into_raw(Box::new(EnvsafeClient(client)))
+}
+
+/// Open an environment of the current user by workspace, project and environment name
+///
+/// # Safety
+///
+/// All arguments must be NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn envsafe_open_environment(
+    workspace: *const c_char,
+    project: *const c_char,
+    environment: *const c_char,
+) -> *mut EnvsafeClient {
+    clear_error();
+    let (Some(workspace), Some(project), Some(environment)) = (
+        to_str(workspace, "workspace"),
+        to_str(project, "project"),
+        to_str(environment, "environment"),
+    ) else {
+        return ptr::null_mut();
+    };
+    Box::into_raw(Box::new(EnvsafeClient(Client::open(
+        workspace,
+        project,
+        environment,
+    ))))
+}
+
+/// # Safety
+///
+/// `client` must be NULL or a handle from `envsafe_open*` not closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn envsafe_close(client: *mut EnvsafeClient) {
+    if !client.is_null() {
+        drop(Box::from_raw(client));
+    }
+}
+
+/// Value of `key`, or NULL if it is not set (`envsafe_last_error` is then NULL) or on error.
+/// Release with `envsafe_free`.
+///
+/// # Safety
+///
+/// `client` must be a live handle and `key` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn envsafe_get(
+    client: *const EnvsafeClient,
+    key: *const c_char,
+) -> *mut c_char {
+    clear_error();
+    let Some(client) = client.as_ref() else {
+        set_error("client is NULL");
+        return ptr::null_mut();
+    };
+    let Some(key) = to_str(key, "key") else {
+        return ptr::null_mut();
+    };
+
+    match client.0.get(key) {
+        Ok(Some(value)) => into_c_string(value),
+        Ok(None) => ptr::null_mut(),
+        Err(e) => {
+            set_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Version of the cached snapshot; 0 if nothing is cached or on error. Poll it to detect
+/// hot reloads.
+///
+/// # Safety
+///
+/// `client` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn envsafe_version(client: *const EnvsafeClient) -> u64 {
+    clear_error();
+    let Some(client) = client.as_ref() else {
+        set_error("client is NULL");
+        return 0;
+    };
+
+    client.0.version().unwrap_or_else(|e| {
+        set_error(e);
+        0
+    })
+}
+
+/// The whole snapshot as JSON (`version`, `project_id`, `environment`, `variables`,
+/// `last_updated`), or NULL if nothing is cached or on error. Release with `envsafe_free`.
+///
+/// # Safety
+///
+/// `client` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn envsafe_snapshot_json(client: *const EnvsafeClient) -> *mut c_char {
+    clear_error();
+    let Some(client) = client.as_ref() else {
+        set_error("client is NULL");
+        return ptr::null_mut();
+    };
+
+    match client.0.snapshot() {
+        Ok(Some(snapshot)) => match serde_json::to_string(&snapshot) {
+            Ok(json) => into_c_string(json),
+            Err(e) => {
+                set_error(e);
+                ptr::null_mut()
+            }
+        },
+        Ok(None) => ptr::null_mut(),
+        Err(e) => {
+            set_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Release a string returned by this library
+///
+/// # Safety
+///
+/// `s` must be NULL or a string returned by this library, not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn envsafe_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Description of the last error on this thread, or NULL. Valid until the next call into
+/// the library on the same thread; do not free.
+#[no_mangle]
+pub extern "C" fn envsafe_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| match last.borrow().as_ref() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
@@ -0,0 +1,140 @@
+//! Loads the built shared library the way a Python or Node service would, and calls it
+//! through plain C function pointers.
+
+use envsafe_client::{codec, segment, SessionKey, SharedEnvData};
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::path::PathBuf;
+
+type Open = unsafe extern "C" fn(*const c_char) -> *mut c_void;
+type Close = unsafe extern "C" fn(*mut c_void);
+type Get = unsafe extern "C" fn(*const c_void, *const c_char) -> *mut c_char;
+type Version = unsafe extern "C" fn(*const c_void) -> u64;
+type SnapshotJson = unsafe extern "C" fn(*const c_void) -> *mut c_char;
+type Free = unsafe extern "C" fn(*mut c_char);
+type LastError = unsafe extern "C" fn() -> *const c_char;
+
+struct Library(*mut c_void);
+
+impl Library {
+    fn open() -> Self {
+        let name = format!(
+            "{}envsafe_ffi{}",
+            std::env::consts::DLL_PREFIX,
+            std::env::consts::DLL_SUFFIX
+        );
+        // Integration tests run from target/<profile>/deps, where cargo also puts the cdylib
+        let deps = std::env::current_exe()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf();
+        let path: PathBuf = [deps.join(&name), deps.parent().unwrap().join(&name)]
+            .into_iter()
+            .find(|path| path.exists())
+            .unwrap_or_else(|| panic!("{} was not built", name));
+
+        let path = CString::new(path.to_str().unwrap()).unwrap();
+        let handle = unsafe { libc::dlopen(path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        assert!(!handle.is_null(), "dlopen failed");
+        Self(handle)
+    }
+
+    /// # Safety
+    ///
+    /// `T` must be the function pointer type of `name`.
+    unsafe fn symbol<T: Copy>(&self, name: &str) -> T {
+        let name = CString::new(name).unwrap();
+        let symbol = libc::dlsym(self.0, name.as_ptr());
+        assert!(!symbol.is_null(), "{:?} is not exported", name);
+        std::mem::transmute_copy(&symbol)
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        unsafe { libc::dlclose(self.0) };
+    }
+}
+
+/// Stand-in for `envsafe pull`
+fn publish(os_id: &str, version: u64) {
+    let data = SharedEnvData {
+        version,
+        project_id: "ffi".to_string(),
+        environment: "development".to_string(),
+        variables: [
+            ("DATABASE_URL".to_string(), "postgres://db/app".to_string()),
+            ("GREETING".to_string(), "héllo".to_string()),
+        ]
+        .into_iter()
+        .collect(),
+        last_updated: chrono::Utc::now().to_rfc3339(),
+        etag: None,
+    };
+    let sealed = SessionKey::current()
+        .unwrap()
+        .seal(&codec::encode(&data), os_id)
+        .unwrap();
+    let shmem = segment::create_or_open(os_id, 4096).unwrap();
+    segment::region(&shmem).write(&sealed).unwrap();
+}
+
+unsafe fn take_string(free: Free, s: *mut c_char) -> Option<String> {
+    if s.is_null() {
+        return None;
+    }
+    let value = CStr::from_ptr(s).to_str().unwrap().to_string();
+    free(s);
+    Some(value)
+}
+
+#[test]
+fn test_reads_cache_through_dlopen() {
+    let os_id = format!("envsafe_ffi_test_{}", std::process::id());
+    let library = Library::open();
+
+    unsafe {
+        let open: Open = library.symbol("envsafe_open");
+        let close: Close = library.symbol("envsafe_close");
+        let get: Get = library.symbol("envsafe_get");
+        let version: Version = library.symbol("envsafe_version");
+        let snapshot_json: SnapshotJson = library.symbol("envsafe_snapshot_json");
+        let free: Free = library.symbol("envsafe_free");
+        let last_error: LastError = library.symbol("envsafe_last_error");
+
+        let segment = CString::new(os_id.as_str()).unwrap();
+        let client = open(segment.as_ptr());
+        assert!(!client.is_null());
+
+        // Nothing cached yet
+        assert_eq!(version(client), 0);
+        assert!(snapshot_json(client).is_null());
+
+        publish(&os_id, 3);
+        assert_eq!(version(client), 3);
+
+        let key = CString::new("GREETING").unwrap();
+        assert_eq!(
+            take_string(free, get(client, key.as_ptr())).unwrap(),
+            "héllo"
+        );
+
+        let missing = CString::new("MISSING").unwrap();
+        assert!(get(client, missing.as_ptr()).is_null());
+        assert!(last_error().is_null());
+
+        let json = take_string(free, snapshot_json(client)).unwrap();
+        let snapshot: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(snapshot["version"], 3);
+        assert_eq!(snapshot["variables"]["DATABASE_URL"], "postgres://db/app");
+
+        // Errors are reported instead of crashing the host
+        assert!(get(client, std::ptr::null()).is_null());
+        assert!(!last_error().is_null());
+        assert_eq!(version(std::ptr::null()), 0);
+
+        close(client);
+    }
+
+    segment::unlink(&os_id).unwrap();
+}
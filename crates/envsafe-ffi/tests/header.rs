@@ -0,0 +1,24 @@
+use std::path::Path;
+
+/// `include/envsafe.h` must match the exported functions.
+/// Regenerate it with `ENVSAFE_UPDATE_HEADER=1 cargo test -p envsafe-ffi --test header`.
+#[test]
+fn test_header_is_up_to_date() {
+    let crate_dir = env!("CARGO_MANIFEST_DIR");
+    let mut generated = Vec::new();
+    cbindgen::generate(crate_dir)
+        .expect("Failed to generate the C header")
+        .write(&mut generated);
+    let generated = String::from_utf8(generated).unwrap();
+
+    let path = Path::new(crate_dir).join("include").join("envsafe.h");
+    if std::env::var_os("ENVSAFE_UPDATE_HEADER").is_some() {
+        std::fs::write(&path, &generated).unwrap();
+    }
+
+    let committed = std::fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "include/envsafe.h is out of date. Regenerate it with: ENVSAFE_UPDATE_HEADER=1 cargo test -p envsafe-ffi --test header"
+    );
+}
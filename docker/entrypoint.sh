@@ -10,27 +10,32 @@ if [ -z "$ENVSAFE_TOKEN" ]; then
 fi
 
 # Login with token
-echo "$ENVSAFE_TOKEN" | envsafe login -t
-
-# Check if project is configured
-if [ -n "$ENVSAFE_PROJECT_ID" ] && [ -n "$ENVSAFE_WORKSPACE_ID" ]; then
-    # Create .envsafe config file
-    cat > .envsafe <<EOF
-{
-  "workspace_id": "$ENVSAFE_WORKSPACE_ID",
-  "project_id": "$ENVSAFE_PROJECT_ID",
-  "project_name": "$ENVSAFE_PROJECT_NAME"
-}
-EOF
+envsafe login --token "$ENVSAFE_TOKEN"
+
+# Link the project (slugs, names or IDs are accepted)
+ENVSAFE_WORKSPACE="${ENVSAFE_WORKSPACE:-$ENVSAFE_WORKSPACE_ID}"
+ENVSAFE_PROJECT="${ENVSAFE_PROJECT:-$ENVSAFE_PROJECT_ID}"
+if [ -n "$ENVSAFE_PROJECT" ]; then
+    INIT_ARGS=(--project "$ENVSAFE_PROJECT")
+    if [ -n "$ENVSAFE_WORKSPACE" ]; then
+        INIT_ARGS+=(--workspace "$ENVSAFE_WORKSPACE")
+    fi
+    if [ -n "$ENVSAFE_ENV" ]; then
+        INIT_ARGS+=(--env "$ENVSAFE_ENV")
+    fi
+    if [ "$ENVSAFE_CREATE_PROJECT" = "true" ]; then
+        INIT_ARGS+=(--create)
+    fi
+    envsafe init "${INIT_ARGS[@]}"
     echo "✓ Project configured"
 fi
 
 # Pull initial variables
 echo "📥 Pulling environment variables..."
 if [ -n "$ENVSAFE_ENV" ]; then
-    envsafe pull --env "$ENVSAFE_ENV" --output .env
+    envsafe pull --env "$ENVSAFE_ENV" --file .env
 else
-    envsafe pull --prod --output .env
+    envsafe pull --prod --file .env
 fi
 
 # Start watch mode if enabled
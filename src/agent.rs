@@ -0,0 +1,650 @@
+//! `envsafe agent`: a per-user daemon that keeps environments hot reloaded in shared memory
+//! and serves them over a Unix socket, so `envsafe run` does not have to call the API.
+//!
+//! The protocol is newline-delimited JSON: each line is a [`Request`], answered by one
+//! [`Response`] line, or by a stream of them for `subscribe`.
+
+use crate::api::ApiClient;
+use crate::config::Config;
+use crate::status;
+use crate::storage::{self, SegmentKey, SharedEnvData};
+use crate::watcher::EnvWatcher;
+use anyhow::Result;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::cell::{OnceCell, RefCell};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{watch, Notify};
+
+/// Overrides the socket location
+pub const SOCKET_ENV_VAR: &str = "ENVSAFE_AGENT_SOCKET";
+const SOCKET_FILE: &str = "agent.sock";
+
+/// How long a request waits for the first snapshot of an environment it started watching
+const FIRST_SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Request {
+    /// Variables of an environment, or only `key`. Starts watching the environment if needed.
+    Get {
+        /// Default: the workspace the agent was started in
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        workspace: Option<String>,
+        project: String,
+        environment: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        key: Option<String>,
+    },
+    /// Environments being watched
+    List,
+    /// Snapshot of an environment now and after every change, until the connection closes
+    Subscribe {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        workspace: Option<String>,
+        project: String,
+        environment: String,
+    },
+    /// Stop the agent. Like every request, only accepted from processes of the user running
+    /// the agent, who could as well kill it.
+    Shutdown,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<SharedEnvData>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environments: Option<Vec<WatchedEnvironment>>,
+}
+
+impl Response {
+    fn success() -> Self {
+        Self {
+            ok: true,
+            ..Default::default()
+        }
+    }
+
+    fn failure(error: impl ToString) -> Self {
+        Self {
+            ok: false,
+            error: Some(error.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// What `list` reports about an environment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchedEnvironment {
+    pub workspace: String,
+    pub project: String,
+    pub environment: String,
+    /// 0 until the first snapshot is fetched
+    pub version: u64,
+    pub variables: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_updated: Option<String>,
+}
+
+/// `$ENVSAFE_AGENT_SOCKET`, otherwise `agent.sock` in the user's runtime directory
+pub fn socket_path() -> Result<PathBuf> {
+    if let Ok(path) = std::env::var(SOCKET_ENV_VAR) {
+        if !path.is_empty() {
+            return Ok(PathBuf::from(path));
+        }
+    }
+    let dir = match dirs::runtime_dir() {
+        Some(dir) => dir.join("envsafe-cli"),
+        None => Config::config_dir()?,
+    };
+    Ok(dir.join(SOCKET_FILE))
+}
+
+/// Listen on `path`, only reachable by the current user. Replaces a socket left behind by
+/// an agent that did not exit cleanly, but not one that is still answering.
+pub fn bind(path: &Path) -> Result<UnixListener> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        if !dir.exists() {
+            std::fs::DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(dir)?;
+        }
+    }
+
+    if path.exists() {
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            anyhow::bail!("An agent is already running on {}", path.display());
+        }
+        std::fs::remove_file(path)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+#[derive(Clone)]
+struct Watched {
+    key: SegmentKey,
+    updates: watch::Receiver<Option<SharedEnvData>>,
+    /// Why the environment could not be fetched. The watcher is gone by then, so `updates`
+    /// is closed.
+    failure: Rc<OnceCell<String>>,
+}
+
+/// State shared by the connections. `EnvWatcher` holds a shared memory mapping, which is not
+/// `Send`, so everything runs on one thread.
+struct Agent {
+    config: Config,
+    environments: Rc<RefCell<HashMap<String, Watched>>>,
+    shutdown: Notify,
+}
+
+/// Serve on `listener` until a `shutdown` request or Ctrl+C, watching `targets` from the start
+pub async fn serve(config: Config, listener: UnixListener, targets: Vec<SegmentKey>) -> Result<()> {
+    let agent = Rc::new(Agent {
+        config,
+        environments: Rc::new(RefCell::new(HashMap::new())),
+        shutdown: Notify::new(),
+    });
+
+    let local = tokio::task::LocalSet::new();
+    local
+        .run_until(async move {
+            for key in &targets {
+                agent.watch(key)?;
+            }
+
+            loop {
+                tokio::select! {
+                    accepted = listener.accept() => {
+                        let (stream, _) = accepted?;
+                        let agent = Rc::clone(&agent);
+                        tokio::task::spawn_local(async move {
+                            if let Err(e) = agent.handle(stream).await {
+                                eprintln!("{}", format!("Agent connection error: {}", e).red());
+                            }
+                        });
+                    }
+                    _ = agent.shutdown.notified() => break,
+                    _ = tokio::signal::ctrl_c() => break,
+                }
+            }
+            Ok(())
+        })
+        .await
+}
+
+impl Agent {
+    fn key(
+        &self,
+        workspace: Option<String>,
+        project: &str,
+        environment: &str,
+    ) -> Result<SegmentKey> {
+        match workspace {
+            Some(workspace) => Ok(SegmentKey::new(&workspace, project, environment)),
+            None => storage::current_key(&self.config, project, environment),
+        }
+    }
+
+    /// The environment of `key`, starting a watcher for it the first time. An environment
+    /// whose first fetch fails is dropped, so the next request tries again from scratch.
+    fn watch(&self, key: &SegmentKey) -> Result<Watched> {
+        let segment = key.os_id();
+        if let Some(watched) = self.environments.borrow().get(&segment) {
+            return Ok(watched.clone());
+        }
+
+        let (sender, updates) = watch::channel(None);
+        let mut watcher = EnvWatcher::new(
+            ApiClient::from_config(&self.config),
+            self.config.clone(),
+            key,
+        )?
+        .without_file()
+        .with_updates(sender);
+
+        let watched = Watched {
+            key: key.clone(),
+            updates,
+            failure: Rc::new(OnceCell::new()),
+        };
+
+        let (project, environment) = (key.project.clone(), key.environment.clone());
+        let failure = Rc::clone(&watched.failure);
+        let environments = Rc::clone(&self.environments);
+        let entry = segment.clone();
+        tokio::task::spawn_local(async move {
+            if let Err(e) = watcher.keep_watching(&project, &environment).await {
+                eprintln!(
+                    "{}",
+                    format!("Could not watch {}/{}: {}", project, environment, e).red()
+                );
+                let _ = failure.set(e.to_string());
+                environments.borrow_mut().remove(&entry);
+            }
+        });
+
+        status!(
+            "{}",
+            format!("  Watching {}/{}", key.project, key.environment).bright_black()
+        );
+        self.environments
+            .borrow_mut()
+            .insert(segment, watched.clone());
+        Ok(watched)
+    }
+
+    fn list(&self) -> Vec<WatchedEnvironment> {
+        let mut environments: Vec<WatchedEnvironment> = self
+            .environments
+            .borrow()
+            .values()
+            .map(|watched| {
+                let snapshot = watched.updates.borrow();
+                WatchedEnvironment {
+                    workspace: watched.key.workspace.clone(),
+                    project: watched.key.project.clone(),
+                    environment: watched.key.environment.clone(),
+                    version: snapshot.as_ref().map_or(0, |data| data.version),
+                    variables: snapshot.as_ref().map_or(0, |data| data.variables.len()),
+                    last_updated: snapshot.as_ref().map(|data| data.last_updated.clone()),
+                }
+            })
+            .collect();
+        environments
+            .sort_by(|a, b| (&a.project, &a.environment).cmp(&(&b.project, &b.environment)));
+        environments
+    }
+
+    async fn handle(&self, stream: UnixStream) -> Result<()> {
+        let peer = stream.peer_cred()?;
+        if peer.uid() != unsafe { libc::geteuid() } {
+            eprintln!(
+                "{}",
+                format!("Refused connection from uid {}", peer.uid()).yellow()
+            );
+            return Ok(());
+        }
+
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let request = match serde_json::from_str::<Request>(&line) {
+                Ok(request) => request,
+                Err(e) => {
+                    send(
+                        &mut write,
+                        &Response::failure(format!("Invalid request: {}", e)),
+                    )
+                    .await?;
+                    continue;
+                }
+            };
+
+            match request {
+                Request::Get {
+                    workspace,
+                    project,
+                    environment,
+                    key,
+                } => {
+                    let response = match self.get(workspace, &project, &environment).await {
+                        Ok(data) => match key {
+                            Some(key) => Response {
+                                value: data.variables.get(&key).cloned(),
+                                ..Response::success()
+                            },
+                            None => Response {
+                                data: Some(data),
+                                ..Response::success()
+                            },
+                        },
+                        Err(e) => Response::failure(e),
+                    };
+                    send(&mut write, &response).await?;
+                }
+                Request::List => {
+                    let response = Response {
+                        environments: Some(self.list()),
+                        ..Response::success()
+                    };
+                    send(&mut write, &response).await?;
+                }
+                Request::Subscribe {
+                    workspace,
+                    project,
+                    environment,
+                } => {
+                    let watched = match self
+                        .key(workspace, &project, &environment)
+                        .and_then(|key| self.watch(&key))
+                    {
+                        Ok(watched) => watched,
+                        Err(e) => {
+                            send(&mut write, &Response::failure(e)).await?;
+                            continue;
+                        }
+                    };
+                    // Takes over the connection until the client closes it
+                    return subscribe(watched, &mut lines, &mut write).await;
+                }
+                Request::Shutdown => {
+                    send(&mut write, &Response::success()).await?;
+                    self.shutdown.notify_one();
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn get(
+        &self,
+        workspace: Option<String>,
+        project: &str,
+        environment: &str,
+    ) -> Result<SharedEnvData> {
+        let Watched {
+            mut updates,
+            failure,
+            ..
+        } = self.watch(&self.key(workspace, project, environment)?)?;
+        let waited = tokio::time::timeout(
+            FIRST_SNAPSHOT_TIMEOUT,
+            updates.wait_for(|snapshot| snapshot.is_some()),
+        )
+        .await;
+
+        match waited {
+            Ok(Ok(snapshot)) => snapshot
+                .clone()
+                .ok_or_else(|| first_fetch_error(project, environment, &failure)),
+            // The watcher gave up before the first snapshot
+            Ok(Err(_)) => Err(first_fetch_error(project, environment, &failure)),
+            Err(_) => anyhow::bail!(
+                "Timed out fetching {}/{} from EnvSafe",
+                project,
+                environment
+            ),
+        }
+    }
+}
+
+fn first_fetch_error(
+    project: &str,
+    environment: &str,
+    failure: &OnceCell<String>,
+) -> anyhow::Error {
+    anyhow::anyhow!(
+        "Could not fetch {}/{} from EnvSafe: {}",
+        project,
+        environment,
+        failure.get().map_or("the watcher stopped", |e| e.as_str())
+    )
+}
+
+async fn subscribe(
+    watched: Watched,
+    lines: &mut Lines<BufReader<OwnedReadHalf>>,
+    write: &mut OwnedWriteHalf,
+) -> Result<()> {
+    let Watched {
+        key,
+        mut updates,
+        failure,
+    } = watched;
+    loop {
+        let snapshot = updates.borrow_and_update().clone();
+        if let Some(data) = snapshot {
+            let response = Response {
+                data: Some(data),
+                ..Response::success()
+            };
+            if send(write, &response).await.is_err() {
+                return Ok(());
+            }
+        }
+
+        tokio::select! {
+            changed = updates.changed() => {
+                if changed.is_err() {
+                    let error = first_fetch_error(&key.project, &key.environment, &failure);
+                    let _ = send(write, &Response::failure(error)).await;
+                    return Ok(());
+                }
+            }
+            line = lines.next_line() => {
+                // Anything but a closed connection is ignored while subscribed
+                if !matches!(line, Ok(Some(_))) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+async fn send(write: &mut OwnedWriteHalf, response: &Response) -> Result<()> {
+    let mut line = serde_json::to_string(response)?;
+    line.push('\n');
+    write.write_all(line.as_bytes()).await?;
+    Ok(())
+}
+
+/// Connection to a running agent
+pub struct AgentClient {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    write: OwnedWriteHalf,
+}
+
+impl AgentClient {
+    /// Connect to the agent listening on `path`, `None` if none is
+    pub async fn connect(path: &Path) -> Result<Option<Self>> {
+        match UnixStream::connect(path).await {
+            Ok(stream) => {
+                let (read, write) = stream.into_split();
+                Ok(Some(Self {
+                    lines: BufReader::new(read).lines(),
+                    write,
+                }))
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => {
+                Ok(None)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Send `request` and wait for its response, failing if the agent reports an error
+    pub async fn request(&mut self, request: &Request) -> Result<Response> {
+        let mut line = serde_json::to_string(request)?;
+        line.push('\n');
+        self.write.write_all(line.as_bytes()).await?;
+
+        let response = self
+            .next()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("The agent closed the connection"))?;
+        match response.error {
+            Some(error) if !response.ok => anyhow::bail!("Agent: {}", error),
+            _ => Ok(response),
+        }
+    }
+
+    /// Next response, e.g. the next snapshot after `subscribe`; `None` once disconnected
+    pub async fn next(&mut self) -> Result<Option<Response>> {
+        match self.lines.next_line().await? {
+            Some(line) => Ok(Some(serde_json::from_str(&line)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Variables of `key` from the running agent, `None` if no agent is running
+pub async fn fetch(key: &SegmentKey) -> Result<Option<SharedEnvData>> {
+    let Some(mut client) = AgentClient::connect(&socket_path()?).await? else {
+        return Ok(None);
+    };
+    let response = client
+        .request(&Request::Get {
+            workspace: Some(key.workspace.clone()),
+            project: key.project.clone(),
+            environment: key.environment.clone(),
+            key: None,
+        })
+        .await?;
+    Ok(response.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_wire_format() {
+        let request: Request = serde_json::from_str(
+            r#"{"op":"get","project":"api","environment":"production","key":"PORT"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            request,
+            Request::Get {
+                workspace: None,
+                project: "api".to_string(),
+                environment: "production".to_string(),
+                key: Some("PORT".to_string()),
+            }
+        );
+
+        assert_eq!(
+            serde_json::to_string(&Request::List).unwrap(),
+            r#"{"op":"list"}"#
+        );
+        assert!(serde_json::from_str::<Request>(r#"{"op":"delete"}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_socket_is_private_and_not_stolen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent").join(SOCKET_FILE);
+
+        let listener = bind(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let dir_mode = std::fs::metadata(path.parent().unwrap())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(dir_mode & 0o777, 0o700);
+
+        // A live agent keeps its socket
+        assert!(bind(&path).is_err());
+
+        // A stale socket is replaced
+        drop(listener);
+        assert!(bind(&path).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_list_and_shutdown_over_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SOCKET_FILE);
+        let listener = bind(&path).unwrap();
+
+        let config = Config::default();
+        let server = tokio::spawn(async move {
+            // `serve` is not Send: give it a thread of its own
+            tokio::task::spawn_blocking(move || {
+                tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap()
+                    .block_on(serve(config, listener, Vec::new()))
+            })
+            .await
+            .unwrap()
+        });
+
+        let mut client = AgentClient::connect(&path).await.unwrap().unwrap();
+        let response = client.request(&Request::List).await.unwrap();
+        assert_eq!(response.environments, Some(Vec::new()));
+
+        client.request(&Request::Shutdown).await.unwrap();
+        server.await.unwrap().unwrap();
+
+        // The socket file is left behind, but nothing answers on it any more
+        assert!(AgentClient::connect(&path).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_first_fetch_failure_is_reported_and_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SOCKET_FILE);
+        let listener = bind(&path).unwrap();
+
+        // Nothing listens there: the first connection fails at once
+        let config = Config {
+            token: Some("test-token".to_string()),
+            ws_url: Some("ws://127.0.0.1:1".to_string()),
+            ..Config::default()
+        };
+        let server = tokio::spawn(async move {
+            tokio::task::spawn_blocking(move || {
+                tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap()
+                    .block_on(serve(config, listener, Vec::new()))
+            })
+            .await
+            .unwrap()
+        });
+
+        let key = SegmentKey::new(
+            "agent-test",
+            "api",
+            &format!("unreachable-{}", std::process::id()),
+        );
+        let get = Request::Get {
+            workspace: Some(key.workspace.clone()),
+            project: key.project.clone(),
+            environment: key.environment.clone(),
+            key: None,
+        };
+
+        let mut client = AgentClient::connect(&path).await.unwrap().unwrap();
+        let started = std::time::Instant::now();
+        let error = client.request(&get).await.unwrap_err().to_string();
+        assert!(error.contains("Could not fetch api/"), "{}", error);
+        assert!(started.elapsed() < FIRST_SNAPSHOT_TIMEOUT);
+
+        // Not kept around to be retried forever
+        let response = client.request(&Request::List).await.unwrap();
+        assert_eq!(response.environments, Some(Vec::new()));
+
+        client.request(&Request::Shutdown).await.unwrap();
+        server.await.unwrap().unwrap();
+        storage::EnvStorage::remove(&key).unwrap();
+    }
+}
@@ -52,6 +52,56 @@ pub struct Project {
     pub environments: Option<Vec<String>>,
     #[serde(rename = "updatedAt", default)]
     pub updated_at: Option<String>,
+    #[serde(default)]
+    pub archived: bool,
+}
+
+/// Fields to change on a project; `None` leaves the field untouched
+#[derive(Debug, Default, Serialize)]
+pub struct ProjectUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MembersResponse {
+    pub members: Vec<Member>,
+    #[serde(default)]
+    pub count: usize,
+}
+
+/// A user's membership in a workspace
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Member {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub email: String,
+    pub role: String,
+    #[serde(rename = "joinedAt", default)]
+    pub joined_at: Option<String>,
+}
+
+impl Workspace {
+    /// Whether `query` names this workspace by ID, slug or (case-insensitive) name
+    pub fn matches(&self, query: &str) -> bool {
+        self.id == query
+            || self.slug.as_deref() == Some(query)
+            || self.name.eq_ignore_ascii_case(query)
+    }
+}
+
+impl Project {
+    /// Whether `query` names this project by ID, slug or (case-insensitive) name
+    pub fn matches(&self, query: &str) -> bool {
+        self.id == query
+            || self.slug.as_deref() == Some(query)
+            || self.name.eq_ignore_ascii_case(query)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -62,11 +112,157 @@ pub struct EnvironmentResponse {
     pub count: usize,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnvironmentsResponse {
+    pub environments: Vec<EnvironmentInfo>,
+    #[serde(default)]
+    pub count: usize,
+}
+
+/// Environment metadata, without its variables
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentInfo {
+    pub name: String,
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(rename = "variableCount", default)]
+    pub variable_count: Option<usize>,
+    #[serde(rename = "updatedAt", default)]
+    pub updated_at: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Environment {
     pub id: String,
     pub name: String,
     pub variables: Vec<EnvVariable>,
+    /// Server version (ETag) used for optimistic concurrency on updates
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryResponse {
+    pub entries: Vec<HistoryEntry>,
+    #[serde(default)]
+    pub count: usize,
+}
+
+/// One change to one variable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub version: u64,
+    pub key: String,
+    /// "created", "updated" or "deleted"
+    pub action: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+/// Full state of an environment at a given version
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotResponse {
+    pub version: u64,
+    #[serde(rename = "createdAt", default)]
+    pub created_at: Option<String>,
+    pub variables: std::collections::HashMap<String, String>,
+}
+
+/// Point in time to restore an environment to
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotPoint {
+    Version(u64),
+    Timestamp(chrono::DateTime<chrono::Utc>),
+}
+
+impl std::str::FromStr for SnapshotPoint {
+    type Err = anyhow::Error;
+
+    /// Accepts a version number, an RFC 3339 timestamp or a `YYYY-MM-DD` date (midnight UTC)
+    fn from_str(s: &str) -> Result<Self> {
+        if let Ok(version) = s.parse::<u64>() {
+            return Ok(SnapshotPoint::Version(version));
+        }
+
+        if let Ok(at) = chrono::DateTime::parse_from_rfc3339(s) {
+            return Ok(SnapshotPoint::Timestamp(at.with_timezone(&chrono::Utc)));
+        }
+
+        if let Ok(date) = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+            return Ok(SnapshotPoint::Timestamp(midnight.and_utc()));
+        }
+
+        anyhow::bail!(
+            "Invalid point in time '{}'. Use a version number, an RFC 3339 timestamp or YYYY-MM-DD",
+            s
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditResponse {
+    pub entries: Vec<AuditEntry>,
+    #[serde(rename = "nextPage", default)]
+    pub next_page: Option<u32>,
+}
+
+/// Who did what to which secret, and when
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: String,
+    /// e.g. "read", "create", "update", "delete"
+    pub action: String,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub environment: Option<String>,
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(rename = "ipAddress", default)]
+    pub ip_address: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+/// Filters for the audit log endpoint
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AuditQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    #[serde(rename = "perPage", skip_serializing_if = "Option::is_none")]
+    pub per_page: Option<u32>,
+}
+
+/// Errors callers may want to react to instead of just reporting
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("The remote environment was modified since it was last fetched ({0})")]
+    Conflict(reqwest::StatusCode),
+    #[error("The server does not support this request ({0})")]
+    Unsupported(reqwest::StatusCode),
+}
+
+/// Whether `error` is an [`ApiError::Conflict`]
+pub fn is_conflict(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<ApiError>(),
+        Some(ApiError::Conflict(_))
+    )
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -76,6 +272,51 @@ pub struct EnvVariable {
     pub updated_at: Option<String>,
 }
 
+/// Single variable returned by the per-variable endpoint
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VariableResponse {
+    pub key: String,
+    pub value: String,
+    #[serde(rename = "updatedAt", default)]
+    pub updated_at: Option<String>,
+}
+
+/// Answered by servers for requests they do not implement
+fn is_unsupported(status: reqwest::StatusCode) -> bool {
+    matches!(
+        status,
+        reqwest::StatusCode::METHOD_NOT_ALLOWED | reqwest::StatusCode::NOT_IMPLEMENTED
+    )
+}
+
+/// How a per-variable route answered
+enum VariableRoute {
+    Answered(reqwest::Response),
+    /// The API reported that the variable does not exist
+    Missing,
+    /// Older servers only expose the full-map endpoint. They answer per-variable routes with
+    /// 405/501, or with a 404 that is not the API's JSON error for a missing variable.
+    Unsupported,
+}
+
+impl VariableRoute {
+    async fn classify(response: reqwest::Response) -> Self {
+        if is_unsupported(response.status()) {
+            return Self::Unsupported;
+        }
+        if response.status() != reqwest::StatusCode::NOT_FOUND {
+            return Self::Answered(response);
+        }
+
+        // The API reports a missing variable as `{"error": "..."}`; an unknown route
+        // gets the web server's own page
+        match response.json::<serde_json::Value>().await {
+            Ok(body) if body.get("error").is_some() => Self::Missing,
+            _ => Self::Unsupported,
+        }
+    }
+}
+
 pub struct ApiClient {
     client: Client,
     base_url: String,
@@ -152,6 +393,7 @@ impl ApiClient {
             anyhow::bail!("Failed to get environment: {}", response.status());
         }
 
+        let version = etag(&response);
         let env_response = response.json::<EnvironmentResponse>().await?;
 
         let variables: Vec<EnvVariable> = env_response
@@ -168,11 +410,53 @@ impl ApiClient {
             id: env_response.project.clone(),
             name: env_response.environment,
             variables,
+            version,
         };
 
         Ok(environment)
     }
 
+    /// Current version (ETag) of an environment, without downloading its variables.
+    /// `None` if the server does not report one.
+    pub async fn environment_version(
+        &self,
+        token: &str,
+        project_id: &str,
+        env_name: &str,
+    ) -> Result<Option<String>> {
+        let url = format!(
+            "{}/api/v1/projects/{}/{}",
+            self.base_url, project_id, env_name
+        );
+
+        let response = self.client.head(&url).bearer_auth(token).send().await?;
+
+        if response.status() == reqwest::StatusCode::METHOD_NOT_ALLOWED {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to get environment: {}", response.status());
+        }
+
+        Ok(etag(&response))
+    }
+
+    /// Fetch an environment as a plain key/value map
+    pub async fn get_variables(
+        &self,
+        token: &str,
+        project_id: &str,
+        env_name: &str,
+    ) -> Result<std::collections::HashMap<String, String>> {
+        let env = self.get_environment(token, project_id, env_name).await?;
+
+        Ok(env
+            .variables
+            .into_iter()
+            .map(|v| (v.key, v.value))
+            .collect())
+    }
+
     pub async fn update_variables(
         &self,
         token: &str,
@@ -180,6 +464,23 @@ impl ApiClient {
         env_name: &str,
         variables: Vec<EnvVariable>,
     ) -> Result<()> {
+        self.update_variables_if_match(token, project_id, env_name, variables, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Update variables only if the environment is still at `expected_version`.
+    ///
+    /// Fails with [`ApiError::Conflict`] when someone else changed it in between.
+    /// Returns the new version reported by the server, if any.
+    pub async fn update_variables_if_match(
+        &self,
+        token: &str,
+        project_id: &str,
+        env_name: &str,
+        variables: Vec<EnvVariable>,
+        expected_version: Option<&str>,
+    ) -> Result<Option<String>> {
         let url = format!(
             "{}/api/v1/projects/{}/{}",
             self.base_url, project_id, env_name
@@ -193,18 +494,385 @@ impl ApiClient {
             variables: std::collections::HashMap<String, String>,
         }
 
-        let response = self
+        let mut request = self
             .client
             .post(&url)
             .bearer_auth(token)
             .json(&PushRequest {
                 variables: vars_map,
-            })
+            });
+
+        if let Some(version) = expected_version {
+            request = request.header(reqwest::header::IF_MATCH, version);
+        }
+
+        let response = request.send().await?;
+
+        if matches!(
+            response.status(),
+            reqwest::StatusCode::CONFLICT | reqwest::StatusCode::PRECONDITION_FAILED
+        ) {
+            return Err(ApiError::Conflict(response.status()).into());
+        }
+
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to update variables: {}", response.status());
+        }
+
+        Ok(etag(&response))
+    }
+
+    /// Fetch a single variable, falling back to the full environment on older servers
+    pub async fn get_variable(
+        &self,
+        token: &str,
+        project_id: &str,
+        env_name: &str,
+        key: &str,
+    ) -> Result<Option<String>> {
+        let url = self.variable_url(project_id, env_name, key)?;
+
+        let response = self.client.get(url).bearer_auth(token).send().await?;
+
+        let response = match VariableRoute::classify(response).await {
+            VariableRoute::Answered(response) => response,
+            VariableRoute::Missing => return Ok(None),
+            VariableRoute::Unsupported => {
+                let vars = self.get_variables(token, project_id, env_name).await?;
+                return Ok(vars.get(key).cloned());
+            }
+        };
+
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to get variable: {}", response.status());
+        }
+
+        let variable = response.json::<VariableResponse>().await?;
+        Ok(Some(variable.value))
+    }
+
+    /// Create or update the given variables, leaving every other key untouched
+    pub async fn upsert_variables(
+        &self,
+        token: &str,
+        project_id: &str,
+        env_name: &str,
+        variables: std::collections::HashMap<String, String>,
+    ) -> Result<()> {
+        #[derive(Serialize)]
+        struct SetVariableRequest<'a> {
+            value: &'a str,
+        }
+
+        for (key, value) in &variables {
+            let url = self.variable_url(project_id, env_name, key)?;
+
+            let response = self
+                .client
+                .put(url)
+                .bearer_auth(token)
+                .json(&SetVariableRequest { value })
+                .send()
+                .await?;
+
+            let response = match VariableRoute::classify(response).await {
+                VariableRoute::Answered(response) => response,
+                VariableRoute::Missing | VariableRoute::Unsupported => {
+                    return self
+                        .modify_variables(token, project_id, env_name, |current| {
+                            current.extend(variables);
+                            Ok(())
+                        })
+                        .await;
+                }
+            };
+
+            if !response.status().is_success() {
+                anyhow::bail!("Failed to set variable {}: {}", key, response.status());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Delete the given variables, leaving every other key untouched.
+    ///
+    /// Nothing is deleted unless every key exists.
+    pub async fn remove_variables(
+        &self,
+        token: &str,
+        project_id: &str,
+        env_name: &str,
+        keys: &[String],
+    ) -> Result<()> {
+        let current = self.get_variables(token, project_id, env_name).await?;
+        if let Some(missing) = keys.iter().find(|k| !current.contains_key(*k)) {
+            anyhow::bail!("Variable '{}' not found", missing);
+        }
+
+        for key in keys {
+            let url = self.variable_url(project_id, env_name, key)?;
+
+            let response = self.client.delete(url).bearer_auth(token).send().await?;
+
+            let response = match VariableRoute::classify(response).await {
+                VariableRoute::Answered(response) => response,
+                // Already gone, which is what we want
+                VariableRoute::Missing => continue,
+                VariableRoute::Unsupported => {
+                    return self
+                        .modify_variables(token, project_id, env_name, |current| {
+                            if let Some(missing) = keys.iter().find(|k| !current.contains_key(*k)) {
+                                anyhow::bail!("Variable '{}' not found", missing);
+                            }
+                            current.retain(|k, _| !keys.contains(k));
+                            Ok(())
+                        })
+                        .await;
+                }
+            };
+
+            if !response.status().is_success() {
+                anyhow::bail!("Failed to delete variable {}: {}", key, response.status());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// URL of a single variable, with the key percent-encoded as one path segment
+    fn variable_url(&self, project_id: &str, env_name: &str, key: &str) -> Result<reqwest::Url> {
+        let mut url = reqwest::Url::parse(&format!(
+            "{}/api/v1/projects/{}/{}/variables",
+            self.base_url, project_id, env_name
+        ))?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("Invalid API URL: {}", self.base_url))?
+            .push(key);
+        Ok(url)
+    }
+
+    /// Read-modify-write the whole map for servers without per-variable routes.
+    /// Fails with [`ApiError::Conflict`] if the environment changes in between.
+    async fn modify_variables(
+        &self,
+        token: &str,
+        project_id: &str,
+        env_name: &str,
+        modify: impl FnOnce(&mut std::collections::HashMap<String, String>) -> Result<()>,
+    ) -> Result<()> {
+        let environment = self.get_environment(token, project_id, env_name).await?;
+        let mut current: std::collections::HashMap<String, String> = environment
+            .variables
+            .into_iter()
+            .map(|v| (v.key, v.value))
+            .collect();
+        modify(&mut current)?;
+
+        self.update_variables_if_match(
+            token,
+            project_id,
+            env_name,
+            to_env_variables(current),
+            environment.version.as_deref(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// List past changes of an environment, optionally for a single key
+    pub async fn get_history(
+        &self,
+        token: &str,
+        project_id: &str,
+        env_name: &str,
+        key: Option<&str>,
+    ) -> Result<Vec<HistoryEntry>> {
+        let url = format!(
+            "{}/api/v1/projects/{}/{}/history",
+            self.base_url, project_id, env_name
+        );
+
+        let mut request = self.client.get(&url).bearer_auth(token);
+        if let Some(key) = key {
+            request = request.query(&[("key", key)]);
+        }
+
+        let response = request.send().await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to get history: {}", response.status());
+        }
+
+        let history_response = response.json::<HistoryResponse>().await?;
+        Ok(history_response.entries)
+    }
+
+    /// Fetch the full state of an environment as it was at `point`
+    pub async fn get_snapshot(
+        &self,
+        token: &str,
+        project_id: &str,
+        env_name: &str,
+        point: &SnapshotPoint,
+    ) -> Result<SnapshotResponse> {
+        let url = format!(
+            "{}/api/v1/projects/{}/{}/snapshot",
+            self.base_url, project_id, env_name
+        );
+
+        let query = match point {
+            SnapshotPoint::Version(version) => [("version", version.to_string())],
+            SnapshotPoint::Timestamp(at) => [("at", at.to_rfc3339())],
+        };
+
+        let response = self
+            .client
+            .get(&url)
+            .bearer_auth(token)
+            .query(&query)
             .send()
             .await?;
 
         if !response.status().is_success() {
-            anyhow::bail!("Failed to update variables: {}", response.status());
+            anyhow::bail!("Failed to get snapshot: {}", response.status());
+        }
+
+        let snapshot = response.json::<SnapshotResponse>().await?;
+        Ok(snapshot)
+    }
+
+    /// Fetch one page of the audit log of a project
+    pub async fn get_audit_log(
+        &self,
+        token: &str,
+        project_id: &str,
+        query: &AuditQuery,
+    ) -> Result<AuditResponse> {
+        let url = format!("{}/api/v1/projects/{}/audit", self.base_url, project_id);
+
+        let response = self
+            .client
+            .get(&url)
+            .bearer_auth(token)
+            .query(query)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to get audit log: {}", response.status());
+        }
+
+        let audit_response = response.json::<AuditResponse>().await?;
+        Ok(audit_response)
+    }
+
+    pub async fn get_environments(
+        &self,
+        token: &str,
+        project_id: &str,
+    ) -> Result<Vec<EnvironmentInfo>> {
+        let url = format!(
+            "{}/api/v1/projects/{}/environments",
+            self.base_url, project_id
+        );
+
+        let response = self.client.get(&url).bearer_auth(token).send().await?;
+
+        if is_unsupported(response.status()) {
+            return Err(ApiError::Unsupported(response.status()).into());
+        }
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to get environments: {}", response.status());
+        }
+
+        let environments_response = response.json::<EnvironmentsResponse>().await?;
+        Ok(environments_response.environments)
+    }
+
+    /// Create an environment, optionally copying the variables of `clone_from`
+    pub async fn create_environment(
+        &self,
+        token: &str,
+        project_id: &str,
+        name: &str,
+        clone_from: Option<&str>,
+    ) -> Result<EnvironmentInfo> {
+        let url = format!(
+            "{}/api/v1/projects/{}/environments",
+            self.base_url, project_id
+        );
+
+        #[derive(Serialize)]
+        struct CreateEnvironmentRequest<'a> {
+            name: &'a str,
+            #[serde(rename = "cloneFrom", skip_serializing_if = "Option::is_none")]
+            clone_from: Option<&'a str>,
+        }
+
+        let response = self
+            .client
+            .post(&url)
+            .bearer_auth(token)
+            .json(&CreateEnvironmentRequest { name, clone_from })
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to create environment: {}", response.status());
+        }
+
+        let environment = response.json::<EnvironmentInfo>().await?;
+        Ok(environment)
+    }
+
+    pub async fn rename_environment(
+        &self,
+        token: &str,
+        project_id: &str,
+        name: &str,
+        new_name: &str,
+    ) -> Result<()> {
+        let url = format!(
+            "{}/api/v1/projects/{}/environments/{}",
+            self.base_url, project_id, name
+        );
+
+        #[derive(Serialize)]
+        struct RenameEnvironmentRequest<'a> {
+            name: &'a str,
+        }
+
+        let response = self
+            .client
+            .patch(&url)
+            .bearer_auth(token)
+            .json(&RenameEnvironmentRequest { name: new_name })
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to rename environment: {}", response.status());
+        }
+
+        Ok(())
+    }
+
+    pub async fn delete_environment(
+        &self,
+        token: &str,
+        project_id: &str,
+        name: &str,
+    ) -> Result<()> {
+        let url = format!(
+            "{}/api/v1/projects/{}/environments/{}",
+            self.base_url, project_id, name
+        );
+
+        let response = self.client.delete(&url).bearer_auth(token).send().await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to delete environment: {}", response.status());
         }
 
         Ok(())
@@ -243,4 +911,105 @@ impl ApiClient {
         let project = response.json::<Project>().await?;
         Ok(project)
     }
+
+    pub async fn get_project(&self, token: &str, project_id: &str) -> Result<Project> {
+        let url = format!("{}/api/v1/projects/{}", self.base_url, project_id);
+
+        let response = self.client.get(&url).bearer_auth(token).send().await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to get project: {}", response.status());
+        }
+
+        let project = response.json::<Project>().await?;
+        Ok(project)
+    }
+
+    /// Rename, re-describe, archive or unarchive a project
+    pub async fn update_project(
+        &self,
+        token: &str,
+        project_id: &str,
+        update: &ProjectUpdate,
+    ) -> Result<Project> {
+        let url = format!("{}/api/v1/projects/{}", self.base_url, project_id);
+
+        let response = self
+            .client
+            .patch(&url)
+            .bearer_auth(token)
+            .json(update)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to update project: {}", response.status());
+        }
+
+        let project = response.json::<Project>().await?;
+        Ok(project)
+    }
+
+    pub async fn delete_project(&self, token: &str, project_id: &str) -> Result<()> {
+        let url = format!("{}/api/v1/projects/{}", self.base_url, project_id);
+
+        let response = self.client.delete(&url).bearer_auth(token).send().await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to delete project: {}", response.status());
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_workspace(&self, token: &str, workspace_id: &str) -> Result<Workspace> {
+        let url = format!("{}/api/v1/workspaces/{}", self.base_url, workspace_id);
+
+        let response = self.client.get(&url).bearer_auth(token).send().await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to get workspace: {}", response.status());
+        }
+
+        let workspace = response.json::<Workspace>().await?;
+        Ok(workspace)
+    }
+
+    pub async fn get_workspace_members(
+        &self,
+        token: &str,
+        workspace_id: &str,
+    ) -> Result<Vec<Member>> {
+        let url = format!(
+            "{}/api/v1/workspaces/{}/members",
+            self.base_url, workspace_id
+        );
+
+        let response = self.client.get(&url).bearer_auth(token).send().await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to get workspace members: {}", response.status());
+        }
+
+        let members_response = response.json::<MembersResponse>().await?;
+        Ok(members_response.members)
+    }
+}
+
+fn to_env_variables(vars: std::collections::HashMap<String, String>) -> Vec<EnvVariable> {
+    vars.into_iter()
+        .map(|(key, value)| EnvVariable {
+            key,
+            value,
+            updated_at: None,
+        })
+        .collect()
+}
+
+fn etag(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
 }
@@ -0,0 +1,183 @@
+#[cfg(unix)]
+mod unix {
+    use crate::agent::{self, AgentClient, Request};
+    use crate::api::ApiClient;
+    use crate::config::Config;
+    use crate::status;
+    use crate::storage;
+    use crate::utils::output::{self, OutputFormat};
+    use crate::utils::target::RemoteTarget;
+    use anyhow::Result;
+    use colored::*;
+    use std::path::PathBuf;
+
+    /// Run the agent in the foreground, watching `targets` from the start and any other
+    /// environment once it is asked for
+    pub async fn start(targets: Vec<String>, socket: Option<PathBuf>) -> Result<()> {
+        let config = Config::load()?;
+        let token = config.get_token()?;
+        let api_client = ApiClient::from_config(&config);
+
+        let mut keys = Vec::new();
+        for spec in &targets {
+            let target = RemoteTarget::parse(spec)?;
+            target.validate(&api_client, &token).await?;
+            keys.push(storage::current_key(
+                &config,
+                &target.project,
+                &target.environment,
+            )?);
+        }
+
+        let path = match socket {
+            Some(path) => path,
+            None => agent::socket_path()?,
+        };
+        let listener = agent::bind(&path)?;
+
+        status!("{}", "🛰️  EnvSafe agent".cyan().bold());
+        status!("{}", format!("  Socket: {}", path.display()).bright_black());
+        status!("{}", "  Press Ctrl+C to stop".bright_black());
+        status!();
+
+        // Written once listening, so scripts can wait for it before connecting
+        if output::is_machine() {
+            output::emit(&serde_json::json!({
+                "socket": path,
+                "pid": std::process::id(),
+            }))?;
+        }
+
+        let result = agent::serve(config, listener, keys).await;
+        let _ = std::fs::remove_file(&path);
+        result?;
+
+        status!("{}", "✓ Agent stopped".green());
+        Ok(())
+    }
+
+    /// List the environments the running agent watches
+    pub async fn status(socket: Option<PathBuf>) -> Result<()> {
+        let path = match socket {
+            Some(path) => path,
+            None => agent::socket_path()?,
+        };
+        let Some(mut client) = AgentClient::connect(&path).await? else {
+            if output::is_machine() {
+                return output::emit(&serde_json::json!({ "running": false }));
+            }
+            status!(
+                "{}",
+                "ℹ No agent running. Start one with 'envsafe agent start'".bright_black()
+            );
+            return Ok(());
+        };
+
+        let environments = client
+            .request(&Request::List)
+            .await?
+            .environments
+            .unwrap_or_default();
+
+        if output::is_machine() {
+            return output::emit(&serde_json::json!({
+                "running": true,
+                "socket": path,
+                "environments": environments,
+            }));
+        }
+
+        if output::format() == OutputFormat::Table {
+            let rows: Vec<Vec<String>> = environments
+                .iter()
+                .map(|e| {
+                    vec![
+                        e.project.clone(),
+                        e.environment.clone(),
+                        e.version.to_string(),
+                        e.variables.to_string(),
+                        e.last_updated.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            output::print_table(
+                &["project", "environment", "version", "variables", "updated"],
+                &rows,
+            );
+            return Ok(());
+        }
+
+        status!("{}", "🛰️  EnvSafe agent".cyan().bold());
+        status!("{}", format!("  Socket: {}", path.display()).bright_black());
+        status!();
+
+        if environments.is_empty() {
+            status!("{}", "  Not watching any environment yet".bright_black());
+            return Ok(());
+        }
+
+        for entry in &environments {
+            let state = match &entry.last_updated {
+                Some(updated) => format!(
+                    "v{} · {} variables · {}",
+                    entry.version, entry.variables, updated
+                ),
+                None => "connecting...".to_string(),
+            };
+            println!(
+                "  {} {}/{} {}",
+                "•".cyan(),
+                entry.project.bright_white(),
+                entry.environment,
+                state.bright_black()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Ask the running agent to exit
+    pub async fn stop(socket: Option<PathBuf>) -> Result<()> {
+        let path = match socket {
+            Some(path) => path,
+            None => agent::socket_path()?,
+        };
+        let stopped = match AgentClient::connect(&path).await? {
+            Some(mut client) => {
+                client.request(&Request::Shutdown).await?;
+                status!("{}", "✓ Agent stopped".green());
+                true
+            }
+            None => {
+                status!("{}", "ℹ No agent running".bright_black());
+                false
+            }
+        };
+
+        if output::is_machine() {
+            return output::emit(&serde_json::json!({ "stopped": stopped }));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+pub use unix::{start, status, stop};
+
+#[cfg(not(unix))]
+pub async fn start(
+    _targets: Vec<String>,
+    _socket: Option<std::path::PathBuf>,
+) -> anyhow::Result<()> {
+    anyhow::bail!("'envsafe agent' needs Unix domain sockets and is not available on this platform")
+}
+
+#[cfg(not(unix))]
+pub async fn status(socket: Option<std::path::PathBuf>) -> anyhow::Result<()> {
+    start(Vec::new(), socket).await
+}
+
+#[cfg(not(unix))]
+pub async fn stop(socket: Option<std::path::PathBuf>) -> anyhow::Result<()> {
+    start(Vec::new(), socket).await
+}
@@ -0,0 +1,216 @@
+use crate::api::{ApiClient, AuditEntry, AuditQuery};
+use crate::config::Config;
+use crate::status;
+use crate::utils::output;
+use crate::utils::target::RemoteTarget;
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use colored::*;
+
+/// Entries requested per page; the server may cap this lower
+const PAGE_SIZE: usize = 100;
+
+pub struct AuditOptions {
+    /// Restrict to one environment (all environments when `None`)
+    pub environment: Option<String>,
+    pub user: Option<String>,
+    pub action: Option<String>,
+    pub key: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: usize,
+    pub json: bool,
+    pub csv: bool,
+}
+
+/// Parse an absolute time (RFC 3339 or YYYY-MM-DD) or a relative one like `7d`, `12h`, `30m`
+pub fn parse_time(s: &str) -> Result<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(s) {
+        return Ok(at.with_timezone(&Utc));
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+
+    if s.len() > 1 {
+        let (amount, unit) = s.split_at(s.len() - 1);
+        if let Ok(amount) = amount.parse::<i64>() {
+            let ago = match unit {
+                "d" => Duration::try_days(amount),
+                "h" => Duration::try_hours(amount),
+                "m" => Duration::try_minutes(amount),
+                _ => None,
+            };
+            // Out of range amounts fall through to the parse error
+            if let Some(at) = ago.and_then(|ago| Utc::now().checked_sub_signed(ago)) {
+                return Ok(at);
+            }
+        }
+    }
+
+    anyhow::bail!(
+        "Invalid time '{}'. Use RFC 3339, YYYY-MM-DD or a relative duration like 7d, 12h, 30m",
+        s
+    )
+}
+
+pub async fn execute(project: Option<String>, options: AuditOptions) -> Result<()> {
+    let config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+    let project = RemoteTarget::resolve_project(project)?;
+
+    let mut query = AuditQuery {
+        environment: options.environment.clone(),
+        user: options.user.clone(),
+        action: options.action.clone(),
+        key: options.key.clone(),
+        since: options.since.map(|t| t.to_rfc3339()),
+        until: options.until.map(|t| t.to_rfc3339()),
+        page: Some(1),
+        per_page: Some(options.limit.min(PAGE_SIZE) as u32),
+    };
+
+    let mut entries: Vec<AuditEntry> = Vec::new();
+
+    loop {
+        let response = api_client.get_audit_log(&token, &project, &query).await?;
+        let received = response.entries.len();
+        entries.extend(response.entries);
+
+        match response.next_page {
+            Some(next) if received > 0 && entries.len() < options.limit => {
+                query.page = Some(next);
+            }
+            _ => break,
+        }
+    }
+
+    entries.truncate(options.limit);
+
+    if options.json || output::is_machine() {
+        output::emit(&entries)?;
+    } else if options.csv {
+        print_csv(&entries);
+    } else {
+        print_table(&project, &entries);
+    }
+
+    Ok(())
+}
+
+fn print_table(project: &str, entries: &[AuditEntry]) {
+    status!("{}", format!("🕵️  Audit log of {}", project).cyan().bold());
+    status!();
+
+    if entries.is_empty() {
+        status!("{}", "  No matching entries".bright_black());
+        return;
+    }
+
+    let user_width = column_width(entries, "USER", |e| e.user.as_deref());
+    let env_width = column_width(entries, "ENVIRONMENT", |e| e.environment.as_deref());
+    let action_width = column_width(entries, "ACTION", |e| Some(e.action.as_str()));
+
+    status!(
+        "  {}",
+        format!(
+            "{:<25} {:<user_width$} {:<action_width$} {:<env_width$} KEY",
+            "TIME", "USER", "ACTION", "ENVIRONMENT"
+        )
+        .bold()
+    );
+
+    for entry in entries {
+        println!(
+            "  {} {:<user_width$} {} {:<env_width$} {}",
+            format!("{:<25}", entry.created_at).bright_black(),
+            entry.user.as_deref().unwrap_or("-"),
+            format!("{:<action_width$}", entry.action).yellow(),
+            entry.environment.as_deref().unwrap_or("-"),
+            entry.key.as_deref().unwrap_or("-").bright_white(),
+        );
+    }
+
+    status!();
+    status!("{}", format!("  {} entries", entries.len()).bright_black());
+}
+
+fn column_width<F>(entries: &[AuditEntry], header: &str, field: F) -> usize
+where
+    F: Fn(&AuditEntry) -> Option<&str>,
+{
+    entries
+        .iter()
+        .map(|e| field(e).unwrap_or("-").len())
+        .chain(std::iter::once(header.len()))
+        .max()
+        .unwrap_or(0)
+}
+
+fn print_csv(entries: &[AuditEntry]) {
+    println!("time,user,action,environment,key,ip_address");
+    for entry in entries {
+        let row = [
+            entry.created_at.as_str(),
+            entry.user.as_deref().unwrap_or(""),
+            entry.action.as_str(),
+            entry.environment.as_deref().unwrap_or(""),
+            entry.key.as_deref().unwrap_or(""),
+            entry.ip_address.as_deref().unwrap_or(""),
+        ];
+        let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        println!("{}", row.join(","));
+    }
+}
+
+/// Quote a CSV field when it contains a separator, quote or newline.
+///
+/// A field a spreadsheet would read as a formula (`=`, `+`, `-`, `@`, tab or carriage return
+/// first) gets a leading `'`: user names and keys come from other people.
+fn csv_field(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", field)
+    } else {
+        field.to_string()
+    };
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("API_KEY"), "API_KEY");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field(r#"say "hi""#), r#""say ""hi""""#);
+    }
+
+    #[test]
+    fn test_csv_field_neutralizes_formulas() {
+        assert_eq!(csv_field(r#"=HYPERLINK("x")"#), r#""'=HYPERLINK(""x"")""#);
+        assert_eq!(csv_field("+1"), "'+1");
+        assert_eq!(csv_field("-2"), "'-2");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("\tcmd"), "'\tcmd");
+        assert_eq!(csv_field("a-b"), "a-b");
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(
+            parse_time("2024-05-01").unwrap().to_rfc3339(),
+            "2024-05-01T00:00:00+00:00"
+        );
+        assert!(parse_time("7d").unwrap() < Utc::now());
+        assert!(parse_time("999999999999d").is_err());
+        assert!(parse_time("7w").is_err());
+    }
+}
@@ -0,0 +1,268 @@
+use super::project::is_linked;
+use crate::config::{Config, ProjectConfig};
+use crate::status;
+use crate::storage::{self, EnvStorage, SegmentKey, SegmentState};
+use crate::sync_state::SyncState;
+use crate::utils::diff::display_value;
+use crate::utils::output::{self, format_size, OutputFormat};
+use crate::utils::prompt::confirm;
+use crate::utils::target::RemoteTarget;
+use anyhow::Result;
+use colored::*;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// List the environments cached in shared memory for the current user
+pub async fn list() -> Result<()> {
+    let entries = EnvStorage::list()?;
+
+    if output::is_machine() {
+        return output::emit(&entries);
+    }
+
+    if output::format() == OutputFormat::Table {
+        let rows: Vec<Vec<String>> = entries
+            .iter()
+            .map(|e| {
+                vec![
+                    e.project.clone(),
+                    e.environment.clone(),
+                    e.version.to_string(),
+                    e.variables.to_string(),
+                    e.last_updated.clone(),
+                    e.segment.clone(),
+                ]
+            })
+            .collect();
+        output::print_table(
+            &[
+                "project",
+                "environment",
+                "version",
+                "variables",
+                "updated",
+                "segment",
+            ],
+            &rows,
+        );
+        return Ok(());
+    }
+
+    status!("{}", "🗄️  Cached environments".cyan().bold());
+    status!();
+
+    if entries.is_empty() {
+        status!(
+            "{}",
+            "  Nothing cached. Run 'envsafe pull' to fill the cache".bright_black()
+        );
+        return Ok(());
+    }
+
+    for entry in &entries {
+        println!(
+            "  {} {}/{} {}",
+            "•".cyan(),
+            entry.project.bright_white(),
+            entry.environment,
+            format!(
+                "v{} · {} variables · {}",
+                entry.version, entry.variables, entry.last_updated
+            )
+            .bright_black()
+        );
+        println!("    {}", entry.segment.bright_black());
+    }
+
+    Ok(())
+}
+
+/// Delete the cached segment of one environment, the current one by default, or all of them
+pub async fn clear(target: Option<String>, all: bool, yes: bool) -> Result<()> {
+    if all {
+        let entries = EnvStorage::list()?;
+        if entries.is_empty() {
+            status!("{}", "ℹ Nothing cached".bright_black());
+            if output::is_machine() {
+                return output::emit(&json!({ "cleared": 0 }));
+            }
+            return Ok(());
+        }
+
+        let prompt = format!("Remove {} cached environment(s)?", entries.len());
+        if !confirm(&prompt, yes)? {
+            status!("{}", "✗ Clear cancelled".yellow());
+            if output::is_machine() {
+                return output::emit(&json!({ "cleared": 0 }));
+            }
+            return Ok(());
+        }
+
+        for entry in &entries {
+            let key = SegmentKey::new(&entry.workspace, &entry.project, &entry.environment);
+            EnvStorage::remove(&key)?;
+        }
+
+        status!(
+            "{}",
+            format!("✓ Cleared {} cached environment(s)", entries.len()).green()
+        );
+        if output::is_machine() {
+            return output::emit(&json!({ "cleared": entries.len() }));
+        }
+        return Ok(());
+    }
+
+    let (target, key) = resolve(target)?;
+    let cleared = EnvStorage::remove(&key)?;
+    if cleared {
+        status!("{}", format!("✓ Cleared cache for {}", target).green());
+    } else {
+        status!(
+            "{}",
+            format!("ℹ Nothing cached for {}", target).bright_black()
+        );
+    }
+
+    if output::is_machine() {
+        return output::emit(&json!({ "cleared": usize::from(cleared) }));
+    }
+
+    Ok(())
+}
+
+/// Delete the segments nothing uses any more: expired and unindexed ones, and with `all`
+/// every segment no running `watch`, `agent` or `serve` holds
+pub async fn purge(all: bool) -> Result<()> {
+    let config = Config::load()?;
+    let max_age = Duration::from_secs(config.cache_max_age);
+    let purged = EnvStorage::purge(max_age, |usage| {
+        usage.is_orphaned() || (all && usage.state == SegmentState::Idle)
+    })?;
+
+    if output::is_machine() {
+        return output::emit(&purged);
+    }
+
+    if purged.is_empty() {
+        status!("{}", "ℹ Nothing to purge".bright_black());
+        return Ok(());
+    }
+
+    for usage in &purged {
+        status!(
+            "  {} {} {}",
+            "•".cyan(),
+            usage.label(),
+            format!("({}, {})", usage.state.as_str(), format_size(usage.size)).bright_black()
+        );
+    }
+    let freed: u64 = purged.iter().map(|usage| usage.size).sum();
+    status!(
+        "{}",
+        format!(
+            "✓ Purged {} segment(s), freed {}",
+            purged.len(),
+            format_size(freed)
+        )
+        .green()
+    );
+    Ok(())
+}
+
+/// Show what is cached for one environment, the current one by default
+pub async fn inspect(target: Option<String>, show_values: bool) -> Result<()> {
+    let (target, key) = resolve(target)?;
+
+    let storage = EnvStorage::open_existing(&key)?;
+    let data = match &storage {
+        Some(storage) => storage.read()?,
+        None => None,
+    };
+    let data = data.ok_or_else(|| {
+        anyhow::anyhow!("Nothing cached for {}. Run 'envsafe pull' first", target)
+    })?;
+
+    let variables: BTreeMap<String, String> = data
+        .variables
+        .iter()
+        .map(|(k, v)| (k.clone(), display_value(v, show_values)))
+        .collect();
+
+    if output::is_machine() {
+        return output::emit(&json!({
+            "segment": key.os_id(),
+            "project": target.project,
+            "environment": target.environment,
+            "version": data.version,
+            "last_updated": data.last_updated,
+            "variables": variables,
+        }));
+    }
+
+    status!("{}", format!("🗄️  {}", target).cyan().bold());
+    status!();
+    println!("  Segment: {}", key.os_id().bright_black());
+    println!("  Version: {}", data.version);
+    println!("  Updated: {}", data.last_updated.bright_black());
+    println!("  Variables: {}", variables.len());
+    status!();
+    for (name, value) in &variables {
+        println!("  {}={}", name, value.bright_black());
+    }
+
+    Ok(())
+}
+
+fn resolve(target: Option<String>) -> Result<(RemoteTarget, SegmentKey)> {
+    let config = Config::load()?;
+    let target = match target {
+        Some(spec) => RemoteTarget::parse(&spec)?,
+        None => RemoteTarget::resolve(None, None)?,
+    };
+    let key = storage::current_key(&config, &target.project, &target.environment)?;
+    Ok((target, key))
+}
+
+/// Drop what is kept locally about a deleted project, or only about its `environment`:
+/// cached segments nothing watches any more, and sync states
+pub fn forget_deleted(config: &Config, project: &str, environment: Option<&str>) {
+    // Cached under the ID or the slug: both are known when the directory is linked to it
+    let local_config = ProjectConfig::load().ok().flatten();
+    let mut names = vec![project];
+    if let Some(linked) = local_config.as_ref().filter(|c| is_linked(c, project)) {
+        names.push(&linked.project_id);
+        names.extend(linked.project_slug.as_deref());
+    }
+    names.sort();
+    names.dedup();
+
+    let result = storage::current_workspace(config).and_then(|workspace| {
+        EnvStorage::purge(Duration::from_secs(config.cache_max_age), |usage| {
+            usage.cleared_on_delete(&workspace, &names, environment)
+        })
+    });
+    let result = result.and_then(|purged| {
+        for name in &names {
+            SyncState::remove(name, environment)?;
+        }
+        Ok(purged)
+    });
+
+    match result {
+        Ok(purged) if !purged.is_empty() => status!(
+            "{}",
+            format!("  Removed {} cached environment(s)", purged.len()).bright_black()
+        ),
+        Ok(_) => {}
+        Err(e) => status!(
+            "{}",
+            format!(
+                "⚠ Could not clear the local cache: {}. Run 'envsafe cache purge'",
+                e
+            )
+            .yellow()
+        ),
+    }
+}
@@ -1,24 +1,85 @@
+use crate::config::Config;
+use crate::status;
+use crate::utils::duration::{format_duration, parse_duration};
+use crate::utils::output;
 use anyhow::Result;
 use colored::*;
-use crate::config::Config;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// What `envsafe config --show` reports; the token itself is never included
+#[derive(Serialize)]
+struct ConfigView<'a> {
+    api_url: &'a str,
+    dashboard_url: &'a str,
+    language: &'a str,
+    token_set: bool,
+    current_workspace: Option<&'a str>,
+    current_project: Option<&'a str>,
+    env_aliases: &'a BTreeMap<String, String>,
+    cache_max_age: u64,
+}
 
-pub async fn execute(api_url: Option<String>, show: bool) -> Result<()> {
+pub async fn execute(
+    api_url: Option<String>,
+    cache_max_age: Option<String>,
+    show: bool,
+) -> Result<()> {
     let mut config = Config::load()?;
-    
+
     if let Some(url) = api_url {
         config.api_url = url.clone();
         config.dashboard_url = url.clone();
         config.save()?;
-        println!("{}", format!("✓ API URL set to: {}", url).green());
+        status!("{}", format!("✓ API URL set to: {}", url).green());
+    }
+
+    if let Some(max_age) = cache_max_age {
+        config.cache_max_age = parse_duration(&max_age)?.as_secs();
+        config.save()?;
+        if config.cache_max_age == 0 {
+            status!(
+                "{}",
+                "✓ Shared memory cache disabled for 'envsafe run'".green()
+            );
+        } else {
+            let max_age = format_duration(Duration::from_secs(config.cache_max_age));
+            status!("{}", format!("✓ Cache max age set to: {}", max_age).green());
+        }
+    }
+
+    // Machine output always reports the resulting configuration
+    if output::is_machine() {
+        return output::emit(&ConfigView {
+            api_url: &config.api_url,
+            dashboard_url: &config.dashboard_url,
+            language: &config.language,
+            token_set: config.token.is_some(),
+            current_workspace: config
+                .current_workspace_slug
+                .as_deref()
+                .or(config.current_workspace.as_deref()),
+            current_project: config
+                .current_project_slug
+                .as_deref()
+                .or(config.current_project.as_deref()),
+            env_aliases: &config.env_aliases,
+            cache_max_age: config.cache_max_age,
+        });
     }
-    
+
     if show {
-        println!("{}", "⚙️  Configuration".cyan().bold());
-        println!();
+        status!("{}", "⚙️  Configuration".cyan().bold());
+        status!();
         println!("  API URL: {}", config.api_url);
         println!("  Dashboard URL: {}", config.dashboard_url);
         println!("  Language: {}", config.language);
-        
+        println!(
+            "  Cache max age: {}",
+            format_duration(Duration::from_secs(config.cache_max_age))
+        );
+
         let token_display = if config.token.is_some() {
             "••••••••"
         } else {
@@ -26,6 +87,6 @@ pub async fn execute(api_url: Option<String>, show: bool) -> Result<()> {
         };
         println!("  Token: {}", token_display.bright_black());
     }
-    
+
     Ok(())
 }
@@ -0,0 +1,199 @@
+use crate::api::ApiClient;
+use crate::config::{Config, ProjectConfig};
+use crate::status;
+use crate::storage::{self, EnvStorage};
+use crate::utils::diff::{display_value, EnvDiff};
+use crate::utils::env_parser::FileFormat;
+use crate::utils::output;
+use crate::utils::target::RemoteTarget;
+use anyhow::Result;
+use colored::*;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Where one side of the comparison comes from
+enum DiffSource {
+    /// Local file, as dotenv, JSON or YAML
+    File(String),
+    /// Snapshot of an environment held in shared memory
+    SharedMemory(RemoteTarget),
+    /// Remote environment of a project
+    Remote(RemoteTarget),
+}
+
+impl DiffSource {
+    fn parse(spec: &str) -> Result<Self> {
+        if spec == "shm" || spec == "@shm" {
+            return Ok(DiffSource::SharedMemory(RemoteTarget::resolve(None, None)?));
+        }
+        if let Some(target) = spec.strip_prefix("shm:") {
+            return Ok(DiffSource::SharedMemory(RemoteTarget::parse(target)?));
+        }
+
+        if Path::new(spec).is_file() {
+            return Ok(DiffSource::File(spec.to_string()));
+        }
+
+        Ok(DiffSource::Remote(RemoteTarget::parse(spec)?))
+    }
+
+    fn label(&self) -> String {
+        match self {
+            DiffSource::File(path) => path.clone(),
+            DiffSource::SharedMemory(target) => format!("shared memory ({})", target),
+            DiffSource::Remote(target) => target.to_string(),
+        }
+    }
+
+    async fn load(&self, config: &Config) -> Result<HashMap<String, String>> {
+        match self {
+            DiffSource::File(path) => {
+                // As pull and push read it: the extension, else the `.envsafe` format
+                let format = match FileFormat::from_extension(path) {
+                    Some(format) => format,
+                    None => ProjectConfig::load()?
+                        .and_then(|c| c.format)
+                        .unwrap_or_default(),
+                };
+                format.parse(&fs::read_to_string(path)?)
+            }
+            DiffSource::SharedMemory(target) => {
+                let key = storage::current_key(config, &target.project, &target.environment)?;
+                let data = match EnvStorage::open_existing(&key)? {
+                    Some(storage) => storage.read()?,
+                    None => None,
+                };
+                let data = data.ok_or_else(|| {
+                    anyhow::anyhow!("Nothing cached for {}. Run 'envsafe pull' first", target)
+                })?;
+                Ok(data.variables)
+            }
+            DiffSource::Remote(target) => {
+                let token = config.get_token()?;
+                let api_client = ApiClient::from_config(config);
+                target.validate(&api_client, &token).await?;
+                api_client
+                    .get_variables(&token, &target.project, &target.environment)
+                    .await
+            }
+        }
+    }
+}
+
+/// Compare two variable sources. Returns `true` when they differ.
+pub async fn execute(left: &str, right: &str, json: bool, show_values: bool) -> Result<bool> {
+    let config = Config::load()?;
+
+    let left_source = DiffSource::parse(left)?;
+    let right_source = DiffSource::parse(right)?;
+
+    let left_vars = left_source.load(&config).await?;
+    let right_vars = right_source.load(&config).await?;
+
+    let diff = EnvDiff::compute(&left_vars, &right_vars);
+    let left_label = left_source.label();
+    let right_label = right_source.label();
+
+    if json || output::is_machine() {
+        let masked = |vars: &BTreeMap<String, String>| -> BTreeMap<String, String> {
+            vars.iter()
+                .map(|(k, v)| (k.clone(), display_value(v, show_values)))
+                .collect()
+        };
+        let changed: BTreeMap<String, serde_json::Value> = diff
+            .changed
+            .iter()
+            .map(|(k, (l, r))| {
+                let values = json!({
+                    "left": display_value(l, show_values),
+                    "right": display_value(r, show_values),
+                });
+                (k.clone(), values)
+            })
+            .collect();
+
+        let document = json!({
+            "left": left_label,
+            "right": right_label,
+            "only_in_left": masked(&diff.removed),
+            "only_in_right": masked(&diff.added),
+            "changed": changed,
+            "unchanged": diff.unchanged,
+        });
+        output::emit(&document)?;
+        return Ok(!diff.is_empty());
+    }
+
+    status!(
+        "{}",
+        format!("🔍 Comparing {} ↔ {}", left_label, right_label).cyan()
+    );
+    status!();
+
+    if diff.is_empty() {
+        status!(
+            "{}",
+            format!("✓ No differences ({} keys)", diff.unchanged).green()
+        );
+        return Ok(false);
+    }
+
+    // The differences are the result: printed like data, even with --quiet
+    if !diff.removed.is_empty() {
+        println!("{}", format!("Only in {}:", left_label).bold());
+        for (key, value) in &diff.removed {
+            println!(
+                "  {} {} = {}",
+                "<".red(),
+                key.red(),
+                display_value(value, show_values).bright_black()
+            );
+        }
+        println!();
+    }
+
+    if !diff.added.is_empty() {
+        println!("{}", format!("Only in {}:", right_label).bold());
+        for (key, value) in &diff.added {
+            println!(
+                "  {} {} = {}",
+                ">".green(),
+                key.green(),
+                display_value(value, show_values).bright_black()
+            );
+        }
+        println!();
+    }
+
+    if !diff.changed.is_empty() {
+        println!("{}", "Changed:".bold());
+        for (key, (left_value, right_value)) in &diff.changed {
+            println!(
+                "  {} {}: {} → {}",
+                "~".yellow(),
+                key.yellow(),
+                display_value(left_value, show_values).bright_black(),
+                display_value(right_value, show_values).bright_black()
+            );
+        }
+        println!();
+    }
+
+    status!(
+        "{}",
+        format!(
+            "  {} only in {}, {} only in {}, {} changed, {} unchanged",
+            diff.removed.len(),
+            left_label,
+            diff.added.len(),
+            right_label,
+            diff.changed.len(),
+            diff.unchanged
+        )
+        .bright_black()
+    );
+
+    Ok(true)
+}
@@ -0,0 +1,155 @@
+use crate::config::Config;
+use crate::status;
+use crate::storage::{EnvStorage, SegmentState, SegmentUsage, SessionKey};
+use crate::utils::output::{self, format_size};
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+use serde_json::json;
+use std::time::Duration;
+
+#[derive(Debug, Serialize)]
+struct Check {
+    name: &'static str,
+    ok: bool,
+    detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, ok: bool, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            ok,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Check the local setup and report shared memory segments nothing uses any more
+pub async fn execute() -> Result<()> {
+    let config = Config::load()?;
+    let max_age = Duration::from_secs(config.cache_max_age);
+    let segments = EnvStorage::usage(max_age)?;
+
+    let checks = vec![
+        match config.token {
+            Some(_) => Check::new("login", true, "Logged in"),
+            None => Check::new("login", false, "Not logged in. Run 'envsafe login'"),
+        },
+        session_key(),
+        agent().await,
+        cache(&segments),
+        orphans(&segments),
+    ];
+
+    if output::is_machine() {
+        return output::emit(&json!({
+            "checks": checks,
+            "segments": segments,
+        }));
+    }
+
+    status!("{}", "🩺 EnvSafe doctor".cyan().bold());
+    status!();
+    for check in &checks {
+        let mark = if check.ok {
+            "✓".green()
+        } else {
+            "⚠".yellow()
+        };
+        println!("  {} {}: {}", mark, check.name, check.detail);
+    }
+
+    let orphaned: Vec<&SegmentUsage> = segments.iter().filter(|u| u.is_orphaned()).collect();
+    if !orphaned.is_empty() {
+        status!();
+        for usage in orphaned {
+            println!(
+                "    {} {} {}",
+                "•".yellow(),
+                usage.label(),
+                format!("({}, {})", usage.state.as_str(), format_size(usage.size)).bright_black()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn session_key() -> Check {
+    let path = match SessionKey::path() {
+        Ok(path) => path,
+        Err(e) => return Check::new("session key", false, e.to_string()),
+    };
+    if !path.exists() {
+        return Check::new("session key", true, "Created on first use");
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            if metadata.permissions().mode() & 0o077 != 0 {
+                return Check::new(
+                    "session key",
+                    false,
+                    format!("{} is readable by other users", path.display()),
+                );
+            }
+        }
+    }
+    Check::new("session key", true, path.display().to_string())
+}
+
+#[cfg(unix)]
+async fn agent() -> Check {
+    use crate::agent::{self, AgentClient};
+
+    let running = match agent::socket_path() {
+        Ok(path) => matches!(AgentClient::connect(&path).await, Ok(Some(_))),
+        Err(_) => false,
+    };
+    // Optional: not running is not a problem
+    let detail = if running { "Running" } else { "Not running" };
+    Check::new("agent", true, detail)
+}
+
+#[cfg(not(unix))]
+async fn agent() -> Check {
+    Check::new("agent", true, "Not available on this platform")
+}
+
+fn cache(segments: &[SegmentUsage]) -> Check {
+    let held = segments
+        .iter()
+        .filter(|u| u.state == SegmentState::Held)
+        .count();
+    let size: u64 = segments.iter().map(|u| u.size).sum();
+    Check::new(
+        "cache",
+        true,
+        format!(
+            "{} segment(s), {} watched, {} in shared memory",
+            segments.len(),
+            held,
+            format_size(size)
+        ),
+    )
+}
+
+fn orphans(segments: &[SegmentUsage]) -> Check {
+    let orphaned: Vec<&SegmentUsage> = segments.iter().filter(|u| u.is_orphaned()).collect();
+    if orphaned.is_empty() {
+        return Check::new("orphaned segments", true, "None");
+    }
+    let size: u64 = orphaned.iter().map(|u| u.size).sum();
+    Check::new(
+        "orphaned segments",
+        false,
+        format!(
+            "{} segment(s) using {}. Run 'envsafe cache purge'",
+            orphaned.len(),
+            format_size(size)
+        ),
+    )
+}
@@ -0,0 +1,236 @@
+use super::cache;
+use crate::api::ApiClient;
+use crate::config::Config;
+use crate::status;
+use crate::utils::output;
+use crate::utils::prompt::confirm;
+use crate::utils::target::{validate_environment, RemoteTarget};
+use anyhow::Result;
+use colored::*;
+use std::collections::BTreeMap;
+
+/// List the environments of a project
+pub async fn list(project: Option<String>) -> Result<()> {
+    let config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+    let project = RemoteTarget::resolve_project(project)?;
+
+    let environments = api_client.get_environments(&token, &project).await?;
+
+    if output::is_machine() {
+        return output::emit(&environments);
+    }
+
+    status!(
+        "{}",
+        format!("🌍 Environments of {}", project).cyan().bold()
+    );
+    status!();
+
+    if environments.is_empty() {
+        status!("{}", "  No environments".bright_black());
+        return Ok(());
+    }
+
+    for environment in &environments {
+        let count = environment
+            .variable_count
+            .map(|n| format!("{} variables", n))
+            .unwrap_or_default();
+        println!(
+            "  {} {} {}",
+            "•".cyan(),
+            environment.name.bright_white(),
+            count.bright_black()
+        );
+
+        let aliases: Vec<&str> = config
+            .env_aliases
+            .iter()
+            .filter(|(_, target)| **target == environment.name)
+            .map(|(alias, _)| alias.as_str())
+            .collect();
+        if !aliases.is_empty() {
+            status!(
+                "    {}",
+                format!("aliases: {}", aliases.join(", ")).bright_black()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Create an empty environment, or a copy of `from`
+pub async fn create(project: Option<String>, name: &str, from: Option<&str>) -> Result<()> {
+    let config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+    let project = RemoteTarget::resolve_project(project)?;
+    let from = from.map(|source| config.resolve_environment(source));
+
+    if let Some(source) = &from {
+        validate_environment(&api_client, &token, &project, source).await?;
+    }
+
+    let environment = api_client
+        .create_environment(&token, &project, name, from.as_deref())
+        .await?;
+
+    if output::is_machine() {
+        return output::emit(&environment);
+    }
+
+    match from {
+        Some(source) => status!(
+            "{}",
+            format!("✓ Created {}/{} from {}", project, environment.name, source).green()
+        ),
+        None => status!(
+            "{}",
+            format!("✓ Created {}/{}", project, environment.name).green()
+        ),
+    }
+
+    Ok(())
+}
+
+/// Delete an environment and all of its variables
+pub async fn delete(project: Option<String>, name: &str, yes: bool) -> Result<()> {
+    let config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+    let project = RemoteTarget::resolve_project(project)?;
+
+    validate_environment(&api_client, &token, &project, name).await?;
+
+    status!(
+        "{}",
+        format!(
+            "⚠️  This permanently deletes {}/{} and all of its variables",
+            project, name
+        )
+        .yellow()
+    );
+
+    let prompt = format!("Delete environment {}?", name);
+    if !confirm(&prompt, yes)? {
+        status!("{}", "✗ Deletion cancelled".yellow());
+        if output::is_machine() {
+            return output::emit(&serde_json::json!({
+                "project": project,
+                "environment": name,
+                "deleted": false,
+            }));
+        }
+        return Ok(());
+    }
+
+    api_client
+        .delete_environment(&token, &project, name)
+        .await?;
+
+    status!("{}", format!("✓ Deleted {}/{}", project, name).green());
+    cache::forget_deleted(&config, &project, Some(name));
+
+    if output::is_machine() {
+        return output::emit(&serde_json::json!({
+            "project": project,
+            "environment": name,
+            "deleted": true,
+        }));
+    }
+
+    Ok(())
+}
+
+/// Rename an environment, keeping local aliases pointing at it
+pub async fn rename(project: Option<String>, name: &str, new_name: &str) -> Result<()> {
+    let mut config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+    let project = RemoteTarget::resolve_project(project)?;
+
+    validate_environment(&api_client, &token, &project, name).await?;
+
+    api_client
+        .rename_environment(&token, &project, name, new_name)
+        .await?;
+
+    let mut updated = false;
+    for target in config.env_aliases.values_mut() {
+        if target == name {
+            *target = new_name.to_string();
+            updated = true;
+        }
+    }
+    if updated {
+        config.save()?;
+    }
+
+    status!(
+        "{}",
+        format!("✓ Renamed {}/{} to {}", project, name, new_name).green()
+    );
+
+    if output::is_machine() {
+        return output::emit(&serde_json::json!({
+            "project": project,
+            "environment": new_name,
+            "previous_name": name,
+        }));
+    }
+
+    Ok(())
+}
+
+/// Manage local environment aliases: list them, add `alias -> target`, or remove one
+pub async fn alias(alias: Option<String>, target: Option<String>, remove: bool) -> Result<()> {
+    let mut config = Config::load()?;
+
+    match (alias, target) {
+        (Some(alias), _) if remove => {
+            if config.env_aliases.remove(&alias).is_none() {
+                anyhow::bail!("Alias '{}' does not exist", alias);
+            }
+            config.save()?;
+            status!("{}", format!("✓ Removed alias {}", alias).green());
+            if output::is_machine() {
+                output::emit(&config.env_aliases)?;
+            }
+        }
+        (Some(alias), Some(target)) => {
+            config.env_aliases.insert(alias.clone(), target.clone());
+            config.save()?;
+            status!("{}", format!("✓ {} → {}", alias, target).green());
+            if output::is_machine() {
+                output::emit(&config.env_aliases)?;
+            }
+        }
+        (Some(alias), None) if output::is_machine() => {
+            let target = config.resolve_environment(&alias);
+            output::emit(&BTreeMap::from([(alias, target)]))?;
+        }
+        (Some(alias), None) => {
+            println!("{}", config.resolve_environment(&alias));
+        }
+        (None, _) if output::is_machine() => {
+            output::emit(&config.env_aliases)?;
+        }
+        (None, _) => {
+            status!("{}", "🔖 Environment aliases".cyan().bold());
+            status!();
+            status!(
+                "{}",
+                "  dev → development, staging → staging, prod → production (built-in)"
+                    .bright_black()
+            );
+            for (alias, target) in &config.env_aliases {
+                println!("  {} → {}", alias.bright_white(), target);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,228 @@
+use crate::api::{is_conflict, ApiClient, EnvVariable, SnapshotPoint};
+use crate::config::Config;
+use crate::status;
+use crate::utils::diff::EnvDiff;
+use crate::utils::output;
+use crate::utils::prompt::confirm;
+use crate::utils::target::RemoteTarget;
+use anyhow::Result;
+use chrono::Utc;
+use colored::*;
+use std::collections::HashMap;
+
+/// Show past versions of an environment, or of a single key
+pub async fn list(
+    project: Option<String>,
+    environment: Option<String>,
+    key: Option<String>,
+    limit: usize,
+) -> Result<()> {
+    let config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+    let target = RemoteTarget::resolve(project, environment)?;
+    target.validate(&api_client, &token).await?;
+
+    let mut entries = api_client
+        .get_history(&token, &target.project, &target.environment, key.as_deref())
+        .await?;
+
+    // Newest first
+    entries.sort_by_key(|e| std::cmp::Reverse(e.version));
+
+    if output::is_machine() {
+        entries.truncate(limit);
+        return output::emit(&entries);
+    }
+
+    status!("{}", format!("📜 History of {}", target).cyan().bold());
+    if let Some(key) = &key {
+        status!("{}", format!("  Key: {}", key).bright_black());
+    }
+    status!();
+
+    if entries.is_empty() {
+        status!("{}", "  No history found".bright_black());
+        return Ok(());
+    }
+
+    for entry in entries.iter().take(limit) {
+        let action = match entry.action.as_str() {
+            "created" => entry.action.green(),
+            "deleted" => entry.action.red(),
+            _ => entry.action.yellow(),
+        };
+        println!(
+            "  {} {} {:<8} {} {}",
+            format!("v{:<5}", entry.version).cyan(),
+            entry.created_at.bright_black(),
+            action,
+            entry.key.bright_white(),
+            entry
+                .author
+                .as_deref()
+                .map(|a| format!("by {}", a))
+                .unwrap_or_default()
+                .bright_black()
+        );
+    }
+
+    if entries.len() > limit {
+        status!();
+        status!(
+            "{}",
+            format!(
+                "  … {} older entries (use --limit to show more)",
+                entries.len() - limit
+            )
+            .bright_black()
+        );
+    }
+
+    status!();
+    status!(
+        "{}",
+        "Restore with: envsafe rollback --to <version|timestamp>".bright_black()
+    );
+
+    Ok(())
+}
+
+/// Restore an environment (or some of its keys) to a previous snapshot
+pub async fn rollback(
+    project: Option<String>,
+    environment: Option<String>,
+    to: SnapshotPoint,
+    keys: Vec<String>,
+    show_values: bool,
+    yes: bool,
+) -> Result<()> {
+    let config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+    let target = RemoteTarget::resolve(project, environment)?;
+    target.validate(&api_client, &token).await?;
+
+    status!("{}", "⏪ Rolling back environment variables...".cyan());
+    status!("{}", format!("  Target: {}", target).bright_black());
+
+    let snapshot = api_client
+        .get_snapshot(&token, &target.project, &target.environment, &to)
+        .await?;
+    let remote = api_client
+        .get_environment(&token, &target.project, &target.environment)
+        .await?;
+    let current: HashMap<String, String> = remote
+        .variables
+        .into_iter()
+        .map(|v| (v.key, v.value))
+        .collect();
+
+    status!(
+        "{}",
+        format!(
+            "  Snapshot: v{}{}",
+            snapshot.version,
+            snapshot
+                .created_at
+                .as_deref()
+                .map(|at| format!(" ({})", at))
+                .unwrap_or_default()
+        )
+        .bright_black()
+    );
+
+    let desired = if keys.is_empty() {
+        snapshot.variables
+    } else {
+        // Only the selected keys go back; a key absent from the snapshot is removed
+        let mut desired = current.clone();
+        for key in &keys {
+            match snapshot.variables.get(key) {
+                Some(value) => {
+                    desired.insert(key.clone(), value.clone());
+                }
+                None => {
+                    desired.remove(key);
+                }
+            }
+        }
+        desired
+    };
+
+    let diff = EnvDiff::compute(&current, &desired);
+
+    status!();
+    diff.print(show_values);
+    status!();
+    status!("{}", format!("  {}", diff.summary()).bright_black());
+
+    if diff.is_empty() {
+        status!("{}", "✓ Already at this version".green());
+        if output::is_machine() {
+            return emit_rollback(&target, snapshot.version, &diff);
+        }
+        return Ok(());
+    }
+
+    let prompt = format!("Restore {} to v{}?", target, snapshot.version);
+    if !confirm(&prompt, yes)? {
+        status!("{}", "✗ Rollback cancelled".yellow());
+        return output::cancelled();
+    }
+
+    let variables: Vec<EnvVariable> = desired
+        .into_iter()
+        .map(|(key, value)| EnvVariable {
+            key,
+            value,
+            updated_at: Some(Utc::now().to_rfc3339()),
+        })
+        .collect();
+
+    // One version-checked update: keys left out of the map are removed with the rest
+    api_client
+        .update_variables_if_match(
+            &token,
+            &target.project,
+            &target.environment,
+            variables,
+            remote.version.as_deref(),
+        )
+        .await
+        .map_err(|e| {
+            if is_conflict(&e) {
+                anyhow::anyhow!(
+                    "{} changed while rolling back. Review the new diff and retry",
+                    target
+                )
+            } else {
+                e
+            }
+        })?;
+
+    status!(
+        "{}",
+        format!("✓ Rolled back {} to v{}", target, snapshot.version).green()
+    );
+
+    if output::is_machine() {
+        return emit_rollback(&target, snapshot.version, &diff);
+    }
+    status!(
+        "{}",
+        "  Run 'envsafe pull' to update your local file".bright_black()
+    );
+
+    Ok(())
+}
+
+/// The json/yaml document of a rollback, also written when nothing had to change
+fn emit_rollback(target: &RemoteTarget, version: u64, diff: &EnvDiff) -> Result<()> {
+    output::emit(&serde_json::json!({
+        "project": target.project,
+        "environment": target.environment,
+        "version": version,
+        "changes": diff.key_summary(),
+    }))
+}
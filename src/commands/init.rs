@@ -1,20 +1,40 @@
-use crate::api::ApiClient;
+use super::link::find_workspace;
+use crate::api::{ApiClient, Project};
 use crate::config::{Config, ProjectConfig};
+use crate::status;
 use crate::utils::i18n::get_translations;
+use crate::utils::output;
+use crate::utils::prompt::require_interactive;
+use crate::utils::suggest::not_found;
+use crate::utils::target::validate_environment;
 use anyhow::Result;
 use colored::*;
 
-pub async fn execute() -> Result<()> {
+/// Link the current directory to a project.
+///
+/// Every value that is not passed as an argument is asked for interactively.
+pub async fn execute(
+    workspace: Option<String>,
+    project: Option<String>,
+    environment: Option<String>,
+    create: bool,
+) -> Result<()> {
     let mut config = Config::load()?;
     let t = get_translations(&config.language);
 
     let token = config.get_token()?;
     let api_client = ApiClient::from_config(&config);
 
-    println!("{}", t.init.title.cyan().bold());
-    println!();
+    status!("{}", t.init.title.cyan().bold());
+    status!();
 
-    let (workspace_slug, workspace_id) = if let Some(ws_slug) = &config.current_workspace_slug {
+    let (workspace_slug, workspace_id) = if let Some(query) = workspace {
+        let workspaces = api_client.get_workspaces(&token).await?;
+        let selected = find_workspace(workspaces, &query)?;
+        let ws_slug = selected.slug.clone().unwrap_or(selected.id.clone());
+        config.set_workspace(&selected.id, Some(&ws_slug))?;
+        (ws_slug, selected.id)
+    } else if let Some(ws_slug) = &config.current_workspace_slug {
         (ws_slug.clone(), config.current_workspace.clone().unwrap())
     } else if let Some(ws_id) = &config.current_workspace {
         // Fallback for legacy config
@@ -28,6 +48,11 @@ pub async fn execute() -> Result<()> {
 
         let items: Vec<String> = workspaces.iter().map(|w| w.name.clone()).collect();
 
+        require_interactive(
+            "a workspace",
+            "Pass --workspace <slug> or run 'envsafe link <workspace>' first",
+        )?;
+
         let selection = dialoguer::Select::new()
             .with_prompt(t.common.select_workspace)
             .items(&items)
@@ -42,38 +67,94 @@ pub async fn execute() -> Result<()> {
 
     let projects = api_client.get_projects(&token, &workspace_slug).await?;
 
-    if projects.is_empty() {
-        println!("{}", t.common.no_projects.yellow());
-        // Note: Creating a project message isn't translated yet in i18n struct, using generic fallback or keeping English for now on this specific hint line
-        // Or better, let's just stick to translated "no projects" message.
-        return Ok(());
-    }
-
-    let items: Vec<String> = projects.iter().map(|p| p.name.clone()).collect();
-
-    let selection = dialoguer::Select::new()
-        .with_prompt(t.common.select_project)
-        .items(&items)
-        .interact()?;
-
-    let selected_project = &projects[selection];
+    let (selected_project, created) = if let Some(query) = project {
+        let names: Vec<String> = projects
+            .iter()
+            .map(|p| p.slug.clone().unwrap_or_else(|| p.name.clone()))
+            .collect();
+
+        match projects.into_iter().find(|p| p.matches(&query)) {
+            Some(found) => (found, false),
+            None if create => {
+                let created = api_client
+                    .create_project(&token, &workspace_slug, &query)
+                    .await?;
+                status!("{}", format!("✓ Created project: {}", created.name).green());
+                (created, true)
+            }
+            None => {
+                let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+                anyhow::bail!(
+                    "{} (pass --create to create it)",
+                    not_found("Project", &query, &names)
+                );
+            }
+        }
+    } else {
+        if projects.is_empty() {
+            status!("{}", t.common.no_projects.yellow());
+            // Note: Creating a project message isn't translated yet in i18n struct, using generic fallback or keeping English for now on this specific hint line
+            // Or better, let's just stick to translated "no projects" message.
+            return Ok(());
+        }
 
-    let project_config = ProjectConfig {
-        workspace_id: workspace_id.clone(),
-        project_id: selected_project.id.clone(),
-        project_name: selected_project.name.clone(),
-        project_slug: selected_project.slug.clone(),
+        (select_project(projects, t.common.select_project)?, false)
     };
 
+    let mut project_config = ProjectConfig::load_in(&std::env::current_dir()?)?;
+    project_config.link(&workspace_id, &selected_project);
+
+    if let Some(env) = environment {
+        let env = config.resolve_environment(&env);
+        // A project created a moment ago only has the server's default environments
+        if !created {
+            let slug = selected_project
+                .slug
+                .clone()
+                .unwrap_or(selected_project.id.clone());
+            validate_environment(&api_client, &token, &slug, &env).await?;
+        }
+        project_config.default_environment = Some(env);
+    }
+
     project_config.save()?;
 
-    println!();
-    println!(
+    status!();
+    status!(
         "{} {}",
         "✓".green(),
         format!("{} : {}", t.init.success, selected_project.name).green()
     );
-    println!("{}", t.init.creating_config.bright_black());
+    if let Some(env) = &project_config.default_environment {
+        status!(
+            "{}",
+            format!("  Default environment: {}", env).bright_black()
+        );
+    }
+    status!("{}", t.init.creating_config.bright_black());
+
+    if output::is_machine() {
+        return output::emit(&project_config);
+    }
 
     Ok(())
 }
+
+fn select_project(projects: Vec<Project>, prompt: &str) -> Result<Project> {
+    let items: Vec<String> = projects.iter().map(|p| p.name.clone()).collect();
+
+    require_interactive(
+        "a project",
+        "Pass --project <slug>, or run 'envsafe select <project>' instead",
+    )?;
+
+    let selection = dialoguer::Select::new()
+        .with_prompt(prompt)
+        .items(&items)
+        .interact()?;
+
+    projects
+        .into_iter()
+        .nth(selection)
+        .ok_or_else(|| anyhow::anyhow!("Invalid selection"))
+}
@@ -1,30 +1,42 @@
+use crate::config::Config;
+use crate::status;
+use crate::utils::output;
+use crate::utils::prompt::require_interactive;
 use anyhow::Result;
 use colored::*;
-use crate::config::Config;
 
 pub async fn execute(language: Option<String>) -> Result<()> {
     let mut config = Config::load()?;
-    
+
     let lang = if let Some(l) = language {
         l
     } else {
+        require_interactive("a language", "Pass it as an argument: envsafe lang <en|fr>")?;
         let selection = dialoguer::Select::new()
             .with_prompt("Select language / Sélectionner la langue")
             .items(&["English (en)", "Français (fr)"])
             .default(0)
             .interact()?;
-        
-        if selection == 0 { "en".to_string() } else { "fr".to_string() }
+
+        if selection == 0 {
+            "en".to_string()
+        } else {
+            "fr".to_string()
+        }
     };
-    
+
     config.set_language(&lang)?;
-    
+
     let message = match lang.as_str() {
         "fr" => "✓ Langue changée en français",
         _ => "✓ Language changed to English",
     };
-    
-    println!("{}", message.green());
-    
+
+    status!("{}", message.green());
+
+    if output::is_machine() {
+        return output::emit(&serde_json::json!({ "language": lang }));
+    }
+
     Ok(())
 }
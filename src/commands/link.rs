@@ -1,43 +1,80 @@
+use crate::api::{ApiClient, Workspace};
+use crate::config::Config;
+use crate::status;
+use crate::utils::output;
+use crate::utils::prompt::require_interactive;
+use crate::utils::suggest::not_found;
 use anyhow::Result;
 use colored::*;
-use crate::config::Config;
-use crate::api::ApiClient;
 
 pub async fn execute(workspace: Option<String>) -> Result<()> {
     let mut config = Config::load()?;
     let token = config.get_token()?;
     let api_client = ApiClient::from_config(&config);
-    
-    println!("{}", "🔗 Link Workspace".cyan().bold());
-    println!();
-    
+
+    status!("{}", "🔗 Link Workspace".cyan().bold());
+    status!();
+
     let (workspace_id, workspace_slug) = if let Some(ws) = workspace {
-        (ws.clone(), None)
+        let workspaces = api_client.get_workspaces(&token).await?;
+        let selected = find_workspace(workspaces, &ws)?;
+        (selected.id, selected.slug)
     } else {
         // Fetch available workspaces
         let workspaces = api_client.get_workspaces(&token).await?;
-        
+
         if workspaces.is_empty() {
             anyhow::bail!("No workspaces found");
         }
-        
+
         let items: Vec<String> = workspaces
             .iter()
             .map(|w| format!("{} ({})", w.name, w.id))
             .collect();
-        
+
+        require_interactive(
+            "a workspace",
+            "Pass it as an argument: envsafe link <workspace>",
+        )?;
+
         let selection = dialoguer::Select::new()
             .with_prompt("Select a workspace")
             .items(&items)
             .interact()?;
-        
+
         let selected = &workspaces[selection];
         (selected.id.clone(), selected.slug.clone())
     };
-    
+
     config.set_workspace(&workspace_id, workspace_slug.as_deref())?;
-    
-    println!("{}", format!("✓ Linked to workspace: {}", workspace_id).green());
-    
+
+    status!(
+        "{}",
+        format!("✓ Linked to workspace: {}", workspace_id).green()
+    );
+
+    if output::is_machine() {
+        return output::emit(&serde_json::json!({
+            "workspace_id": workspace_id,
+            "workspace_slug": workspace_slug,
+        }));
+    }
+
     Ok(())
 }
+
+/// Pick the workspace named by ID, slug or name
+pub fn find_workspace(workspaces: Vec<Workspace>, query: &str) -> Result<Workspace> {
+    let names: Vec<String> = workspaces
+        .iter()
+        .map(|w| w.slug.clone().unwrap_or_else(|| w.name.clone()))
+        .collect();
+
+    workspaces
+        .into_iter()
+        .find(|w| w.matches(query))
+        .ok_or_else(|| {
+            let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+            not_found("Workspace", query, &names)
+        })
+}
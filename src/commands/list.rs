@@ -1,31 +1,53 @@
+use crate::api::ApiClient;
+use crate::config::Config;
+use crate::status;
+use crate::utils::output::{self, OutputFormat};
 use anyhow::Result;
 use colored::*;
-use crate::config::Config;
-use crate::api::ApiClient;
 
 pub async fn execute() -> Result<()> {
     let config = Config::load()?;
     let token = config.get_token()?;
-    
-    let workspace_id = config.current_workspace
+
+    let workspace_id = config
+        .current_workspace
         .as_ref()
         .ok_or_else(|| anyhow::anyhow!("No workspace linked. Run 'envsafe link' first"))?;
-    
+
     let api_client = ApiClient::from_config(&config);
     let projects = api_client.get_projects(&token, workspace_id).await?;
-    
-    println!("{}", "📋 Projects".cyan().bold());
-    println!();
-    
+
+    if output::is_machine() {
+        return output::emit(&projects);
+    }
+
+    if output::format() == OutputFormat::Table {
+        let rows: Vec<Vec<String>> = projects
+            .iter()
+            .map(|p| {
+                vec![
+                    p.name.clone(),
+                    p.slug.clone().unwrap_or_default(),
+                    p.id.clone(),
+                ]
+            })
+            .collect();
+        output::print_table(&["name", "slug", "id"], &rows);
+        return Ok(());
+    }
+
+    status!("{}", "📋 Projects".cyan().bold());
+    status!();
+
     if projects.is_empty() {
-        println!("{}", "  No projects found".bright_black());
+        status!("{}", "  No projects found".bright_black());
         return Ok(());
     }
-    
+
     for project in projects {
         println!("  {} {}", "•".cyan(), project.name.bright_white());
-        println!("    {}", project.id.bright_black());
+        status!("    {}", project.id.bright_black());
     }
-    
+
     Ok(())
 }
@@ -1,5 +1,8 @@
 use crate::api::ApiClient;
 use crate::config::Config;
+use crate::status;
+use crate::utils::output;
+use crate::utils::prompt::{is_interactive, require_interactive};
 use anyhow::Result;
 use colored::*;
 use dialoguer::Input;
@@ -7,13 +10,18 @@ use dialoguer::Input;
 pub async fn execute(token: Option<String>) -> Result<()> {
     let mut config = Config::load()?;
 
-    println!("{}", "🔐 EnvSafe Login".cyan().bold());
-    println!();
+    status!("{}", "🔐 EnvSafe Login".cyan().bold());
+    status!();
 
     let token = if let Some(t) = token {
         t
     } else {
-        println!(
+        require_interactive(
+            "an API token",
+            "Pass it with 'envsafe login --token <token>'",
+        )?;
+
+        status!(
             "{}",
             "Opening browser to generate API token...".bright_black()
         );
@@ -21,12 +29,12 @@ pub async fn execute(token: Option<String>) -> Result<()> {
         let dashboard_url = format!("{}/dashboard/settings/tokens", config.dashboard_url);
 
         if open::that(&dashboard_url).is_ok() {
-            println!("{}", format!("✓ Browser opened: {}", dashboard_url).green());
+            status!("{}", format!("✓ Browser opened: {}", dashboard_url).green());
         } else {
-            println!("{}", format!("Please visit: {}", dashboard_url).yellow());
+            status!("{}", format!("Please visit: {}", dashboard_url).yellow());
         }
 
-        println!();
+        status!();
 
         Input::<String>::new()
             .with_prompt("Enter your API token")
@@ -35,33 +43,38 @@ pub async fn execute(token: Option<String>) -> Result<()> {
 
     let api_client = ApiClient::from_config(&config);
 
-    print!("{}", "Verifying token... ".bright_black());
+    status!("{}", "Verifying token...".bright_black());
 
     match api_client.get_user(&token).await {
         Ok(user) => {
-            println!("{}", "✓".green());
-            println!();
-            println!("{}", format!("Welcome, {}!", user.name).green().bold());
-            println!("{}", format!("Email: {}", user.email).bright_black());
+            status!();
+            status!("{}", format!("Welcome, {}!", user.name).green().bold());
+            status!("{}", format!("Email: {}", user.email).bright_black());
 
             config.set_token(token)?;
 
-            println!();
-            let lang = dialoguer::Select::new()
-                .with_prompt("Preferred language / Langue préférée")
-                .items(&["English (en)", "Français (fr)"])
-                .default(0)
-                .interact()?;
+            // Keep the current language when nobody is there to choose
+            if is_interactive() {
+                status!();
+                let lang = dialoguer::Select::new()
+                    .with_prompt("Preferred language / Langue préférée")
+                    .items(&["English (en)", "Français (fr)"])
+                    .default(0)
+                    .interact()?;
+
+                let lang_code = if lang == 0 { "en" } else { "fr" };
+                config.set_language(lang_code)?;
+            }
 
-            let lang_code = if lang == 0 { "en" } else { "fr" };
-            config.set_language(lang_code)?;
+            status!();
+            status!("{}", "✓ Successfully logged in!".green());
 
-            println!();
-            println!("{}", "✓ Successfully logged in!".green());
+            if output::is_machine() {
+                output::emit(&user)?;
+            }
         }
         Err(e) => {
-            println!("{}", "✗".red());
-            println!();
+            status!();
             eprintln!("{}", format!("Login failed: {}", e).red());
             anyhow::bail!("Invalid token");
         }
@@ -1,12 +1,65 @@
+use crate::config::Config;
+use crate::status;
+use crate::storage::{EnvStorage, SegmentState};
+use crate::utils::output;
 use anyhow::Result;
 use colored::*;
-use crate::config::Config;
+use std::time::Duration;
 
 pub async fn execute() -> Result<()> {
     let mut config = Config::load()?;
     config.clear_token()?;
-    
-    println!("{}", "✓ Successfully logged out".green());
-    
+
+    status!("{}", "✓ Successfully logged out".green());
+
+    // Cached variables must not outlive the session. Segments a running `watch`, `agent` or
+    // `serve` holds are left to them: removing them would not stop the process.
+    let purged = match EnvStorage::purge(Duration::ZERO, |usage| usage.cleared_on_logout()) {
+        Ok(purged) => purged,
+        Err(e) => {
+            status!(
+                "{}",
+                format!(
+                    "⚠ Could not clear the cache: {}. Run 'envsafe cache purge --all'",
+                    e
+                )
+                .yellow()
+            );
+            Vec::new()
+        }
+    };
+    if !purged.is_empty() {
+        status!(
+            "{}",
+            format!("✓ Removed {} cached environment(s)", purged.len()).green()
+        );
+    }
+    let held = EnvStorage::usage(Duration::ZERO)
+        .map(|usage| {
+            usage
+                .iter()
+                .filter(|u| u.state == SegmentState::Held)
+                .count()
+        })
+        .unwrap_or(0);
+    if held > 0 {
+        status!(
+            "{}",
+            format!(
+                "⚠ {} cached environment(s) kept: still watched by a running 'envsafe watch', 'agent' or 'serve'. Stop them to clear it",
+                held
+            )
+            .yellow()
+        );
+    }
+
+    if output::is_machine() {
+        return output::emit(&serde_json::json!({
+            "logged_out": true,
+            "purged": purged,
+            "held": held,
+        }));
+    }
+
     Ok(())
 }
@@ -2,6 +2,7 @@
 
 use crate::api::ApiClient;
 use crate::config::Config;
+use crate::status;
 use anyhow::Result;
 use colored::Colorize;
 
@@ -12,8 +13,8 @@ pub async fn execute(
     workspace_id: Option<String>,
     project_id: Option<String>,
 ) -> Result<()> {
-    println!("{}", "🤖 M2M Authentication".cyan().bold());
-    println!();
+    status!("{}", "🤖 M2M Authentication".cyan().bold());
+    status!();
 
     // Get token from argument or environment variable
     let m2m_token = if let Some(t) = token {
@@ -32,11 +33,11 @@ pub async fn execute(
     let mut config = Config::load()?;
     let api_client = ApiClient::from_config(&config);
 
-    println!("{}", "Validating M2M token...".dimmed());
+    status!("{}", "Validating M2M token...".dimmed());
 
     let user = api_client.get_user(&m2m_token).await?;
 
-    println!("{}", format!("✓ Authenticated as: {}", user.name).green());
+    status!("{}", format!("✓ Authenticated as: {}", user.name).green());
 
     // Save token
     config.set_token(m2m_token.clone())?;
@@ -44,25 +45,25 @@ pub async fn execute(
     // Optionally set workspace and project
     if let Some(ws_id) = workspace_id.or_else(|| std::env::var("ENVSAFE_WORKSPACE_ID").ok()) {
         config.current_workspace = Some(ws_id.clone());
-        println!("{}", format!("✓ Workspace set: {}", ws_id).green());
+        status!("{}", format!("✓ Workspace set: {}", ws_id).green());
     }
 
     if let Some(proj_id) = project_id.or_else(|| std::env::var("ENVSAFE_PROJECT_ID").ok()) {
         config.current_project = Some(proj_id.clone());
-        println!("{}", format!("✓ Project set: {}", proj_id).green());
+        status!("{}", format!("✓ Project set: {}", proj_id).green());
     }
 
     config.save()?;
 
-    println!();
-    println!("{}", "✓ M2M authentication successful".green().bold());
-    println!();
-    println!(
+    status!();
+    status!("{}", "✓ M2M authentication successful".green().bold());
+    status!();
+    status!(
         "{}",
         "You can now use CLI commands non-interactively:".dimmed()
     );
-    println!("{}", "  envsafe pull --prod".dimmed());
-    println!("{}", "  envsafe run --prod -- your-command".dimmed());
+    status!("{}", "  envsafe pull --prod".dimmed());
+    status!("{}", "  envsafe run --prod -- your-command".dimmed());
 
     Ok(())
 }
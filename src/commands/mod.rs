@@ -1,17 +1,29 @@
-pub mod login;
-pub mod whoami;
-pub mod link;
+pub mod agent;
+pub mod audit;
+pub mod cache;
+pub mod config;
+pub mod create;
+pub mod diff;
+pub mod doctor;
+pub mod env;
+pub mod history;
 pub mod init;
+pub mod lang;
+pub mod link;
 pub mod list;
-pub mod select;
-pub mod create;
+pub mod login;
+pub mod logout;
+pub mod m2m;
+pub mod project;
 pub mod projects;
+pub mod promote;
 pub mod pull;
 pub mod push;
+pub mod rotate;
 pub mod run;
+pub mod select;
+pub mod serve;
+pub mod variable;
 pub mod watch;
-pub mod rotate;
-pub mod config;
-pub mod lang;
-pub mod logout;
-pub mod m2m;
+pub mod whoami;
+pub mod workspace;
@@ -0,0 +1,196 @@
+use super::cache;
+use crate::api::{ApiClient, Project, ProjectUpdate};
+use crate::config::{Config, ProjectConfig};
+use crate::status;
+use crate::utils::output;
+use crate::utils::prompt::confirm;
+use crate::utils::target::RemoteTarget;
+use anyhow::Result;
+use colored::*;
+
+/// Show a project's details, or change its description with `set_description`
+pub async fn describe(
+    project: Option<String>,
+    set_description: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+    let identifier = RemoteTarget::resolve_project(project)?;
+
+    let project = match set_description {
+        Some(description) => {
+            let update = ProjectUpdate {
+                description: Some(description),
+                ..Default::default()
+            };
+            api_client
+                .update_project(&token, &identifier, &update)
+                .await?
+        }
+        None => api_client.get_project(&token, &identifier).await?,
+    };
+
+    if json || output::is_machine() {
+        return output::emit(&project);
+    }
+
+    print_project(&project);
+
+    Ok(())
+}
+
+pub async fn rename(project: Option<String>, new_name: &str, json: bool) -> Result<()> {
+    let config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+    let identifier = RemoteTarget::resolve_project(project)?;
+
+    let update = ProjectUpdate {
+        name: Some(new_name.to_string()),
+        ..Default::default()
+    };
+    let project = api_client
+        .update_project(&token, &identifier, &update)
+        .await?;
+
+    // Keep the directory link in sync when renaming the linked project
+    if let Some(mut local_config) = ProjectConfig::load()? {
+        // Services live in the monorepo's root file, which is edited by hand
+        if local_config.service.is_none() && is_linked(&local_config, &identifier) {
+            local_config.project_name = project.name.clone();
+            if project.slug.is_some() {
+                local_config.project_slug = project.slug.clone();
+            }
+            local_config.save()?;
+        }
+    }
+
+    if json || output::is_machine() {
+        return output::emit(&project);
+    }
+
+    status!(
+        "{}",
+        format!("✓ Renamed {} to {}", identifier, project.name).green()
+    );
+
+    Ok(())
+}
+
+/// Archive a project (read-only, hidden from lists), or restore it with `undo`
+pub async fn archive(project: Option<String>, undo: bool, yes: bool, json: bool) -> Result<()> {
+    let config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+    let identifier = RemoteTarget::resolve_project(project)?;
+
+    if !undo {
+        let prompt = format!("Archive project {}?", identifier);
+        if !confirm(&prompt, yes)? {
+            status!("{}", "✗ Archive cancelled".yellow());
+            return output::cancelled();
+        }
+    }
+
+    let update = ProjectUpdate {
+        archived: Some(!undo),
+        ..Default::default()
+    };
+    let project = api_client
+        .update_project(&token, &identifier, &update)
+        .await?;
+
+    if json || output::is_machine() {
+        return output::emit(&project);
+    }
+
+    if undo {
+        status!("{}", format!("✓ Restored {}", project.name).green());
+    } else {
+        status!("{}", format!("✓ Archived {}", project.name).green());
+    }
+
+    Ok(())
+}
+
+/// Permanently delete a project with all its environments and variables
+pub async fn delete(project: Option<String>, yes: bool) -> Result<()> {
+    let config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+    let identifier = RemoteTarget::resolve_project(project)?;
+
+    status!(
+        "{}",
+        format!(
+            "⚠️  This permanently deletes {} with all of its environments and variables",
+            identifier
+        )
+        .yellow()
+    );
+
+    let prompt = format!("Delete project {}?", identifier);
+    if !confirm(&prompt, yes)? {
+        status!("{}", "✗ Deletion cancelled".yellow());
+        if output::is_machine() {
+            return output::emit(&serde_json::json!({
+                "project": identifier,
+                "deleted": false,
+            }));
+        }
+        return Ok(());
+    }
+
+    api_client.delete_project(&token, &identifier).await?;
+
+    status!("{}", format!("✓ Deleted {}", identifier).green());
+
+    cache::forget_deleted(&config, &identifier, None);
+
+    if output::is_machine() {
+        return output::emit(&serde_json::json!({
+            "project": identifier,
+            "deleted": true,
+        }));
+    }
+
+    if let Some(local_config) = ProjectConfig::load()? {
+        if is_linked(&local_config, &identifier) {
+            status!(
+                "{}",
+                "  This directory was linked to it. Run 'envsafe init' to link another project"
+                    .bright_black()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn is_linked(local_config: &ProjectConfig, identifier: &str) -> bool {
+    local_config.project_id == identifier
+        || local_config.project_slug.as_deref() == Some(identifier)
+}
+
+fn print_project(project: &Project) {
+    status!("{}", format!("📁 {}", project.name).cyan().bold());
+    status!();
+    println!("  ID: {}", project.id.bright_black());
+    if let Some(slug) = &project.slug {
+        println!("  Slug: {}", slug.bright_black());
+    }
+    if let Some(description) = &project.description {
+        println!("  Description: {}", description);
+    }
+    if let Some(environments) = &project.environments {
+        println!("  Environments: {}", environments.join(", "));
+    }
+    if let Some(updated_at) = &project.updated_at {
+        println!("  Updated: {}", updated_at.bright_black());
+    }
+    if project.archived {
+        println!("  {}", "Archived".yellow());
+    }
+}
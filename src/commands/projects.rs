@@ -1,34 +1,72 @@
+use crate::api::{ApiClient, Project, Workspace};
+use crate::config::Config;
+use crate::status;
+use crate::utils::output::{self, OutputFormat};
 use anyhow::Result;
 use colored::*;
-use crate::config::Config;
-use crate::api::ApiClient;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct WorkspaceProjects {
+    workspace: Workspace,
+    projects: Vec<Project>,
+}
 
 pub async fn execute() -> Result<()> {
     let config = Config::load()?;
     let token = config.get_token()?;
     let api_client = ApiClient::from_config(&config);
-    
-    println!("{}", "📁 All Accessible Projects".cyan().bold());
-    println!();
-    
+
     let workspaces = api_client.get_workspaces(&token).await?;
-    
+
+    let mut all = Vec::new();
     for workspace in workspaces {
-        println!("  {} {}", "📦".bright_blue(), workspace.name.bright_white().bold());
-        
         let projects = api_client.get_projects(&token, &workspace.id).await?;
-        
-        if projects.is_empty() {
-            println!("    {}", "No projects".bright_black());
+        all.push(WorkspaceProjects {
+            workspace,
+            projects,
+        });
+    }
+
+    if output::is_machine() {
+        return output::emit(&all);
+    }
+
+    if output::format() == OutputFormat::Table {
+        let rows: Vec<Vec<String>> = all
+            .iter()
+            .flat_map(|entry| {
+                entry
+                    .projects
+                    .iter()
+                    .map(|p| vec![entry.workspace.name.clone(), p.name.clone(), p.id.clone()])
+            })
+            .collect();
+        output::print_table(&["workspace", "project", "id"], &rows);
+        return Ok(());
+    }
+
+    status!("{}", "📁 All Accessible Projects".cyan().bold());
+    status!();
+
+    for entry in all {
+        status!(
+            "  {} {}",
+            "📦".bright_blue(),
+            entry.workspace.name.bright_white().bold()
+        );
+
+        if entry.projects.is_empty() {
+            status!("    {}", "No projects".bright_black());
         } else {
-            for project in projects {
+            for project in entry.projects {
                 println!("    {} {}", "•".cyan(), project.name);
-                println!("      {}", project.id.bright_black());
+                status!("      {}", project.id.bright_black());
             }
         }
-        
-        println!();
+
+        status!();
     }
-    
+
     Ok(())
 }
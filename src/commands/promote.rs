@@ -0,0 +1,164 @@
+use crate::api::{is_conflict, ApiClient, EnvVariable};
+use crate::config::Config;
+use crate::status;
+use crate::utils::diff::EnvDiff;
+use crate::utils::output;
+use crate::utils::prompt::{confirm, is_protected_environment};
+use crate::utils::target::RemoteTarget;
+use anyhow::Result;
+use chrono::Utc;
+use colored::*;
+use std::collections::HashMap;
+
+/// Copy selected variables from one environment to another (possibly in another project)
+pub async fn execute(
+    from: &str,
+    to: &str,
+    keys: Vec<String>,
+    all: bool,
+    force: bool,
+    show_values: bool,
+    yes: bool,
+) -> Result<()> {
+    let config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+
+    let source = RemoteTarget::parse(from)?;
+    let destination = RemoteTarget::parse(to)?;
+    source.validate(&api_client, &token).await?;
+    destination.validate(&api_client, &token).await?;
+
+    if source == destination {
+        anyhow::bail!("Source and destination are the same environment");
+    }
+
+    status!("{}", "🚚 Promoting environment variables...".cyan());
+    status!("{}", format!("  From: {}", source).bright_black());
+    status!("{}", format!("  To: {}", destination).bright_black());
+
+    let source_vars = api_client
+        .get_variables(&token, &source.project, &source.environment)
+        .await?;
+    let target = api_client
+        .get_environment(&token, &destination.project, &destination.environment)
+        .await?;
+    let target_vars: HashMap<String, String> = target
+        .variables
+        .into_iter()
+        .map(|v| (v.key, v.value))
+        .collect();
+
+    // Pick the keys to promote
+    let selected: HashMap<String, String> = if all {
+        source_vars
+    } else {
+        let missing: Vec<&String> = keys
+            .iter()
+            .filter(|k| !source_vars.contains_key(*k))
+            .collect();
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "Keys not found in {}: {}",
+                source,
+                missing
+                    .iter()
+                    .map(|k| k.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        keys.into_iter()
+            .filter_map(|k| source_vars.get(&k).map(|v| (k.clone(), v.clone())))
+            .collect()
+    };
+
+    // Target keeps everything it has; promoted keys are layered on top
+    let mut promoted = target_vars.clone();
+    promoted.extend(selected);
+
+    let diff = EnvDiff::compute(&target_vars, &promoted);
+
+    status!();
+    diff.print(show_values);
+    status!();
+    status!("{}", format!("  {}", diff.summary()).bright_black());
+
+    if diff.is_empty() {
+        status!("{}", "✓ Nothing to promote".green());
+        return report(&source, &destination, &diff);
+    }
+
+    if !diff.changed.is_empty() && !force {
+        let conflicting: Vec<&str> = diff.changed.keys().map(|k| k.as_str()).collect();
+        anyhow::bail!(
+            "{} key(s) already have a different value in {}: {}. Use --force to overwrite",
+            conflicting.len(),
+            destination,
+            conflicting.join(", ")
+        );
+    }
+
+    if is_protected_environment(&destination.environment) {
+        let prompt = format!("Promote these changes to {}?", destination);
+        if !confirm(&prompt, yes)? {
+            status!("{}", "✗ Promotion cancelled".yellow());
+            return output::cancelled();
+        }
+    }
+
+    let variables: Vec<EnvVariable> = promoted
+        .into_iter()
+        .map(|(key, value)| EnvVariable {
+            key,
+            value,
+            updated_at: Some(Utc::now().to_rfc3339()),
+        })
+        .collect();
+
+    // Only write over the destination we diffed against
+    api_client
+        .update_variables_if_match(
+            &token,
+            &destination.project,
+            &destination.environment,
+            variables,
+            target.version.as_deref(),
+        )
+        .await
+        .map_err(|e| {
+            if is_conflict(&e) {
+                anyhow::anyhow!(
+                    "{} changed while promoting. Review the new diff and retry",
+                    destination
+                )
+            } else {
+                e
+            }
+        })?;
+
+    status!(
+        "{}",
+        format!(
+            "✓ Promoted {} variable(s) to {}",
+            diff.added.len() + diff.changed.len(),
+            destination
+        )
+        .green()
+    );
+
+    report(&source, &destination, &diff)
+}
+
+/// The json/yaml document of a promotion, also written when there was nothing to promote
+fn report(source: &RemoteTarget, destination: &RemoteTarget, diff: &EnvDiff) -> Result<()> {
+    if output::is_machine() {
+        output::emit(&serde_json::json!({
+            "from": source.to_string(),
+            "to": destination.to_string(),
+            "changes": diff.key_summary(),
+        }))?;
+    }
+
+    Ok(())
+}
@@ -1,52 +1,136 @@
 use crate::api::ApiClient;
-use crate::config::{Config, ProjectConfig};
-use crate::storage::{EnvStorage, SharedEnvData};
+use crate::config::{Config, ProjectConfig, DEFAULT_ENVIRONMENT};
+use crate::status;
+use crate::storage::{self, EnvStorage, SharedEnvData};
+use crate::sync_state::SyncState;
+use crate::utils::diff::EnvDiff;
+use crate::utils::env_parser::FileFormat;
+use crate::utils::output;
+use crate::utils::pattern::KeyFilter;
+use crate::utils::prompt::{confirm, is_protected_environment};
+use crate::utils::target::validate_environment;
 use anyhow::Result;
 use chrono::Utc;
 use colored::*;
+use serde_json::json;
 use std::collections::HashMap;
 use std::fs;
 
+pub struct PullOptions {
+    /// Show what would change without writing the file
+    pub dry_run: bool,
+    pub show_values: bool,
+    pub yes: bool,
+}
+
 pub async fn execute(
     project: Option<String>,
     environment: Option<String>,
-    output_file: &str,
+    file: Option<String>,
+    format: Option<FileFormat>,
+    options: PullOptions,
 ) -> Result<()> {
+    let local_config = ProjectConfig::load()?;
+
+    if let Some(result) = pull(local_config, project, environment, file, format, &options).await? {
+        if output::is_machine() {
+            output::emit(&result)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Pull every service of the monorepo `.envsafe` into the service's own file
+pub async fn all_services(environment: Option<String>, options: PullOptions) -> Result<()> {
+    let root = ProjectConfig::find_root_from(&std::env::current_dir()?)?.ok_or_else(|| {
+        anyhow::anyhow!("No .envsafe found. Run 'envsafe init' in the repository root")
+    })?;
+
+    if root.services.is_empty() {
+        anyhow::bail!("No services are defined in the .envsafe file");
+    }
+
+    let mut results = Vec::new();
+    for name in root.services.keys() {
+        status!("{}", format!("▸ {}", name).cyan().bold());
+        let service_config = root.for_service(name)?;
+        let result = pull(
+            Some(service_config),
+            None,
+            environment.clone(),
+            None,
+            None,
+            &options,
+        )
+        .await?;
+        results.extend(result);
+        status!();
+    }
+
+    if output::is_machine() {
+        output::emit(&results)?;
+    }
+
+    Ok(())
+}
+
+/// Pull into the local file. Returns the summary document, or `None` when cancelled.
+async fn pull(
+    local_config: Option<ProjectConfig>,
+    project: Option<String>,
+    environment: Option<String>,
+    file: Option<String>,
+    format: Option<FileFormat>,
+    options: &PullOptions,
+) -> Result<Option<serde_json::Value>> {
     let config = Config::load()?;
+    let (output_file, format) = ProjectConfig::local_file(local_config.as_ref(), file, format);
+    let output_file = output_file.as_str();
+    let service = local_config.as_ref().and_then(|c| c.service.clone());
 
     // Determine project (we need both ID for display and slug for API)
-    let (project_id, project_slug, env_name) = if let Some(proj) = project {
+    let (project_id, project_slug, env_name, filter) = if let Some(proj) = project {
         // When provided as argument, assume it's a slug
-        let env = environment.unwrap_or_else(|| "development".to_string());
-        (proj.clone(), proj, env)
-    } else if let Some(local_config) = ProjectConfig::load()? {
-        let env = environment.unwrap_or_else(|| "development".to_string());
+        let env = environment.unwrap_or_else(|| DEFAULT_ENVIRONMENT.to_string());
+        (proj.clone(), proj, env, KeyFilter::default())
+    } else if let Some(local_config) = local_config {
+        let env = environment.unwrap_or_else(|| local_config.environment());
         let slug = local_config
             .project_slug
             .clone()
             .unwrap_or(local_config.project_id.clone());
-        (local_config.project_id, slug, env)
+        (local_config.project_id, slug, env, local_config.filter)
     } else {
         anyhow::bail!("No project specified. Run 'envsafe init' or provide project name");
     };
 
-    println!("{}", "📥 Pulling environment variables...".cyan());
-    println!("{}", format!("  Project: {}", project_id).bright_black());
-    println!("{}", format!("  Environment: {}", env_name).bright_black());
+    status!("{}", "📥 Pulling environment variables...".cyan());
+    status!("{}", format!("  Project: {}", project_id).bright_black());
+    if let Some(service) = &service {
+        status!("{}", format!("  Service: {}", service).bright_black());
+    }
+    status!("{}", format!("  Environment: {}", env_name).bright_black());
 
     let token = config.get_token()?;
     let api_client = ApiClient::from_config(&config);
+    validate_environment(&api_client, &token, &project_slug, &env_name).await?;
 
     // Fetch environment variables (use slug for API)
-    let env = api_client
+    let mut env = api_client
         .get_environment(&token, &project_slug, &env_name)
         .await?;
 
+    // Keys left out by the `.envsafe` filters are neither written nor cached
+    env.variables.retain(|v| filter.allows(&v.key));
+
     // Read existing .env file if it exists
     let mut existing_vars: HashMap<String, String> = HashMap::new();
     let mut local_only_vars: Vec<(String, String)> = Vec::new();
 
-    if std::path::Path::new(output_file).exists() {
+    if std::path::Path::new(output_file).exists() && format != FileFormat::Dotenv {
+        existing_vars = format.parse(&fs::read_to_string(output_file)?)?;
+    } else if std::path::Path::new(output_file).exists() {
         let existing_content = fs::read_to_string(output_file)?;
         for line in existing_content.lines() {
             let line = line.trim();
@@ -70,38 +154,75 @@ pub async fn execute(
     }
 
     // Keep local variables that are not in API
-    for (key, value) in existing_vars {
-        if !api_keys.contains(&key) {
+    for (key, value) in &existing_vars {
+        if !api_keys.contains(key) {
             local_only_vars.push((key.clone(), value.clone()));
-            merged_vars.insert(key, value);
+            merged_vars.insert(key.clone(), value.clone());
         }
     }
 
-    // Write merged content to file
-    let mut content = String::new();
-    content.push_str(&format!("# EnvSafe - {}\n", project_id));
-    content.push_str(&format!("# Environment: {}\n", env_name));
-    content.push_str(&format!("# Updated: {}\n", Utc::now().to_rfc3339()));
-    content.push_str("# Variables from EnvSafe (managed remotely)\n\n");
+    let diff = EnvDiff::compute(&existing_vars, &merged_vars);
 
-    // Write API variables first
-    for var in &env.variables {
-        content.push_str(&format!("{}={}\n", var.key, var.value));
+    // Preview what the pull would change in the local file
+    if options.dry_run || is_protected_environment(&env_name) {
+        status!();
+        diff.print(options.show_values);
+        status!();
+        status!("{}", format!("  {}", diff.summary()).bright_black());
+
+        if options.dry_run {
+            status!(
+                "{}",
+                format!("ℹ Dry run: {} was not modified", output_file).bright_black()
+            );
+            return Ok(Some(json!({
+                "project": project_id,
+                "service": service,
+                "environment": env_name,
+                "file": output_file,
+                "dry_run": true,
+                "changes": diff.key_summary(),
+            })));
+        }
+
+        let prompt = format!("Write {} variables to {}?", env_name, output_file);
+        if !confirm(&prompt, options.yes)? {
+            status!("{}", "✗ Pull cancelled".yellow());
+            return Ok(None);
+        }
     }
 
-    // Write local-only variables separately
     let local_vars_count = local_only_vars.len();
-    if !local_only_vars.is_empty() {
-        content.push_str("\n# Local variables (not managed by EnvSafe)\n");
-        for (key, value) in local_only_vars {
-            content.push_str(&format!("{}={}\n", key, value));
+
+    if format == FileFormat::Dotenv {
+        // Write merged content to file
+        let mut content = String::new();
+        content.push_str(&format!("# EnvSafe - {}\n", project_id));
+        content.push_str(&format!("# Environment: {}\n", env_name));
+        content.push_str(&format!("# Updated: {}\n", Utc::now().to_rfc3339()));
+        content.push_str("# Variables from EnvSafe (managed remotely)\n\n");
+
+        // Write API variables first
+        for var in &env.variables {
+            content.push_str(&format!("{}={}\n", var.key, var.value));
+        }
+
+        // Write local-only variables separately
+        if !local_only_vars.is_empty() {
+            content.push_str("\n# Local variables (not managed by EnvSafe)\n");
+            for (key, value) in local_only_vars {
+                content.push_str(&format!("{}={}\n", key, value));
+            }
         }
-    }
 
-    fs::write(output_file, &content)?;
+        fs::write(output_file, &content)?;
+    } else {
+        // Structured formats cannot hold comments, so local-only keys are simply kept in place
+        fs::write(output_file, format.render(&merged_vars)?)?;
+    }
 
     // Update shared memory
-    let mut storage = EnvStorage::new()?;
+    let mut storage = EnvStorage::open(&storage::current_key(&config, &project_slug, &env_name)?)?;
     let vars_map: HashMap<String, String> = env
         .variables
         .iter()
@@ -115,11 +236,15 @@ pub async fn execute(
         environment: env_name.clone(),
         variables: vars_map,
         last_updated: Utc::now().to_rfc3339(),
+        etag: env.version.clone(),
     };
 
     storage.write(&data)?;
 
-    println!(
+    // Remember what we pulled so a later push can detect concurrent remote edits
+    SyncState::from_vars(&data.variables, env.version.clone()).save(&project_slug, &env_name)?;
+
+    status!(
         "{}",
         format!(
             "✓ Pulled {} variables from EnvSafe to {}",
@@ -130,13 +255,22 @@ pub async fn execute(
     );
 
     if local_vars_count > 0 {
-        println!(
+        status!(
             "{}",
             format!("✓ Preserved {} local variables", local_vars_count).green()
         );
     }
 
-    println!("{}", "✓ Updated shared memory".green());
+    status!("{}", "✓ Updated shared memory".green());
 
-    Ok(())
+    Ok(Some(json!({
+        "project": project_id,
+        "service": service,
+        "environment": env_name,
+        "file": output_file,
+        "dry_run": false,
+        "pulled": env.variables.len(),
+        "preserved_local": local_vars_count,
+        "changes": diff.key_summary(),
+    })))
 }
@@ -1,66 +1,451 @@
 #![allow(deprecated)]
 
-use crate::api::{ApiClient, EnvVariable};
-use crate::config::{Config, ProjectConfig};
+use crate::api::{is_conflict, ApiClient, EnvVariable};
+use crate::config::{Config, ProjectConfig, DEFAULT_ENVIRONMENT};
+use crate::status;
+use crate::sync_state::SyncState;
+use crate::utils::diff::EnvDiff;
+use crate::utils::env_parser::FileFormat;
+use crate::utils::output;
+use crate::utils::pattern::KeyFilter;
+use crate::utils::prompt::{confirm, is_protected_environment, require_interactive};
+use crate::utils::target::validate_environment;
 use anyhow::Result;
 use chrono::Utc;
 use colored::Colorize;
+use serde_json::json;
+use std::collections::HashMap;
+
+/// How local variables are reconciled with the remote environment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PushMode {
+    /// Add and update keys from the file, never delete remote keys
+    Merge,
+    /// Make the remote environment an exact mirror of the file, deleting missing keys
+    Replace,
+}
+
+impl std::fmt::Display for PushMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PushMode::Merge => write!(f, "merge"),
+            PushMode::Replace => write!(f, "replace"),
+        }
+    }
+}
+
+pub struct PushOptions {
+    pub mode: PushMode,
+    /// Only push these keys from the file (merge mode only)
+    pub keys: Vec<String>,
+    /// Skip the optimistic concurrency check and overwrite remote changes
+    pub force: bool,
+    pub dry_run: bool,
+    pub show_values: bool,
+    pub yes: bool,
+}
 
 pub async fn execute(
     project: Option<String>,
     environment: Option<String>,
-    file_path: &str,
+    file: Option<String>,
+    format: Option<FileFormat>,
+    options: PushOptions,
 ) -> Result<()> {
     let config = Config::load()?;
+    let local_config = ProjectConfig::load()?;
+    let (file_path, format) = ProjectConfig::local_file(local_config.as_ref(), file, format);
+    let file_path = file_path.as_str();
+
+    if options.mode == PushMode::Replace && !options.keys.is_empty() {
+        anyhow::bail!("--keys cannot be combined with --mode replace");
+    }
 
     // Determine project
-    // Determine project
-    let (identifier, env_name) = if let Some(proj) = project {
-        let env = environment.unwrap_or_else(|| "development".to_string());
-        (proj, env)
-    } else if let Some(local_config) = ProjectConfig::load()? {
-        let env = environment.unwrap_or_else(|| "development".to_string());
+    let (identifier, env_name, filter) = if let Some(proj) = project {
+        let env = environment.unwrap_or_else(|| DEFAULT_ENVIRONMENT.to_string());
+        (proj, env, KeyFilter::default())
+    } else if let Some(local_config) = local_config {
+        let env = environment.unwrap_or_else(|| local_config.environment());
         let id = local_config
             .project_slug
             .clone()
             .unwrap_or(local_config.project_id);
-        (id, env)
+        (id, env, local_config.filter)
     } else {
         anyhow::bail!("No project specified. Run 'envsafe init' or provide project name");
     };
 
-    println!("{}", "📤 Pushing environment variables...".cyan());
-    println!("{}", format!("  Project: {}", identifier).bright_black());
-    println!("{}", format!("  Environment: {}", env_name).bright_black());
-    println!("{}", format!("  File: {}", file_path).bright_black());
+    status!("{}", "📤 Pushing environment variables...".cyan());
+    status!("{}", format!("  Project: {}", identifier).bright_black());
+    status!("{}", format!("  Environment: {}", env_name).bright_black());
+    status!("{}", format!("  File: {}", file_path).bright_black());
+    status!("{}", format!("  Mode: {}", options.mode).bright_black());
 
     // Read .env file
-    let variables: Vec<EnvVariable> = dotenv::from_path_iter(file_path)?
-        .filter_map(|item| item.ok())
-        .map(|(key, value)| EnvVariable {
-            key,
-            value,
-            updated_at: Some(Utc::now().to_rfc3339()),
-        })
-        .collect();
+    let mut local_vars: HashMap<String, String> = match format {
+        FileFormat::Dotenv => dotenv::from_path_iter(file_path)?
+            .filter_map(|item| item.ok())
+            .collect(),
+        _ => format.parse(&std::fs::read_to_string(file_path)?)?,
+    };
+    filter.apply(&mut local_vars);
 
-    if variables.is_empty() {
-        println!("{}", "⚠️  No variables found in file".yellow());
-        return Ok(());
+    if !options.keys.is_empty() {
+        let missing: Vec<&str> = options
+            .keys
+            .iter()
+            .filter(|k| !local_vars.contains_key(*k))
+            .map(|k| k.as_str())
+            .collect();
+        if !missing.is_empty() {
+            anyhow::bail!("Keys not found in {}: {}", file_path, missing.join(", "));
+        }
+        local_vars.retain(|k, _| options.keys.contains(k));
+    }
+
+    if local_vars.is_empty() {
+        status!("{}", "⚠️  No variables found in file".yellow());
+        return report(&identifier, &env_name, &options, None, &EnvDiff::default());
     }
 
     let token = config.get_token()?;
     let api_client = ApiClient::from_config(&config);
+    validate_environment(&api_client, &token, &identifier, &env_name).await?;
 
-    // Push to server
-    api_client
-        .update_variables(&token, &identifier, &env_name, variables.clone())
+    let remote = api_client
+        .get_environment(&token, &identifier, &env_name)
         .await?;
+    let remote_version = remote.version.clone();
+    let remote_vars: HashMap<String, String> = remote
+        .variables
+        .into_iter()
+        .map(|v| (v.key, v.value))
+        .collect();
+
+    // The local file derives from the last pull, so that is the version we must still match
+    let base = SyncState::load(&identifier, &env_name)?;
+    let expected_version = base
+        .as_ref()
+        .and_then(|b| b.version.clone())
+        .or_else(|| remote_version.clone());
+
+    let desired = plan(options.mode, &remote_vars, &local_vars, &filter);
+    let diff = EnvDiff::compute(&remote_vars, &desired);
 
-    println!(
+    if diff.is_empty() {
+        status!("{}", "✓ Remote is already up to date".green());
+        return report(&identifier, &env_name, &options, remote_version, &diff);
+    }
+
+    if !review(&diff, &env_name, &options)? {
+        return Ok(());
+    }
+
+    // Push to server
+    let if_match = if options.force {
+        None
+    } else {
+        expected_version.as_deref()
+    };
+
+    // The full map becomes the environment: keys left out of it, the deletions of replace
+    // mode, go in the same version-checked update
+    let result = api_client
+        .update_variables_if_match(
+            &token,
+            &identifier,
+            &env_name,
+            to_variables(&desired),
+            if_match,
+        )
+        .await;
+
+    let (desired, diff, new_version) = match result {
+        Ok(version) => (desired, diff, version),
+        Err(e) if is_conflict(&e) => {
+            let rebased = resolve_conflict(
+                &api_client,
+                &token,
+                &identifier,
+                &env_name,
+                base.as_ref(),
+                &local_vars,
+                &filter,
+                &options,
+            )
+            .await?;
+
+            match rebased {
+                Some(outcome) => outcome,
+                None => return Ok(()),
+            }
+        }
+        Err(e) => return Err(e),
+    };
+
+    // Like pull, only track the keys this directory works with
+    let mut synced = desired;
+    filter.apply(&mut synced);
+    SyncState::from_vars(&synced, new_version.clone()).save(&identifier, &env_name)?;
+
+    status!(
         "{}",
-        format!("✓ Pushed {} variables", variables.len()).green()
+        format!(
+            "✓ Pushed to {} ({}): {} added, {} updated, {} deleted, {} unchanged",
+            env_name,
+            options.mode,
+            diff.added.len(),
+            diff.changed.len(),
+            diff.removed.len(),
+            diff.unchanged
+        )
+        .green()
     );
 
+    report(&identifier, &env_name, &options, new_version, &diff)
+}
+
+/// The json/yaml document of a push, also written when there was nothing to push
+fn report(
+    identifier: &str,
+    env_name: &str,
+    options: &PushOptions,
+    version: Option<String>,
+    diff: &EnvDiff,
+) -> Result<()> {
+    if output::is_machine() {
+        output::emit(&json!({
+            "project": identifier,
+            "environment": env_name,
+            "mode": options.mode.to_string(),
+            "dry_run": false,
+            "version": version,
+            "changes": diff.key_summary(),
+        }))?;
+    }
+
     Ok(())
 }
+
+/// Compute the remote state we want to end up with
+fn plan(
+    mode: PushMode,
+    remote: &HashMap<String, String>,
+    local: &HashMap<String, String>,
+    filter: &KeyFilter,
+) -> HashMap<String, String> {
+    match mode {
+        PushMode::Merge => {
+            let mut merged = remote.clone();
+            merged.extend(local.iter().map(|(k, v)| (k.clone(), v.clone())));
+            merged
+        }
+        PushMode::Replace => {
+            let mut replaced = local.clone();
+            keep_filtered_out(&mut replaced, remote, filter);
+            replaced
+        }
+    }
+}
+
+/// Replace only mirrors the keys this directory works with; the others stay as they are remotely
+fn keep_filtered_out(
+    desired: &mut HashMap<String, String>,
+    remote: &HashMap<String, String>,
+    filter: &KeyFilter,
+) {
+    for (key, value) in remote {
+        if !filter.allows(key) {
+            desired.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+fn to_variables(vars: &HashMap<String, String>) -> Vec<EnvVariable> {
+    vars.iter()
+        .map(|(key, value)| EnvVariable {
+            key: key.clone(),
+            value: value.clone(),
+            updated_at: Some(Utc::now().to_rfc3339()),
+        })
+        .collect()
+}
+
+/// Show the diff when needed and ask for confirmation. Returns `false` if nothing should be pushed.
+fn review(diff: &EnvDiff, env_name: &str, options: &PushOptions) -> Result<bool> {
+    let needs_confirmation = is_protected_environment(env_name) || !diff.removed.is_empty();
+
+    if !options.dry_run && !needs_confirmation {
+        return Ok(true);
+    }
+
+    status!();
+    diff.print(options.show_values);
+    status!();
+    status!("{}", format!("  {}", diff.summary()).bright_black());
+
+    if options.dry_run {
+        status!("{}", "ℹ Dry run: nothing was pushed".bright_black());
+        if output::is_machine() {
+            output::emit(&json!({
+                "environment": env_name,
+                "mode": options.mode.to_string(),
+                "dry_run": true,
+                "changes": diff.key_summary(),
+            }))?;
+        }
+        return Ok(false);
+    }
+
+    if !diff.removed.is_empty() {
+        let deleted: Vec<&str> = diff.removed.keys().map(|k| k.as_str()).collect();
+        status!(
+            "{}",
+            format!(
+                "⚠️  {} key(s) will be deleted from {}: {}",
+                deleted.len(),
+                env_name,
+                deleted.join(", ")
+            )
+            .yellow()
+        );
+    }
+
+    let prompt = format!("Push these changes to {}?", env_name);
+    if !confirm(&prompt, options.yes)? {
+        status!("{}", "✗ Push cancelled".yellow());
+        output::cancelled()?;
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+/// Someone else pushed since our last pull: explain what changed and offer to rebase.
+///
+/// Returns the pushed state, or `None` if the user aborted.
+#[allow(clippy::too_many_arguments)]
+async fn resolve_conflict(
+    api_client: &ApiClient,
+    token: &str,
+    identifier: &str,
+    env_name: &str,
+    base: Option<&SyncState>,
+    local_vars: &HashMap<String, String>,
+    filter: &KeyFilter,
+    options: &PushOptions,
+) -> Result<Option<(HashMap<String, String>, EnvDiff, Option<String>)>> {
+    status!();
+    status!(
+        "{}",
+        format!(
+            "⚠️  {} was modified remotely since your last pull",
+            env_name
+        )
+        .yellow()
+        .bold()
+    );
+
+    let latest = api_client
+        .get_environment(token, identifier, env_name)
+        .await?;
+    let latest_version = latest.version.clone();
+    let latest_vars: HashMap<String, String> = latest
+        .variables
+        .into_iter()
+        .map(|v| (v.key, v.value))
+        .collect();
+
+    let rebased = match base {
+        Some(base) => {
+            // The state was saved from filtered variables: compare like with like
+            let mut tracked = latest_vars.clone();
+            filter.apply(&mut tracked);
+            let remote_changes = base.changed_keys(&tracked);
+            let local_changes = base.changed_keys(local_vars);
+
+            if !remote_changes.is_empty() {
+                status!("{}", "  Changed remotely:".bright_black());
+                for key in &remote_changes {
+                    if local_changes.contains(key) {
+                        status!(
+                            "    {} {} {}",
+                            "!".red(),
+                            key.red(),
+                            "(also changed locally, your value wins on rebase)".bright_black()
+                        );
+                    } else {
+                        status!("    {} {}", "~".yellow(), key);
+                    }
+                }
+            }
+
+            let mut rebased =
+                base.rebase(local_vars, &latest_vars, options.mode == PushMode::Replace);
+            if options.mode == PushMode::Replace {
+                keep_filtered_out(&mut rebased, &latest_vars, filter);
+            }
+            rebased
+        }
+        None => {
+            status!(
+                "{}",
+                "  No previous pull recorded for this environment; run 'envsafe pull' to track remote changes"
+                    .bright_black()
+            );
+            plan(options.mode, &latest_vars, local_vars, filter)
+        }
+    };
+
+    require_interactive(
+        "how to resolve the conflict",
+        "Run 'envsafe pull' to update, or push with --force to overwrite",
+    )?;
+
+    let choice = dialoguer::Select::new()
+        .with_prompt("How do you want to continue?")
+        .items(&["Rebase my changes on the latest remote", "Abort"])
+        .default(1)
+        .interact()?;
+
+    if choice != 0 {
+        status!(
+            "{}",
+            "✗ Push aborted. Run 'envsafe pull' to update, or push with --force to overwrite"
+                .yellow()
+        );
+        output::cancelled()?;
+        return Ok(None);
+    }
+
+    let diff = EnvDiff::compute(&latest_vars, &rebased);
+
+    if diff.is_empty() {
+        status!("{}", "✓ Remote already contains your changes".green());
+        return Ok(Some((rebased, diff, latest_version)));
+    }
+
+    if !review(&diff, env_name, options)? {
+        return Ok(None);
+    }
+
+    let version = api_client
+        .update_variables_if_match(
+            token,
+            identifier,
+            env_name,
+            to_variables(&rebased),
+            latest_version.as_deref(),
+        )
+        .await
+        .map_err(|e| {
+            if is_conflict(&e) {
+                anyhow::anyhow!("{} changed again while rebasing. Please retry", env_name)
+            } else {
+                e
+            }
+        })?;
+
+    Ok(Some((rebased, diff, version)))
+}
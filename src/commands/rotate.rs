@@ -1,16 +1,22 @@
-use anyhow::Result;
-use colored::*;
-use crate::config::{Config, ProjectConfig};
 use crate::api::ApiClient;
+use crate::config::{Config, ProjectConfig};
 use crate::rotation::SecretRotator;
+use crate::status;
+use crate::utils::output;
+use anyhow::Result;
+use colored::*;
 
 pub async fn enable(interval: u32, exclude: Vec<String>) -> Result<()> {
     let config = Config::load()?;
     let api_client = ApiClient::from_config(&config);
     let mut rotator = SecretRotator::new(api_client, config.clone());
-    
+
     rotator.enable_rotation(interval, exclude)?;
-    
+
+    if output::is_machine() {
+        return output::emit(&rotator.status());
+    }
+
     Ok(())
 }
 
@@ -18,9 +24,13 @@ pub async fn disable() -> Result<()> {
     let config = Config::load()?;
     let api_client = ApiClient::from_config(&config);
     let mut rotator = SecretRotator::new(api_client, config.clone());
-    
+
     rotator.disable_rotation()?;
-    
+
+    if output::is_machine() {
+        return output::emit(&rotator.status());
+    }
+
     Ok(())
 }
 
@@ -28,15 +38,19 @@ pub async fn status() -> Result<()> {
     let config = Config::load()?;
     let api_client = ApiClient::from_config(&config);
     let rotator = SecretRotator::new(api_client, config);
-    
+
+    if output::is_machine() {
+        return output::emit(&rotator.status());
+    }
+
     rotator.show_status()?;
-    
+
     Ok(())
 }
 
 pub async fn now(vars: Vec<String>) -> Result<()> {
     let config = Config::load()?;
-    
+
     // Get current project
     let project_id = if let Some(local_config) = ProjectConfig::load()? {
         local_config.project_id
@@ -45,28 +59,37 @@ pub async fn now(vars: Vec<String>) -> Result<()> {
     } else {
         anyhow::bail!("No project configured. Run 'envsafe init' first");
     };
-    
+
     let api_client = ApiClient::from_config(&config);
     let mut rotator = SecretRotator::new(api_client, config);
-    
-    println!("{}", "🔄 Forcing immediate rotation...".cyan());
-    println!();
-    
-    let specific = if vars.is_empty() {
-        None
-    } else {
-        Some(vars)
-    };
-    
-    let count = rotator.rotate_secrets(&project_id, "production", specific).await?;
-    
+
+    status!("{}", "🔄 Forcing immediate rotation...".cyan());
+    status!();
+
+    let specific = if vars.is_empty() { None } else { Some(vars) };
+
+    let count = rotator
+        .rotate_secrets(&project_id, "production", specific)
+        .await?;
+
     if count > 0 {
-        println!();
-        println!("{}", "⚠️  Important:".yellow().bold());
-        println!("{}", "  - Update your services with new secrets".yellow());
-        println!("{}", "  - Restart affected containers/services".yellow());
-        println!("{}", "  - Run 'envsafe pull' to get updated values".yellow());
+        status!();
+        status!("{}", "⚠️  Important:".yellow().bold());
+        status!("{}", "  - Update your services with new secrets".yellow());
+        status!("{}", "  - Restart affected containers/services".yellow());
+        status!(
+            "{}",
+            "  - Run 'envsafe pull' to get updated values".yellow()
+        );
     }
-    
+
+    if output::is_machine() {
+        return output::emit(&serde_json::json!({
+            "project": project_id,
+            "environment": "production",
+            "rotated": count,
+        }));
+    }
+
     Ok(())
 }
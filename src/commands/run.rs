@@ -1,31 +1,56 @@
 use crate::api::ApiClient;
-use crate::config::{Config, ProjectConfig};
-use crate::storage::EnvStorage;
+use crate::config::{Config, ProjectConfig, DEFAULT_ENVIRONMENT};
+use crate::status;
+use crate::storage::{self, EnvStorage, SegmentKey, SharedEnvData, SEGMENT_ENV_VAR};
+use crate::utils::duration::format_duration;
 use crate::utils::i18n::get_translations;
+use crate::utils::output;
+use crate::utils::pattern::KeyFilter;
+use crate::utils::target::validate_environment;
 use anyhow::Result;
+use chrono::Utc;
 use colored::*;
 use std::collections::HashMap;
 use std::process::Command;
+use std::time::Duration;
+
+/// Where `run` may take the variables from
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheOptions {
+    /// Always fetch from EnvSafe and leave the cache untouched
+    pub no_cache: bool,
+    /// Always fetch from EnvSafe and update the cache
+    pub refresh: bool,
+    /// Overrides the configured `cache_max_age`
+    pub max_age: Option<Duration>,
+    /// Check the cached version against the server before using it
+    pub validate: bool,
+}
 
 pub async fn execute(
     project: Option<String>,
+    service: Option<String>,
     environment: Option<String>,
     command_args: Vec<String>,
+    cache: CacheOptions,
 ) -> Result<()> {
     let config = Config::load()?;
     let t = get_translations(&config.language);
 
     // Determine project (we need both ID for display and slug for API)
-    let (project_id, project_slug, env_name) = if let Some(proj) = project {
-        let env = environment.unwrap_or_else(|| "development".to_string());
-        (proj.clone(), proj, env)
-    } else if let Some(local_config) = ProjectConfig::load()? {
-        let env = environment.unwrap_or_else(|| "development".to_string());
+    let (project_id, project_slug, env_name, filter) = if let Some(proj) = project {
+        let env = environment.unwrap_or_else(|| DEFAULT_ENVIRONMENT.to_string());
+        (proj.clone(), proj, env, KeyFilter::default())
+    } else if let Some(local_config) = match service {
+        Some(name) => Some(ProjectConfig::load_service(&name)?),
+        None => ProjectConfig::load()?,
+    } {
+        let env = environment.unwrap_or_else(|| local_config.environment());
         let slug = local_config
             .project_slug
             .clone()
             .unwrap_or(local_config.project_id.clone());
-        (local_config.project_id, slug, env)
+        (local_config.project_id, slug, env, local_config.filter)
     } else {
         anyhow::bail!("No project specified. Run 'envsafe init' or provide project name");
     };
@@ -34,39 +59,116 @@ pub async fn execute(
         anyhow::bail!("No command specified");
     }
 
-    println!("{}", t.run.executing.cyan());
-    println!("{}", format!("  Project: {}", project_id).bright_black());
-    println!("{}", format!("  Environment: {}", env_name).bright_black());
-    println!();
+    status!("{}", t.run.executing.cyan());
+    status!("{}", format!("  Project: {}", project_id).bright_black());
+    status!("{}", format!("  Environment: {}", env_name).bright_black());
+    status!();
 
     let token = config.get_token()?;
     let api_client = ApiClient::from_config(&config);
 
-    // Try to get from shared memory first (faster)
-    let vars = {
-        let storage = EnvStorage::new()?;
-        if let Some(data) = storage.read()? {
-            if data.project_id == project_id && data.environment == env_name {
-                println!("{}", t.run.using_cached.bright_black());
-                data.variables
+    // Try to get from this environment's shared memory segment first (faster)
+    let key = storage::current_key(&config, &project_slug, &env_name)?;
+    let max_age = cache
+        .max_age
+        .unwrap_or(Duration::from_secs(config.cache_max_age));
+    let use_cache = !cache.no_cache && !cache.refresh && !max_age.is_zero();
+
+    let mut storage = EnvStorage::open_existing(&key)?;
+    let cached = match &storage {
+        Some(storage) if use_cache => storage.read()?,
+        _ => None,
+    };
+
+    let cached = match cached {
+        Some(data) => match data.age() {
+            Some(age) if age <= max_age => Some((data, age)),
+            age => {
+                let age = age.map(format_duration).unwrap_or_else(|| "?".to_string());
+                status!("{}", t.run.cache_stale.replace("{}", &age).yellow());
+                None
+            }
+        },
+        None => None,
+    };
+
+    let cached = match cached {
+        Some((data, age)) if cache.validate => {
+            let current = api_client
+                .environment_version(&token, &project_slug, &env_name)
+                .await?;
+            // Nothing to compare against: trust the age check alone
+            if data.etag.is_none() || current.is_none() || data.etag == current {
+                Some((data, age))
             } else {
-                fetch_vars(&api_client, &token, &project_slug, &env_name).await?
+                status!("{}", t.run.cache_outdated.yellow());
+                None
+            }
+        }
+        cached => cached,
+    };
+
+    // A running `envsafe agent` has the environment live, without an API call
+    let agent_data = match cached {
+        None if !cache.no_cache && !cache.refresh => from_agent(&key).await,
+        _ => None,
+    };
+
+    let (mut vars, source) = match (cached, agent_data) {
+        (Some((data, age)), _) => {
+            status!(
+                "{}",
+                format!(
+                    "{} (updated {} ago)",
+                    t.run.using_cached,
+                    format_duration(age)
+                )
+                .bright_black()
+            );
+            (data.variables, "cache")
+        }
+        (None, Some(data)) => {
+            status!("{}", t.run.from_agent.bright_black());
+            // The agent keeps this environment's segment up to date
+            storage = EnvStorage::open_existing(&key)?;
+            (data.variables, "agent")
+        }
+        (None, None) => {
+            let (vars, etag) = fetch_vars(&api_client, &token, &project_slug, &env_name).await?;
+            status!("{}", t.run.fetched.bright_black());
+
+            if !cache.no_cache {
+                let mut refreshed = match storage.take() {
+                    Some(storage) => storage,
+                    None => EnvStorage::open(&key)?,
+                };
+                let version = refreshed.get_version()? + 1;
+                refreshed.write(&SharedEnvData {
+                    version,
+                    project_id: project_id.clone(),
+                    environment: env_name.clone(),
+                    variables: vars.clone(),
+                    last_updated: Utc::now().to_rfc3339(),
+                    etag,
+                })?;
+                storage = Some(refreshed);
             }
-        } else {
-            fetch_vars(&api_client, &token, &project_slug, &env_name).await?
+            (vars, "api")
         }
     };
+    filter.apply(&mut vars);
+    let loaded = vars.len();
 
-    println!(
+    status!(
         "{}",
         t.run
             .loaded_vars
             .replace("{}", &vars.len().to_string())
             .green()
     );
-    println!();
-    println!("{}", "─".repeat(50).bright_black());
-    println!();
+    status!();
+    status!("{}", "─".repeat(50).bright_black());
+    status!();
 
     // Execute command with environment variables
     let cmd_name = &command_args[0];
@@ -79,26 +181,59 @@ pub async fn execute(
     for (key, value) in vars {
         cmd.env(key, value);
     }
+    if let (Some(storage), false) = (&storage, cache.no_cache) {
+        cmd.env(SEGMENT_ENV_VAR, storage.segment());
+    }
 
     let status = cmd.status()?;
 
-    println!();
-    println!("{}", "─".repeat(50).bright_black());
+    // Written after the command's own output, which shares stdout
+    if output::is_machine() {
+        output::emit(&serde_json::json!({
+            "project": project_id,
+            "environment": env_name,
+            "source": source,
+            "variables": loaded,
+            "exit_code": status.code(),
+        }))?;
+    }
+
+    status!();
+    status!("{}", "─".repeat(50).bright_black());
 
     if status.success() {
-        println!("{}", t.run.success.green());
+        status!("{}", t.run.success.green());
         Ok(())
     } else {
         anyhow::bail!(t.run.failure.replace("{}", &status.to_string()));
     }
 }
 
+/// Variables from a running `envsafe agent`; `None` if there is none or it fails
+#[cfg(unix)]
+async fn from_agent(key: &SegmentKey) -> Option<SharedEnvData> {
+    match crate::agent::fetch(key).await {
+        Ok(data) => data,
+        Err(e) => {
+            status!("{}", format!("⚠ {}", e).yellow());
+            None
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn from_agent(_key: &SegmentKey) -> Option<SharedEnvData> {
+    None
+}
+
 async fn fetch_vars(
     api_client: &ApiClient,
     token: &str,
     project_slug: &str,
     env_name: &str,
-) -> Result<HashMap<String, String>> {
+) -> Result<(HashMap<String, String>, Option<String>)> {
+    validate_environment(api_client, token, project_slug, env_name).await?;
+
     let env = api_client
         .get_environment(token, project_slug, env_name)
         .await?;
@@ -109,5 +244,5 @@ async fn fetch_vars(
         .map(|v| (v.key, v.value))
         .collect();
 
-    Ok(vars)
+    Ok((vars, env.version))
 }
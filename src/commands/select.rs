@@ -1,36 +1,79 @@
+use crate::api::ApiClient;
+use crate::config::{Config, ProjectConfig};
+use crate::status;
+use crate::storage::EnvStorage;
+use crate::utils::output;
 use anyhow::Result;
 use colored::*;
-use crate::config::{Config, ProjectConfig};
-use crate::api::ApiClient;
+use std::time::Duration;
 
 pub async fn execute(project_name: &str) -> Result<()> {
     let config = Config::load()?;
     let token = config.get_token()?;
-    
-    let workspace_id = config.current_workspace
+
+    let workspace_id = config
+        .current_workspace
         .as_ref()
         .ok_or_else(|| anyhow::anyhow!("No workspace linked. Run 'envsafe link' first"))?;
-    
+
     let api_client = ApiClient::from_config(&config);
     let workspace_slug = config.get_workspace_slug()?;
     let projects = api_client.get_projects(&token, &workspace_slug).await?;
-    
+
     let project = projects
         .iter()
         .find(|p| p.name == project_name || p.id == project_name)
         .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", project_name))?;
-    
-    // Save to local config
-    let project_config = ProjectConfig {
-        workspace_id: workspace_id.clone(),
-        project_id: project.id.clone(),
-        project_name: project.name.clone(),
-        project_slug: project.slug.clone(),
-    };
-    
+
+    let previous = ProjectConfig::load()?;
+
+    // Save to local config, keeping the other settings of an existing file
+    let mut project_config = ProjectConfig::load_in(&std::env::current_dir()?)?;
+    project_config.link(workspace_id, project);
+
     project_config.save()?;
-    
-    println!("{}", format!("✓ Selected project: {}", project.name).green());
-    
+
+    status!(
+        "{}",
+        format!("✓ Selected project: {}", project.name).green()
+    );
+
+    if let Some(previous) = previous.filter(|p| p.project_id != project.id) {
+        purge_project(&config, &previous);
+    }
+
+    if output::is_machine() {
+        return output::emit(&project_config);
+    }
+
     Ok(())
 }
+
+/// Drop the cache of the project we switched away from, unless something still watches it
+fn purge_project(config: &Config, previous: &ProjectConfig) {
+    let names: Vec<&str> = [Some(&previous.project_id), previous.project_slug.as_ref()]
+        .into_iter()
+        .flatten()
+        .map(|name| name.as_str())
+        .collect();
+    let max_age = Duration::from_secs(config.cache_max_age);
+
+    let result = EnvStorage::purge(max_age, |usage| {
+        usage.cleared_on_switch(&previous.workspace_id, &names)
+    });
+    match result {
+        Ok(purged) if !purged.is_empty() => status!(
+            "{}",
+            format!(
+                "  Removed {} cached environment(s) of the previous project",
+                purged.len()
+            )
+            .bright_black()
+        ),
+        Ok(_) => {}
+        Err(e) => status!(
+            "{}",
+            format!("⚠ Could not clear the previous project's cache: {}", e).yellow()
+        ),
+    }
+}
@@ -0,0 +1,115 @@
+use crate::api::ApiClient;
+use crate::config::Config;
+use crate::server;
+use crate::status;
+use crate::storage;
+use crate::utils::output;
+use crate::utils::target::RemoteTarget;
+use crate::watcher::EnvWatcher;
+use anyhow::Result;
+use colored::*;
+use std::io::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use tokio::sync::watch;
+
+/// Serve one environment, the current one by default, on a loopback HTTP port
+pub async fn execute(
+    target: Option<String>,
+    bind: IpAddr,
+    port: u16,
+    token_file: Option<PathBuf>,
+) -> Result<()> {
+    let listener = server::bind(SocketAddr::new(bind, port)).await?;
+    let addr = listener.local_addr()?;
+
+    let config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+
+    let target = match target {
+        Some(spec) => RemoteTarget::parse(&spec)?,
+        None => RemoteTarget::resolve(None, None)?,
+    };
+    target.validate(&api_client, &token).await?;
+
+    let token_file = match token_file {
+        Some(path) => path,
+        None => default_token_file(addr.port())?,
+    };
+    let session_token = server::generate_token();
+    write_token(&token_file, &session_token)?;
+
+    let key = storage::current_key(&config, &target.project, &target.environment)?;
+    let (sender, updates) = watch::channel(None);
+    let mut watcher = EnvWatcher::new(api_client, config, &key)?
+        .without_file()
+        .with_updates(sender);
+
+    status!("{}", format!("🌐 Serving {}", target).cyan().bold());
+    status!(
+        "{}",
+        format!("  URL: http://{}/v1/env", addr).bright_black()
+    );
+    status!(
+        "{}",
+        format!("  Token: {}", token_file.display()).bright_black()
+    );
+    status!("{}", "  Press Ctrl+C to stop".bright_black());
+    status!();
+
+    // Written once listening, so scripts can wait for it before starting their services
+    if output::is_machine() {
+        output::emit(&serde_json::json!({
+            "project": target.project,
+            "environment": target.environment,
+            "url": format!("http://{}", addr),
+            "token_file": token_file,
+        }))?;
+    }
+
+    let shutdown = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    let result = tokio::select! {
+        result = server::serve(listener, updates, &session_token, shutdown) => result,
+        result = watcher.keep_watching(&target.project, &target.environment) => result,
+    };
+    let _ = std::fs::remove_file(&token_file);
+    result?;
+
+    status!("{}", "✓ Server stopped".green());
+    Ok(())
+}
+
+/// `serve-<port>.token` in the user's runtime directory, next to the shared memory key
+fn default_token_file(port: u16) -> Result<PathBuf> {
+    let dir = match dirs::runtime_dir() {
+        Some(dir) => dir.join("envsafe-cli"),
+        None => Config::config_dir()?,
+    };
+    Ok(dir.join(format!("serve-{}.token", port)))
+}
+
+/// Write the session token readable by the current user only
+fn write_token(path: &Path, token: &str) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // An existing file keeps its mode when opened
+        if path.exists() {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+    }
+
+    let mut file = options.open(path)?;
+    file.write_all(token.as_bytes())?;
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use crate::api::ApiClient;
+use crate::config::Config;
+use crate::status;
+use crate::utils::env_parser::is_valid_key;
+use crate::utils::output;
+use crate::utils::prompt::{confirm, is_protected_environment};
+use crate::utils::target::RemoteTarget;
+use anyhow::Result;
+use colored::*;
+use std::collections::HashMap;
+use std::io::Read;
+
+/// Print the raw value of a single variable (no decoration, suitable for scripts)
+pub async fn get(project: Option<String>, environment: Option<String>, key: &str) -> Result<()> {
+    if !is_valid_key(key) {
+        anyhow::bail!("Invalid variable name '{}'", key);
+    }
+
+    let config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+    let target = RemoteTarget::resolve(project, environment)?;
+    target.validate(&api_client, &token).await?;
+
+    let value = api_client
+        .get_variable(&token, &target.project, &target.environment, key)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Variable '{}' not found in {}", key, target))?;
+
+    if output::is_machine() {
+        return output::emit(&serde_json::json!({ "key": key, "value": value }));
+    }
+
+    println!("{}", value);
+
+    Ok(())
+}
+
+/// Set one or more variables without touching the rest of the environment
+pub async fn set(
+    project: Option<String>,
+    environment: Option<String>,
+    assignments: Vec<String>,
+    from_stdin: bool,
+    yes: bool,
+) -> Result<()> {
+    let config = Config::load()?;
+    let target = RemoteTarget::resolve(project, environment)?;
+
+    let variables = if from_stdin {
+        // Keep secrets out of shell history: `echo -n $SECRET | envsafe set KEY --stdin`
+        if assignments.len() != 1 || assignments[0].contains('=') {
+            anyhow::bail!("--stdin expects exactly one KEY without a value");
+        }
+        let mut value = String::new();
+        std::io::stdin().read_to_string(&mut value)?;
+        let value = value.trim_end_matches(['\n', '\r']).to_string();
+
+        HashMap::from([(assignments[0].clone(), value)])
+    } else {
+        let mut variables = HashMap::new();
+        for assignment in &assignments {
+            let (key, value) = assignment.split_once('=').ok_or_else(|| {
+                anyhow::anyhow!("Invalid assignment '{}'. Expected KEY=VALUE", assignment)
+            })?;
+            variables.insert(key.trim().to_string(), value.to_string());
+        }
+        variables
+    };
+
+    if let Some(invalid) = variables.keys().find(|k| !is_valid_key(k)) {
+        anyhow::bail!("Invalid variable name '{}'", invalid);
+    }
+
+    let mut keys: Vec<String> = variables.keys().cloned().collect();
+    keys.sort();
+    let key_list = keys.join(", ");
+
+    if is_protected_environment(&target.environment) {
+        let prompt = format!("Set {} in {}?", key_list, target);
+        if !confirm(&prompt, yes)? {
+            status!("{}", "✗ Cancelled".yellow());
+            return output::cancelled();
+        }
+    }
+
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+    target.validate(&api_client, &token).await?;
+
+    api_client
+        .upsert_variables(&token, &target.project, &target.environment, variables)
+        .await?;
+
+    status!("{}", format!("✓ Set {} in {}", key_list, target).green());
+
+    if output::is_machine() {
+        output::emit(&serde_json::json!({
+            "project": target.project,
+            "environment": target.environment,
+            "set": keys,
+        }))?;
+    }
+
+    Ok(())
+}
+
+/// Delete one or more variables without touching the rest of the environment
+pub async fn unset(
+    project: Option<String>,
+    environment: Option<String>,
+    keys: Vec<String>,
+    yes: bool,
+) -> Result<()> {
+    if let Some(invalid) = keys.iter().find(|k| !is_valid_key(k)) {
+        anyhow::bail!("Invalid variable name '{}'", invalid);
+    }
+
+    let config = Config::load()?;
+    let target = RemoteTarget::resolve(project, environment)?;
+    let key_list = keys.join(", ");
+
+    if is_protected_environment(&target.environment) {
+        let prompt = format!("Delete {} from {}?", key_list, target);
+        if !confirm(&prompt, yes)? {
+            status!("{}", "✗ Cancelled".yellow());
+            return output::cancelled();
+        }
+    }
+
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+    target.validate(&api_client, &token).await?;
+
+    api_client
+        .remove_variables(&token, &target.project, &target.environment, &keys)
+        .await?;
+
+    status!(
+        "{}",
+        format!("✓ Removed {} from {}", key_list, target).green()
+    );
+
+    if output::is_machine() {
+        output::emit(&serde_json::json!({
+            "project": target.project,
+            "environment": target.environment,
+            "removed": keys,
+        }))?;
+    }
+
+    Ok(())
+}
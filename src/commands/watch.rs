@@ -1,49 +1,92 @@
 use crate::api::ApiClient;
-use crate::config::{Config, ProjectConfig};
+use crate::config::{Config, ProjectConfig, DEFAULT_ENVIRONMENT};
+use crate::status;
+use crate::storage;
+use crate::utils::env_parser::FileFormat;
 use crate::utils::i18n::get_translations;
+use crate::utils::output;
+use crate::utils::pattern::KeyFilter;
+use crate::utils::target::validate_environment;
 use crate::watcher::EnvWatcher;
 use anyhow::Result;
 use colored::*;
+use serde_json::json;
+use tokio::sync::watch;
 
 pub async fn execute(
     project: Option<String>,
     environment: Option<String>,
-    _file_path: &str,
+    file: Option<String>,
+    format: Option<FileFormat>,
 ) -> Result<()> {
     let config = Config::load()?;
     let t = get_translations(&config.language);
+    let local_config = ProjectConfig::load()?;
+    let (file_path, format) = ProjectConfig::local_file(local_config.as_ref(), file, format);
 
-    let (identifier, env_name) = if let Some(proj) = project {
-        let env = environment.unwrap_or_else(|| "development".to_string());
-        (proj, env)
-    } else if let Some(local_config) = ProjectConfig::load()? {
-        let env = environment.unwrap_or_else(|| "development".to_string());
+    let (identifier, env_name, filter) = if let Some(proj) = project {
+        let env = environment.unwrap_or_else(|| DEFAULT_ENVIRONMENT.to_string());
+        (proj, env, KeyFilter::default())
+    } else if let Some(local_config) = local_config {
+        let env = environment.unwrap_or_else(|| local_config.environment());
         let id = local_config.project_slug.unwrap_or(local_config.project_id);
-        (id, env)
+        (id, env, local_config.filter)
     } else {
         // Hard to translate ...
         anyhow::bail!("No project specified. Run 'envsafe init' or provide project name");
     };
 
-    println!("{}", t.watch.title.cyan().bold());
-    println!();
-    println!("{}", format!("  Project: {}", identifier).bright_black());
-    println!("{}", format!("  Environment: {}", env_name).bright_black());
-    println!();
-    println!("{}", t.watch.sync_start.cyan());
-    println!("{}", t.watch.remote_to_local.bright_black());
-    println!("{}", t.watch.local_to_remote.bright_black()); // Note: local->remote sync isn't fully implemented yet but message was there
-    println!();
+    status!("{}", t.watch.title.cyan().bold());
+    status!();
+    status!("{}", format!("  Project: {}", identifier).bright_black());
+    status!("{}", format!("  Environment: {}", env_name).bright_black());
+    status!("{}", format!("  File: {}", file_path).bright_black());
+    status!();
+    status!("{}", t.watch.sync_start.cyan());
+    status!("{}", t.watch.remote_to_local.bright_black());
+    status!("{}", t.watch.local_to_remote.bright_black()); // Note: local->remote sync isn't fully implemented yet but message was there
+    status!();
 
     let api_client = ApiClient::from_config(&config);
+    let token = config.get_token()?;
+    validate_environment(&api_client, &token, &identifier, &env_name).await?;
+
     // TODO: Pass translations to watcher if it prints logs
-    let mut watcher = EnvWatcher::new(api_client, config)?;
+    let key = storage::current_key(&config, &identifier, &env_name)?;
+    let (sender, mut updates) = watch::channel(None);
+    let mut watcher = EnvWatcher::new(api_client, config, &key)?
+        .with_file(file_path.clone(), format, filter)
+        .with_updates(sender);
+
+    status!("{}", t.watch.press_ctrl_c.bright_black());
+    status!();
 
-    println!("{}", t.watch.press_ctrl_c.bright_black());
-    println!();
+    if !output::is_machine() {
+        // Start remote monitoring loop
+        watcher.watch_remote(&identifier, &env_name).await?;
+        return Ok(());
+    }
 
-    // Start remote monitoring loop
-    watcher.watch_remote(&identifier, &env_name).await?;
+    // One document per update written to the file, as it happens
+    let report = async {
+        while updates.changed().await.is_ok() {
+            let snapshot = updates.borrow_and_update().clone();
+            if let Some(data) = snapshot {
+                output::emit_event(&json!({
+                    "project": identifier,
+                    "environment": env_name,
+                    "file": file_path,
+                    "version": data.version,
+                    "variables": data.variables.len(),
+                    "last_updated": data.last_updated,
+                }))?;
+            }
+        }
+        Ok::<_, anyhow::Error>(())
+    };
 
-    Ok(())
+    tokio::select! {
+        result = watcher.watch_remote(&identifier, &env_name) => result,
+        result = report => result,
+    }
 }
@@ -1,6 +1,8 @@
 use crate::api::ApiClient;
 use crate::config::Config;
+use crate::status;
 use crate::utils::i18n::get_translations;
+use crate::utils::output::{self, OutputFormat};
 use anyhow::Result;
 use colored::*;
 
@@ -13,8 +15,25 @@ pub async fn execute() -> Result<()> {
 
     let user = api_client.get_user(&token).await?;
 
-    println!("{}", t.whoami.title.cyan().bold());
-    println!();
+    if output::is_machine() {
+        return output::emit(&user);
+    }
+
+    if output::format() == OutputFormat::Table {
+        output::print_table(
+            &["id", "name", "email", "last_login"],
+            &[vec![
+                user.id.clone(),
+                user.name.clone(),
+                user.email.clone(),
+                user.last_login.clone().unwrap_or_default(),
+            ]],
+        );
+        return Ok(());
+    }
+
+    status!("{}", t.whoami.title.cyan().bold());
+    status!();
 
     // Use replace to inject values into translated strings containing "{}"
     println!(
@@ -31,7 +50,7 @@ pub async fn execute() -> Result<()> {
     );
 
     if let Some(last_login) = user.last_login {
-        println!(
+        status!(
             "{}",
             t.whoami
                 .last_login
@@ -0,0 +1,121 @@
+use crate::api::ApiClient;
+use crate::config::Config;
+use crate::status;
+use crate::utils::output;
+use anyhow::Result;
+use colored::*;
+
+pub async fn list(json: bool) -> Result<()> {
+    let config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+
+    let workspaces = api_client.get_workspaces(&token).await?;
+
+    if json || output::is_machine() {
+        return output::emit(&workspaces);
+    }
+
+    status!("{}", "📦 Workspaces".cyan().bold());
+    status!();
+
+    if workspaces.is_empty() {
+        status!("{}", "  No workspaces found".bright_black());
+        return Ok(());
+    }
+
+    for workspace in &workspaces {
+        let current = config.current_workspace.as_deref() == Some(workspace.id.as_str());
+        let marker = if current { "*".green() } else { "•".cyan() };
+        println!(
+            "  {} {} {}",
+            marker,
+            workspace.name.bright_white(),
+            workspace.role.as_deref().unwrap_or_default().bright_black()
+        );
+        println!(
+            "    {}",
+            workspace
+                .slug
+                .as_deref()
+                .unwrap_or(&workspace.id)
+                .bright_black()
+        );
+    }
+
+    Ok(())
+}
+
+/// Show a workspace, the linked one by default
+pub async fn show(workspace: Option<String>, json: bool) -> Result<()> {
+    let config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+    let identifier = resolve_workspace(&config, workspace)?;
+
+    let workspace = api_client.get_workspace(&token, &identifier).await?;
+
+    if json || output::is_machine() {
+        return output::emit(&workspace);
+    }
+
+    status!("{}", format!("📦 {}", workspace.name).cyan().bold());
+    status!();
+    println!("  ID: {}", workspace.id.bright_black());
+    if let Some(slug) = &workspace.slug {
+        println!("  Slug: {}", slug.bright_black());
+    }
+    if let Some(description) = &workspace.description {
+        println!("  Description: {}", description);
+    }
+    if let Some(role) = &workspace.role {
+        println!("  Your role: {}", role);
+    }
+    if let Some(count) = workspace.project_count {
+        println!("  Projects: {}", count);
+    }
+
+    Ok(())
+}
+
+pub async fn members(workspace: Option<String>, json: bool) -> Result<()> {
+    let config = Config::load()?;
+    let token = config.get_token()?;
+    let api_client = ApiClient::from_config(&config);
+    let identifier = resolve_workspace(&config, workspace)?;
+
+    let members = api_client
+        .get_workspace_members(&token, &identifier)
+        .await?;
+
+    if json || output::is_machine() {
+        return output::emit(&members);
+    }
+
+    status!("{}", format!("👥 Members of {}", identifier).cyan().bold());
+    status!();
+
+    if members.is_empty() {
+        status!("{}", "  No members found".bright_black());
+        return Ok(());
+    }
+
+    for member in &members {
+        println!(
+            "  {} {} {} {}",
+            "•".cyan(),
+            member.name.as_deref().unwrap_or("-").bright_white(),
+            member.email.bright_black(),
+            format!("({})", member.role).yellow()
+        );
+    }
+
+    Ok(())
+}
+
+fn resolve_workspace(config: &Config, workspace: Option<String>) -> Result<String> {
+    match workspace {
+        Some(workspace) => Ok(workspace),
+        None => config.get_workspace_slug(),
+    }
+}
@@ -1,8 +1,14 @@
+use crate::api::Project;
+use crate::utils::env_parser::FileFormat;
+use crate::utils::git;
+use crate::utils::pattern::{glob_match, KeyFilter};
+use crate::utils::suggest::not_found;
 use anyhow::Result;
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -18,6 +24,18 @@ pub struct Config {
     #[serde(default)]
     pub current_project_slug: Option<String>,
     pub rotation: RotationConfig,
+    /// Custom environment shortcuts, e.g. `qa -> quality-assurance`
+    #[serde(default)]
+    pub env_aliases: BTreeMap<String, String>,
+    /// Seconds after which `run` stops trusting the shared memory cache (0 disables it)
+    #[serde(default = "default_cache_max_age")]
+    pub cache_max_age: u64,
+}
+
+pub const DEFAULT_CACHE_MAX_AGE: u64 = 60 * 60;
+
+fn default_cache_max_age() -> u64 {
+    DEFAULT_CACHE_MAX_AGE
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,6 +64,8 @@ impl Default for Config {
                 exclude_vars: vec![],
                 last_rotation: None,
             },
+            env_aliases: BTreeMap::new(),
+            cache_max_age: DEFAULT_CACHE_MAX_AGE,
         }
     }
 }
@@ -77,11 +97,31 @@ impl Config {
         Ok(())
     }
 
+    /// Expand an environment alias to the real environment name.
+    ///
+    /// Custom aliases take precedence over the built-in `dev`, `staging` and `prod` shortcuts.
+    pub fn resolve_environment(&self, name: &str) -> String {
+        if let Some(target) = self.env_aliases.get(name) {
+            return target.clone();
+        }
+
+        match name {
+            "dev" => "development".to_string(),
+            "prod" => "production".to_string(),
+            _ => name.to_string(),
+        }
+    }
+
     fn config_path() -> Result<PathBuf> {
+        Ok(Self::config_dir()?.join("config.json"))
+    }
+
+    /// Directory holding the CLI's global configuration and local state
+    pub fn config_dir() -> Result<PathBuf> {
         let config_dir =
             dirs::config_dir().ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?;
 
-        Ok(config_dir.join("envsafe-cli").join("config.json"))
+        Ok(config_dir.join("envsafe-cli"))
     }
 
     pub fn get_token(&self) -> Result<String> {
@@ -130,33 +170,266 @@ impl Config {
     }
 }
 
+/// Environment used when neither a flag nor `.envsafe` names one
+pub const DEFAULT_ENVIRONMENT: &str = "development";
+
+/// File `pull`, `push` and `watch` use when neither a flag nor `.envsafe` names one
+pub const DEFAULT_FILE: &str = ".env";
+
 /// Project configuration stored in local directory
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProjectConfig {
     pub workspace_id: String,
     pub project_id: String,
     pub project_name: String,
     #[serde(default)]
     pub project_slug: Option<String>,
+    /// Environment used by commands run in this directory without `--env`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_environment: Option<String>,
+    /// File `pull`, `push` and `watch` use without `--file`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_file: Option<String>,
+    /// Format of that file, `dotenv` unless configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<FileFormat>,
+    /// Keys this directory works with
+    #[serde(flatten)]
+    pub filter: KeyFilter,
+    /// Git branch (or glob such as `release/*`) to environment
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub branches: BTreeMap<String, String>,
+    /// Monorepo services by name, each in its own subdirectory
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub services: BTreeMap<String, ServiceConfig>,
+    /// Directory the settings apply to, relative to the working directory
+    #[serde(skip)]
+    pub dir: PathBuf,
+    /// Service these settings were resolved for
+    #[serde(skip)]
+    pub service: Option<String>,
+    /// The `.envsafe` file this was loaded from
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+/// A service of a monorepo `.envsafe`; unset fields fall back to the root settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServiceConfig {
+    /// Subdirectory of the service, relative to the root `.envsafe`
+    pub path: String,
+    /// Project slug or ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    /// Relative to the service directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<FileFormat>,
+    #[serde(flatten)]
+    pub filter: KeyFilter,
 }
 
 impl ProjectConfig {
+    /// The directory's default environment: the current git branch's mapping,
+    /// then `default_environment`, then `development`
+    pub fn environment(&self) -> String {
+        let from_branch = if self.branches.is_empty() {
+            None
+        } else {
+            git::current_branch().and_then(|branch| self.branch_environment(&branch))
+        };
+
+        from_branch
+            .or_else(|| self.default_environment.clone())
+            .unwrap_or_else(|| DEFAULT_ENVIRONMENT.to_string())
+    }
+
+    /// Environment mapped to `branch`; an exact name wins over the longest matching glob
+    pub fn branch_environment(&self, branch: &str) -> Option<String> {
+        if let Some(environment) = self.branches.get(branch) {
+            return Some(environment.clone());
+        }
+
+        self.branches
+            .iter()
+            .filter(|(pattern, _)| glob_match(pattern, branch))
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map(|(_, environment)| environment.clone())
+    }
+
+    /// The local file and its format: flags first, then `.envsafe`, then `.env` as dotenv
+    pub fn local_file(
+        project_config: Option<&Self>,
+        file: Option<String>,
+        format: Option<FileFormat>,
+    ) -> (String, FileFormat) {
+        let file = file
+            .or_else(|| {
+                project_config.map(|c| {
+                    let name = c.output_file.as_deref().unwrap_or(DEFAULT_FILE);
+                    c.dir.join(name).to_string_lossy().into_owned()
+                })
+            })
+            .unwrap_or_else(|| DEFAULT_FILE.to_string());
+        let format = format
+            .or_else(|| project_config.and_then(|c| c.format))
+            .unwrap_or_default();
+        (file, format)
+    }
+
+    /// Find the `.envsafe` for the working directory, see [`ProjectConfig::find_from`]
     pub fn load() -> Result<Option<Self>> {
-        let config_path = PathBuf::from(".envsafe");
+        Self::find_from(&std::env::current_dir()?)
+    }
 
-        if !config_path.exists() {
+    /// Look for `.envsafe` in `start` and its parents, stopping at the git repository root.
+    ///
+    /// When `start` is inside one of the file's services, that service's settings are returned.
+    pub fn find_from(start: &Path) -> Result<Option<Self>> {
+        let Some(config) = Self::find_root_from(start)? else {
             return Ok(None);
+        };
+
+        let root_dir = match config.source.as_deref().and_then(Path::parent) {
+            Some(dir) => dir.to_path_buf(),
+            None => return Ok(Some(config)),
+        };
+
+        // The deepest service containing the working directory
+        let service = config
+            .services
+            .iter()
+            .filter(|(_, service)| start.starts_with(root_dir.join(&service.path)))
+            .max_by_key(|(_, service)| Path::new(&service.path).components().count());
+
+        match service {
+            Some((name, service)) => {
+                let mut resolved = config.for_service(name)?;
+                resolved.dir = relative_path(start, &root_dir.join(&service.path));
+                Ok(Some(resolved))
+            }
+            None => Ok(Some(config)),
         }
+    }
 
-        let content = fs::read_to_string(&config_path)?;
-        let config: ProjectConfig = serde_json::from_str(&content)?;
-        Ok(Some(config))
+    /// The nearest `.envsafe` as written, without selecting a service
+    pub fn find_root_from(start: &Path) -> Result<Option<Self>> {
+        for dir in start.ancestors() {
+            let config_path = dir.join(".envsafe");
+
+            if config_path.is_file() {
+                let mut config = Self::read(&config_path)?;
+                config.dir = relative_path(start, dir);
+                return Ok(Some(config));
+            }
+
+            if dir.join(".git").exists() {
+                break;
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The `.envsafe` in `dir` as written, or empty settings to be saved there.
+    ///
+    /// `init` and `select` relink through this so the rest of the file is kept.
+    pub fn load_in(dir: &Path) -> Result<Self> {
+        let config_path = dir.join(".envsafe");
+        if config_path.is_file() {
+            return Self::read(&config_path);
+        }
+        Ok(Self {
+            source: Some(config_path),
+            ..Default::default()
+        })
+    }
+
+    fn read(config_path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(config_path)?;
+        let mut config: ProjectConfig = serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid {}: {}", config_path.display(), e))?;
+        config.source = Some(config_path.to_path_buf());
+        Ok(config)
+    }
+
+    /// Point these settings at `project`, leaving the rest untouched
+    pub fn link(&mut self, workspace_id: &str, project: &Project) {
+        self.workspace_id = workspace_id.to_string();
+        self.project_id = project.id.clone();
+        self.project_name = project.name.clone();
+        self.project_slug = project.slug.clone();
+    }
+
+    /// Settings of a service of the monorepo `.envsafe` found from the working directory
+    pub fn load_service(name: &str) -> Result<Self> {
+        Self::find_root_from(&std::env::current_dir()?)?
+            .ok_or_else(|| {
+                anyhow::anyhow!("No .envsafe found. Run 'envsafe init' in the repository root")
+            })?
+            .for_service(name)
     }
 
+    /// Merge a service's settings over the root ones
+    pub fn for_service(&self, name: &str) -> Result<Self> {
+        let service = self.services.get(name).ok_or_else(|| {
+            let names: Vec<&str> = self.services.keys().map(|n| n.as_str()).collect();
+            not_found("Service", name, &names)
+        })?;
+
+        let mut config = self.clone();
+        if let Some(project) = &service.project {
+            config.project_id = project.clone();
+            config.project_name = project.clone();
+            config.project_slug = Some(project.clone());
+        }
+        if service.environment.is_some() {
+            config.default_environment = service.environment.clone();
+        }
+        config.output_file = service.output_file.clone();
+        config.format = service.format.or(self.format);
+        if !service.filter.is_empty() {
+            config.filter = service.filter.clone();
+        }
+        config.dir = self.dir.join(&service.path);
+        config.service = Some(name.to_string());
+        Ok(config)
+    }
+
+    /// Write to the file this was loaded from, or to `./.envsafe` for a new link
     pub fn save(&self) -> Result<()> {
-        let config_path = PathBuf::from(".envsafe");
+        if let Some(service) = &self.service {
+            anyhow::bail!(
+                "Service '{}' is configured in the monorepo .envsafe; edit that file instead",
+                service
+            );
+        }
+
+        let config_path = self
+            .source
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(".envsafe"));
         let content = serde_json::to_string_pretty(self)?;
         fs::write(&config_path, content)?;
         Ok(())
     }
 }
+
+/// Path leading from directory `from` to `to`, both absolute
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut path = PathBuf::new();
+    for _ in common..from.len() {
+        path.push("..");
+    }
+    for component in &to[common..] {
+        path.push(component);
+    }
+    path
+}
@@ -1,8 +1,12 @@
 // Re-export public modules for testing
+#[cfg(unix)]
+pub mod agent;
 pub mod api;
 pub mod config;
 pub mod rotation;
+pub mod server;
 pub mod storage;
+pub mod sync_state;
 pub mod utils;
 pub mod watcher;
 
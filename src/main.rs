@@ -1,13 +1,21 @@
+#[cfg(unix)]
+mod agent;
 mod api;
 mod commands;
 mod config;
 mod rotation;
+mod server;
 mod storage;
+mod sync_state;
 mod utils;
 mod watcher;
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use std::ffi::OsString;
+use utils::duration::parse_duration;
+use utils::env_parser::FileFormat;
+use utils::output::OutputFormat;
 
 #[derive(Parser)]
 #[command(name = "envsafe")]
@@ -16,6 +24,18 @@ use clap::{Parser, Subcommand};
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Output format; json and yaml write a document to stdout and diagnostics to stderr
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// Suppress decoration and progress messages
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Accessible output: no colors and no emoji
+    #[arg(long, global = true)]
+    plain: bool,
 }
 
 #[derive(Subcommand)]
@@ -37,7 +57,23 @@ enum Commands {
     },
 
     /// Initialize a project
-    Init,
+    Init {
+        /// Workspace ID, slug or name (default: the linked workspace, or ask)
+        #[arg(long)]
+        workspace: Option<String>,
+
+        /// Project ID, slug or name (asked interactively when omitted)
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Default environment for commands run in this directory
+        #[arg(short, long)]
+        env: Option<String>,
+
+        /// Create the project if it does not exist
+        #[arg(long, requires = "project")]
+        create: bool,
+    },
 
     /// List projects in the current workspace
     #[command(alias = "ls")]
@@ -59,6 +95,18 @@ enum Commands {
     /// List all accessible projects
     Projects,
 
+    /// Rename, describe, archive or delete a project
+    Project {
+        #[command(subcommand)]
+        action: ProjectAction,
+    },
+
+    /// Inspect workspaces and their members
+    Workspace {
+        #[command(subcommand)]
+        action: WorkspaceAction,
+    },
+
     /// Download environment variables
     Pull {
         /// Project ID or name
@@ -80,9 +128,29 @@ enum Commands {
         #[arg(short, long)]
         prod: bool,
 
-        /// Output file path
-        #[arg(short, long, default_value = ".env")]
-        output: String,
+        /// Destination file path (default: from .envsafe, otherwise .env)
+        #[arg(short, long, short_alias = 'o')]
+        file: Option<String>,
+
+        /// File format (default: from .envsafe, otherwise dotenv)
+        #[arg(long, value_enum)]
+        format: Option<FileFormat>,
+
+        /// Show what would change without writing the file
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Reveal values in the diff instead of masking them
+        #[arg(long)]
+        show_values: bool,
+
+        /// Skip the confirmation prompt for production
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Pull every service of the monorepo .envsafe into its own directory
+        #[arg(long, conflicts_with_all = ["project", "file", "format"])]
+        all_services: bool,
     },
 
     /// Upload environment variables
@@ -106,9 +174,309 @@ enum Commands {
         #[arg(short, long)]
         prod: bool,
 
-        /// Source file path
-        #[arg(short, long, default_value = ".env")]
-        file: String,
+        /// Source file path (default: from .envsafe, otherwise .env)
+        #[arg(short, long)]
+        file: Option<String>,
+
+        /// File format (default: from .envsafe, otherwise dotenv)
+        #[arg(long, value_enum)]
+        format: Option<FileFormat>,
+
+        /// merge: only add/update keys; replace: mirror the file exactly, deleting missing keys
+        #[arg(short, long, value_enum, default_value_t = commands::push::PushMode::Merge)]
+        mode: commands::push::PushMode,
+
+        /// Comma-separated subset of keys to push from the file
+        #[arg(short, long, value_delimiter = ',')]
+        keys: Vec<String>,
+
+        /// Overwrite the remote even if it changed since your last pull
+        #[arg(long)]
+        force: bool,
+
+        /// Show what would change remotely without pushing
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Reveal values in the diff instead of masking them
+        #[arg(long)]
+        show_values: bool,
+
+        /// Skip the confirmation prompt for production
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Compare variables between environments, projects or local files
+    ///
+    /// Exits like diff(1): 0 when both sides are identical, 1 when they differ, 2 on errors.
+    Diff {
+        /// Left side: <project>/<env>, an environment of the current project, a file, or "shm[:<target>]"
+        left: String,
+
+        /// Right side: <project>/<env>, an environment of the current project, a file, or "shm[:<target>]"
+        right: String,
+
+        /// Output the comparison as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Reveal values instead of masking them
+        #[arg(long)]
+        show_values: bool,
+    },
+
+    /// Copy variables from one environment to another
+    Promote {
+        /// Source: <env> of the current project or <project>/<env>
+        #[arg(long)]
+        from: String,
+
+        /// Destination: <env> of the current project or <project>/<env>
+        #[arg(long)]
+        to: String,
+
+        /// Comma-separated list of keys to promote
+        #[arg(short, long, value_delimiter = ',', required_unless_present = "all")]
+        keys: Vec<String>,
+
+        /// Promote every key of the source environment
+        #[arg(long, conflicts_with = "keys")]
+        all: bool,
+
+        /// Overwrite keys that already have a different value in the destination
+        #[arg(long)]
+        force: bool,
+
+        /// Reveal values in the diff instead of masking them
+        #[arg(long)]
+        show_values: bool,
+
+        /// Skip the confirmation prompt for production
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Print the value of a single variable
+    Get {
+        /// Variable name
+        key: String,
+
+        /// Project ID or name
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Environment name
+        #[arg(short, long)]
+        env: Option<String>,
+
+        /// Development environment (shortcut)
+        #[arg(short, long)]
+        dev: bool,
+
+        /// Staging environment (shortcut)
+        #[arg(short, long)]
+        staging: bool,
+
+        /// Production environment (shortcut)
+        #[arg(short, long)]
+        prod: bool,
+    },
+
+    /// Set one or more variables (KEY=VALUE)
+    Set {
+        /// Assignments as KEY=VALUE, or a single KEY with --stdin
+        #[arg(required = true)]
+        assignments: Vec<String>,
+
+        /// Read the value from stdin to keep it out of shell history
+        #[arg(long)]
+        stdin: bool,
+
+        /// Project ID or name
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Environment name
+        #[arg(short, long)]
+        env: Option<String>,
+
+        /// Development environment (shortcut)
+        #[arg(short, long)]
+        dev: bool,
+
+        /// Staging environment (shortcut)
+        #[arg(short, long)]
+        staging: bool,
+
+        /// Production environment (shortcut)
+        #[arg(short, long)]
+        prod: bool,
+
+        /// Skip the confirmation prompt for production
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Remove one or more variables
+    Unset {
+        /// Variable names
+        #[arg(required = true)]
+        keys: Vec<String>,
+
+        /// Project ID or name
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Environment name
+        #[arg(short, long)]
+        env: Option<String>,
+
+        /// Development environment (shortcut)
+        #[arg(short, long)]
+        dev: bool,
+
+        /// Staging environment (shortcut)
+        #[arg(short, long)]
+        staging: bool,
+
+        /// Production environment (shortcut)
+        #[arg(short, long)]
+        prod: bool,
+
+        /// Skip the confirmation prompt for production
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Show past versions of variables
+    History {
+        /// Only show changes to this key
+        key: Option<String>,
+
+        /// Project ID or name
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Environment name
+        #[arg(short, long)]
+        env: Option<String>,
+
+        /// Development environment (shortcut)
+        #[arg(short, long)]
+        dev: bool,
+
+        /// Staging environment (shortcut)
+        #[arg(short, long)]
+        staging: bool,
+
+        /// Production environment (shortcut)
+        #[arg(short, long)]
+        prod: bool,
+
+        /// Maximum number of entries to show
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+
+    /// Restore variables to a previous version
+    Rollback {
+        /// Version number, RFC 3339 timestamp or YYYY-MM-DD date
+        #[arg(long)]
+        to: api::SnapshotPoint,
+
+        /// Comma-separated list of keys to restore (default: all)
+        #[arg(short, long, value_delimiter = ',')]
+        keys: Vec<String>,
+
+        /// Project ID or name
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Environment name
+        #[arg(short, long)]
+        env: Option<String>,
+
+        /// Development environment (shortcut)
+        #[arg(short, long)]
+        dev: bool,
+
+        /// Staging environment (shortcut)
+        #[arg(short, long)]
+        staging: bool,
+
+        /// Production environment (shortcut)
+        #[arg(short, long)]
+        prod: bool,
+
+        /// Reveal values in the diff instead of masking them
+        #[arg(long)]
+        show_values: bool,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Show who read or changed secrets
+    Audit {
+        /// Project ID or name
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Only this environment (default: all environments)
+        #[arg(short, long)]
+        env: Option<String>,
+
+        /// Development environment (shortcut)
+        #[arg(short, long)]
+        dev: bool,
+
+        /// Staging environment (shortcut)
+        #[arg(short, long)]
+        staging: bool,
+
+        /// Production environment (shortcut)
+        #[arg(short, long)]
+        prod: bool,
+
+        /// Filter by user (email or ID)
+        #[arg(short, long)]
+        user: Option<String>,
+
+        /// Filter by action (read, create, update, delete)
+        #[arg(short, long)]
+        action: Option<String>,
+
+        /// Filter by variable name
+        #[arg(short, long)]
+        key: Option<String>,
+
+        /// Only entries after this time (RFC 3339, YYYY-MM-DD or relative like 7d)
+        #[arg(long, value_parser = commands::audit::parse_time)]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Only entries before this time (RFC 3339, YYYY-MM-DD or relative like 7d)
+        #[arg(long, value_parser = commands::audit::parse_time)]
+        until: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Maximum number of entries to fetch
+        #[arg(short, long, default_value = "100")]
+        limit: usize,
+
+        /// Output entries as JSON
+        #[arg(long, conflicts_with = "csv")]
+        json: bool,
+
+        /// Output entries as CSV
+        #[arg(long)]
+        csv: bool,
+    },
+
+    /// Manage the environments of a project
+    Env {
+        #[command(subcommand)]
+        action: EnvAction,
     },
 
     /// Run a command with injected variables
@@ -117,6 +485,10 @@ enum Commands {
         #[arg(long)]
         project: Option<String>,
 
+        /// Service of the monorepo .envsafe to load variables for
+        #[arg(long, conflicts_with = "project")]
+        service: Option<String>,
+
         /// Command to execute
         #[arg(required = true, trailing_var_arg = true)]
         command: Vec<String>,
@@ -136,6 +508,22 @@ enum Commands {
         /// Production environment (shortcut)
         #[arg(short, long)]
         prod: bool,
+
+        /// Fetch from EnvSafe and leave the shared memory cache untouched
+        #[arg(long, conflicts_with = "refresh")]
+        no_cache: bool,
+
+        /// Fetch from EnvSafe and update the shared memory cache
+        #[arg(long)]
+        refresh: bool,
+
+        /// Ignore cached variables older than this, e.g. 15m (default: 'envsafe config --cache-max-age')
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        max_age: Option<std::time::Duration>,
+
+        /// Check with the server that the cached variables are still current before using them
+        #[arg(long, conflicts_with_all = ["no_cache", "refresh"])]
+        validate: bool,
     },
 
     /// Start real-time variable monitoring
@@ -159,9 +547,50 @@ enum Commands {
         #[arg(short, long)]
         prod: bool,
 
-        /// File to watch
-        #[arg(short, long, default_value = ".env")]
-        file: String,
+        /// File to keep in sync (default: from .envsafe, otherwise .env)
+        #[arg(short, long)]
+        file: Option<String>,
+
+        /// File format (default: from .envsafe, otherwise dotenv)
+        #[arg(long, value_enum)]
+        format: Option<FileFormat>,
+    },
+
+    /// Inspect and clear the shared memory cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
+    /// Background agent serving watched environments over a local socket
+    Agent {
+        #[command(subcommand)]
+        action: AgentAction,
+
+        /// Socket path (default: $ENVSAFE_AGENT_SOCKET, otherwise in the runtime directory)
+        #[arg(long, global = true)]
+        socket: Option<std::path::PathBuf>,
+    },
+
+    /// Check the setup and report leftover shared memory segments
+    Doctor,
+
+    /// Serve an environment over loopback HTTP, for containers and sidecars
+    Serve {
+        /// <project>/<env> or an environment of the current project (default: current)
+        target: Option<String>,
+
+        /// Loopback address to listen on
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: std::net::IpAddr,
+
+        /// Port to listen on (0 picks a free one)
+        #[arg(short, long, default_value_t = server::DEFAULT_PORT)]
+        port: u16,
+
+        /// File to write the bearer token to (default: in the runtime directory)
+        #[arg(long)]
+        token_file: Option<std::path::PathBuf>,
     },
 
     /// Manage secret rotation
@@ -176,6 +605,10 @@ enum Commands {
         #[arg(long)]
         api_url: Option<String>,
 
+        /// How long 'envsafe run' trusts the shared memory cache, e.g. 15m, 1h, 0 to disable
+        #[arg(long, value_name = "DURATION")]
+        cache_max_age: Option<String>,
+
         /// Show current configuration
         #[arg(long)]
         show: bool,
@@ -208,6 +641,232 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum ProjectAction {
+    /// Show project details, or update its description
+    Describe {
+        /// Project ID or name (default: the linked project)
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Set a new description
+        #[arg(long)]
+        set: Option<String>,
+
+        /// Output the project as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Rename a project
+    Rename {
+        /// New project name
+        new_name: String,
+
+        /// Project ID or name (default: the linked project)
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Output the updated project as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Archive a project, or restore it with --undo
+    Archive {
+        /// Project ID or name (default: the linked project)
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Restore an archived project
+        #[arg(long)]
+        undo: bool,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Output the updated project as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Permanently delete a project
+    Delete {
+        /// Project ID or name (default: the linked project)
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum WorkspaceAction {
+    /// List workspaces you belong to
+    #[command(alias = "ls")]
+    List {
+        /// Output workspaces as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show workspace details
+    Show {
+        /// Workspace ID or slug (default: the linked workspace)
+        workspace: Option<String>,
+
+        /// Output the workspace as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// List workspace members and their roles
+    Members {
+        /// Workspace ID or slug (default: the linked workspace)
+        workspace: Option<String>,
+
+        /// Output members as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum EnvAction {
+    /// List environments
+    #[command(alias = "ls")]
+    List {
+        /// Project ID or name
+        #[arg(long)]
+        project: Option<String>,
+    },
+
+    /// Create an environment
+    Create {
+        /// Environment name
+        name: String,
+
+        /// Copy the variables of an existing environment
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Project ID or name
+        #[arg(long)]
+        project: Option<String>,
+    },
+
+    /// Delete an environment and its variables
+    #[command(alias = "rm")]
+    Delete {
+        /// Environment name
+        name: String,
+
+        /// Project ID or name
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Rename an environment
+    Rename {
+        /// Current environment name
+        name: String,
+
+        /// New environment name
+        new_name: String,
+
+        /// Project ID or name
+        #[arg(long)]
+        project: Option<String>,
+    },
+
+    /// Create an environment as a copy of another one
+    Clone {
+        /// Source environment
+        source: String,
+
+        /// Name of the new environment
+        name: String,
+
+        /// Project ID or name
+        #[arg(long)]
+        project: Option<String>,
+    },
+
+    /// List, add or remove shortcut aliases usable with -e (e.g. `qa` -> `quality-assurance`)
+    Alias {
+        /// Alias name (lists all aliases when omitted)
+        alias: Option<String>,
+
+        /// Environment the alias points to
+        target: Option<String>,
+
+        /// Remove the alias
+        #[arg(long, requires = "alias", conflicts_with = "target")]
+        remove: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+    /// List the cached environments
+    #[command(alias = "ls")]
+    List,
+
+    /// Delete a cached environment, the current one by default
+    Clear {
+        /// <project>/<env> or an environment of the current project
+        target: Option<String>,
+
+        /// Delete every cached environment
+        #[arg(long, conflicts_with = "target")]
+        all: bool,
+
+        /// Skip the confirmation prompt for --all
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Delete segments nothing uses any more (expired or left behind by a crash)
+    Purge {
+        /// Also delete fresh segments, unless a running watch, agent or serve holds them
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Show the variables cached for an environment, the current one by default
+    Inspect {
+        /// <project>/<env> or an environment of the current project
+        target: Option<String>,
+
+        /// Reveal values instead of masking them
+        #[arg(long)]
+        show_values: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum AgentAction {
+    /// Run the agent in the foreground
+    Start {
+        /// Environments to watch right away (<project>/<env> or an environment of the
+        /// current project); others are watched once requested
+        targets: Vec<String>,
+    },
+
+    /// Show whether an agent is running and what it watches
+    Status,
+
+    /// Stop the running agent
+    Stop,
+}
+
 #[derive(Subcommand)]
 enum RotateAction {
     /// Configure automatic rotation
@@ -235,32 +894,149 @@ enum RotateAction {
     },
 }
 
+impl Commands {
+    /// Whether the command renders `--output table`; the others only have text
+    fn has_table(&self) -> bool {
+        matches!(
+            self,
+            Commands::List
+                | Commands::Projects
+                | Commands::Whoami
+                | Commands::Cache {
+                    action: CacheAction::List
+                }
+                | Commands::Agent {
+                    action: AgentAction::Status,
+                    ..
+                }
+        )
+    }
+}
+
+/// `pull --output <file>` predates the global `--output <format>` flag. Until it is removed,
+/// a value that is not a format is taken as the file, with a warning.
+fn migrate_pull_output(mut args: Vec<OsString>) -> Vec<OsString> {
+    let Some(pull) = args.iter().position(|arg| arg == "pull") else {
+        return args;
+    };
+
+    let mut i = pull + 1;
+    while i < args.len() {
+        let arg = args[i].to_string_lossy().into_owned();
+        if arg == "--" {
+            break;
+        }
+
+        let (value, width) = if arg == "--output" {
+            match args.get(i + 1) {
+                Some(value) => (value.to_string_lossy().into_owned(), 2),
+                None => break,
+            }
+        } else if let Some(value) = arg.strip_prefix("--output=") {
+            (value.to_string(), 1)
+        } else {
+            i += 1;
+            continue;
+        };
+
+        if OutputFormat::from_str(&value, true).is_err() {
+            eprintln!(
+                "⚠ 'pull --output <file>' is deprecated and will be removed. Use 'pull --file {}'",
+                value
+            );
+            args.splice(
+                i..i + width,
+                [OsString::from("--file"), OsString::from(value)],
+            );
+        }
+        i += 2;
+    }
+
+    args
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize tracing
     tracing_subscriber::fmt::init();
 
-    let cli = Cli::parse();
+    let cli = Cli::parse_from(migrate_pull_output(std::env::args_os().collect()));
+    if cli.output == OutputFormat::Table && !cli.command.has_table() {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--output table is only supported by list, projects, whoami, cache list and agent status",
+            )
+            .exit();
+    }
+    utils::output::init(cli.output, cli.quiet, cli.plain);
 
     match cli.command {
         Commands::Login { token } => commands::login::execute(token).await?,
         Commands::Whoami => commands::whoami::execute().await?,
         Commands::Link { workspace } => commands::link::execute(workspace).await?,
-        Commands::Init => commands::init::execute().await?,
+        Commands::Init {
+            workspace,
+            project,
+            env,
+            create,
+        } => commands::init::execute(workspace, project, env, create).await?,
         Commands::List => commands::list::execute().await?,
         Commands::Select { project } => commands::select::execute(&project).await?,
         Commands::Create { name } => commands::create::execute(name).await?,
         Commands::Projects => commands::projects::execute().await?,
+        Commands::Project { action } => match action {
+            ProjectAction::Describe { project, set, json } => {
+                commands::project::describe(project, set, json).await?
+            }
+            ProjectAction::Rename {
+                new_name,
+                project,
+                json,
+            } => commands::project::rename(project, &new_name, json).await?,
+            ProjectAction::Archive {
+                project,
+                undo,
+                yes,
+                json,
+            } => commands::project::archive(project, undo, yes, json).await?,
+            ProjectAction::Delete { project, yes } => {
+                commands::project::delete(project, yes).await?
+            }
+        },
+        Commands::Workspace { action } => match action {
+            WorkspaceAction::List { json } => commands::workspace::list(json).await?,
+            WorkspaceAction::Show { workspace, json } => {
+                commands::workspace::show(workspace, json).await?
+            }
+            WorkspaceAction::Members { workspace, json } => {
+                commands::workspace::members(workspace, json).await?
+            }
+        },
         Commands::Pull {
             project,
             env,
             dev,
             staging,
             prod,
-            output,
+            file,
+            format,
+            dry_run,
+            show_values,
+            yes,
+            all_services,
         } => {
             let environment = determine_environment(env, dev, staging, prod);
-            commands::pull::execute(project, environment, &output).await?
+            let options = commands::pull::PullOptions {
+                dry_run,
+                show_values,
+                yes,
+            };
+            if all_services {
+                commands::pull::all_services(environment, options).await?
+            } else {
+                commands::pull::execute(project, environment, file, format, options).await?
+            }
         }
         Commands::Push {
             project,
@@ -269,20 +1045,187 @@ async fn main() -> Result<()> {
             staging,
             prod,
             file,
+            format,
+            mode,
+            keys,
+            force,
+            dry_run,
+            show_values,
+            yes,
+        } => {
+            let environment = determine_environment(env, dev, staging, prod);
+            let options = commands::push::PushOptions {
+                mode,
+                keys,
+                force,
+                dry_run,
+                show_values,
+                yes,
+            };
+            commands::push::execute(project, environment, file, format, options).await?
+        }
+        Commands::Diff {
+            left,
+            right,
+            json,
+            show_values,
+        } => {
+            // diff(1) exit codes, so CI can tell drift between environments from a failure
+            match commands::diff::execute(&left, &right, json, show_values).await {
+                Ok(false) => {}
+                Ok(true) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("Error: {:?}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        Commands::Promote {
+            from,
+            to,
+            keys,
+            all,
+            force,
+            show_values,
+            yes,
+        } => commands::promote::execute(&from, &to, keys, all, force, show_values, yes).await?,
+        Commands::Get {
+            key,
+            project,
+            env,
+            dev,
+            staging,
+            prod,
+        } => {
+            let environment = determine_environment(env, dev, staging, prod);
+            commands::variable::get(project, environment, &key).await?
+        }
+        Commands::Set {
+            assignments,
+            stdin,
+            project,
+            env,
+            dev,
+            staging,
+            prod,
+            yes,
+        } => {
+            let environment = determine_environment(env, dev, staging, prod);
+            commands::variable::set(project, environment, assignments, stdin, yes).await?
+        }
+        Commands::Unset {
+            keys,
+            project,
+            env,
+            dev,
+            staging,
+            prod,
+            yes,
         } => {
             let environment = determine_environment(env, dev, staging, prod);
-            commands::push::execute(project, environment, &file).await?
+            commands::variable::unset(project, environment, keys, yes).await?
         }
+        Commands::History {
+            key,
+            project,
+            env,
+            dev,
+            staging,
+            prod,
+            limit,
+        } => {
+            let environment = determine_environment(env, dev, staging, prod);
+            commands::history::list(project, environment, key, limit).await?
+        }
+        Commands::Rollback {
+            to,
+            keys,
+            project,
+            env,
+            dev,
+            staging,
+            prod,
+            show_values,
+            yes,
+        } => {
+            let environment = determine_environment(env, dev, staging, prod);
+            commands::history::rollback(project, environment, to, keys, show_values, yes).await?
+        }
+        Commands::Audit {
+            project,
+            env,
+            dev,
+            staging,
+            prod,
+            user,
+            action,
+            key,
+            since,
+            until,
+            limit,
+            json,
+            csv,
+        } => {
+            let options = commands::audit::AuditOptions {
+                environment: determine_environment(env, dev, staging, prod),
+                user,
+                action,
+                key,
+                since,
+                until,
+                limit,
+                json,
+                csv,
+            };
+            commands::audit::execute(project, options).await?
+        }
+        Commands::Env { action } => match action {
+            EnvAction::List { project } => commands::env::list(project).await?,
+            EnvAction::Create {
+                name,
+                from,
+                project,
+            } => commands::env::create(project, &name, from.as_deref()).await?,
+            EnvAction::Delete { name, project, yes } => {
+                commands::env::delete(project, &name, yes).await?
+            }
+            EnvAction::Rename {
+                name,
+                new_name,
+                project,
+            } => commands::env::rename(project, &name, &new_name).await?,
+            EnvAction::Clone {
+                source,
+                name,
+                project,
+            } => commands::env::create(project, &name, Some(&source)).await?,
+            EnvAction::Alias {
+                alias,
+                target,
+                remove,
+            } => commands::env::alias(alias, target, remove).await?,
+        },
         Commands::Run {
             project,
+            service,
             command,
             env,
             dev,
             staging,
             prod,
+            no_cache,
+            refresh,
+            max_age,
+            validate,
         } => {
             let environment = determine_environment(env, dev, staging, prod);
-            commands::run::execute(project, environment, command).await?
+            let cache = commands::run::CacheOptions {
+                no_cache,
+                refresh,
+                max_age,
+                validate,
+            };
+            commands::run::execute(project, service, environment, command, cache).await?
         }
         Commands::Watch {
             project,
@@ -291,10 +1234,34 @@ async fn main() -> Result<()> {
             staging,
             prod,
             file,
+            format,
         } => {
             let environment = determine_environment(env, dev, staging, prod);
-            commands::watch::execute(project, environment, &file).await?
+            commands::watch::execute(project, environment, file, format).await?
         }
+        Commands::Cache { action } => match action {
+            CacheAction::List => commands::cache::list().await?,
+            CacheAction::Clear { target, all, yes } => {
+                commands::cache::clear(target, all, yes).await?
+            }
+            CacheAction::Purge { all } => commands::cache::purge(all).await?,
+            CacheAction::Inspect {
+                target,
+                show_values,
+            } => commands::cache::inspect(target, show_values).await?,
+        },
+        Commands::Agent { action, socket } => match action {
+            AgentAction::Start { targets } => commands::agent::start(targets, socket).await?,
+            AgentAction::Status => commands::agent::status(socket).await?,
+            AgentAction::Stop => commands::agent::stop(socket).await?,
+        },
+        Commands::Doctor => commands::doctor::execute().await?,
+        Commands::Serve {
+            target,
+            bind,
+            port,
+            token_file,
+        } => commands::serve::execute(target, bind, port, token_file).await?,
         Commands::Rotate { action } => match action {
             RotateAction::Enable { interval, exclude } => {
                 commands::rotate::enable(interval, exclude).await?
@@ -303,7 +1270,11 @@ async fn main() -> Result<()> {
             RotateAction::Status => commands::rotate::status().await?,
             RotateAction::Now { vars } => commands::rotate::now(vars).await?,
         },
-        Commands::Config { api_url, show } => commands::config::execute(api_url, show).await?,
+        Commands::Config {
+            api_url,
+            cache_max_age,
+            show,
+        } => commands::config::execute(api_url, cache_max_age, show).await?,
         Commands::Lang { language } => commands::lang::execute(language).await?,
         Commands::Logout => commands::logout::execute().await?,
         Commands::M2m {
@@ -322,13 +1293,17 @@ fn determine_environment(
     staging: bool,
     prod: bool,
 ) -> Option<String> {
-    if dev {
-        Some("development".to_string())
+    let name = if dev {
+        "dev".to_string()
     } else if staging {
-        Some("staging".to_string())
+        "staging".to_string()
     } else if prod {
-        Some("production".to_string())
+        "prod".to_string()
     } else {
-        env
-    }
+        env?
+    };
+
+    // Shortcuts and -e both go through the user's aliases
+    let config = config::Config::load().unwrap_or_default();
+    Some(config.resolve_environment(&name))
 }
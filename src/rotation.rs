@@ -2,6 +2,7 @@
 
 use crate::api::{ApiClient, EnvVariable};
 use crate::config::Config;
+use crate::status;
 use anyhow::Result;
 use chrono::{DateTime, Duration, Utc};
 use colored::*;
@@ -9,6 +10,16 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashSet;
 
+/// Rotation settings as reported by `envsafe rotate status`
+#[derive(Debug, Clone, Serialize)]
+pub struct RotationStatus {
+    pub enabled: bool,
+    pub interval_days: u32,
+    pub last_rotation: Option<String>,
+    pub next_rotation: Option<String>,
+    pub exclude_vars: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RotationPolicy {
     pub enabled: bool,
@@ -67,7 +78,7 @@ impl SecretRotator {
         environment: &str,
         specific_vars: Option<Vec<String>>,
     ) -> Result<usize> {
-        println!("{}", "🔄 Starting secret rotation...".cyan());
+        status!("{}", "🔄 Starting secret rotation...".cyan());
 
         let token = self.config.get_token()?;
         let env = self
@@ -93,7 +104,7 @@ impl SecretRotator {
             if should_rotate {
                 let new_value = self.generate_secret(&var.value, &var.key);
 
-                println!(
+                status!(
                     "  {} {} → {}",
                     "↻".yellow(),
                     var.key.bright_white(),
@@ -122,12 +133,12 @@ impl SecretRotator {
             self.config.rotation.last_rotation = Some(Utc::now().to_rfc3339());
             self.config.save()?;
 
-            println!(
+            status!(
                 "{}",
                 format!("✓ Rotated {} secret(s)", rotated_count).green()
             );
         } else {
-            println!("{}", "ℹ No secrets to rotate".bright_black());
+            status!("{}", "ℹ No secrets to rotate".bright_black());
         }
 
         Ok(rotated_count)
@@ -163,8 +174,8 @@ impl SecretRotator {
         self.config.rotation.exclude_vars = exclude_vars;
         self.config.save()?;
 
-        println!("{}", "✓ Secret rotation enabled".green());
-        println!(
+        status!("{}", "✓ Secret rotation enabled".green());
+        status!(
             "{}",
             format!("  Interval: {} days", interval_days).bright_black()
         );
@@ -177,25 +188,50 @@ impl SecretRotator {
         self.config.rotation.enabled = false;
         self.config.save()?;
 
-        println!("{}", "✓ Secret rotation disabled".yellow());
+        status!("{}", "✓ Secret rotation disabled".yellow());
 
         Ok(())
     }
 
+    /// Machine-readable view of the rotation settings
+    pub fn status(&self) -> RotationStatus {
+        let rotation = &self.config.rotation;
+        let next_rotation = rotation
+            .last_rotation
+            .as_deref()
+            .and_then(|last| DateTime::parse_from_rfc3339(last).ok())
+            .map(|last| {
+                (last.with_timezone(&Utc) + Duration::days(rotation.interval_days as i64))
+                    .to_rfc3339()
+            });
+
+        RotationStatus {
+            enabled: rotation.enabled,
+            interval_days: rotation.interval_days,
+            last_rotation: rotation.last_rotation.clone(),
+            next_rotation: if rotation.enabled {
+                next_rotation
+            } else {
+                None
+            },
+            exclude_vars: rotation.exclude_vars.clone(),
+        }
+    }
+
     /// Show rotation status
     pub fn show_status(&self) -> Result<()> {
-        println!("{}", "🔐 Secret Rotation Status".cyan().bold());
-        println!();
+        status!("{}", "🔐 Secret Rotation Status".cyan().bold());
+        status!();
 
         if self.config.rotation.enabled {
-            println!("  Status: {}", "Enabled".green());
-            println!(
+            status!("  Status: {}", "Enabled".green());
+            status!(
                 "  Interval: {} days",
                 format!("{}", self.config.rotation.interval_days).bright_black()
             );
 
             if let Some(last) = &self.config.rotation.last_rotation {
-                println!("  Last rotation: {}", last);
+                status!("  Last rotation: {}", last);
 
                 if let Ok(last_date) = DateTime::parse_from_rfc3339(last) {
                     let now = Utc::now();
@@ -204,23 +240,23 @@ impl SecretRotator {
                         Duration::days(self.config.rotation.interval_days as i64) - elapsed;
 
                     if remaining.num_days() > 0 {
-                        println!("  Next rotation: in {} days", remaining.num_days());
+                        status!("  Next rotation: in {} days", remaining.num_days());
                     } else {
-                        println!("  Next rotation: {}", "Now (overdue)".yellow());
+                        status!("  Next rotation: {}", "Now (overdue)".yellow());
                     }
                 }
             } else {
-                println!("  Last rotation: {}", "Never".bright_black());
+                status!("  Last rotation: {}", "Never".bright_black());
             }
 
             if !self.config.rotation.exclude_vars.is_empty() {
-                println!("  Excluded variables:");
+                status!("  Excluded variables:");
                 for var in &self.config.rotation.exclude_vars {
-                    println!("    - {}", var.bright_black());
+                    status!("    - {}", var.bright_black());
                 }
             }
         } else {
-            println!("  Status: {}", "Disabled".bright_black());
+            status!("  Status: {}", "Disabled".bright_black());
         }
 
         Ok(())
@@ -246,7 +282,7 @@ pub mod docker {
 
         fs::write(output_path, content)?;
 
-        println!(
+        status!(
             "{}",
             format!("✓ Docker secrets file generated: {}", output_path).green()
         );
@@ -280,7 +316,7 @@ services:
 
         fs::write(output_path, content)?;
 
-        println!(
+        status!(
             "{}",
             format!("✓ Docker Compose override generated: {}", output_path).green()
         );
@@ -0,0 +1,298 @@
+//! `envsafe serve`: one environment over loopback HTTP, in the style of cloud metadata
+//! services, for containers and sidecars that cannot map the shared memory cache.
+//!
+//! Everything under `/v1` requires `Authorization: Bearer <token>` with the token generated
+//! when the server starts. `/healthz` is open and reveals nothing but readiness.
+
+use crate::storage::SharedEnvData;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use anyhow::Result;
+use axum::extract::{Path, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use futures_util::stream::{self, Stream};
+use serde_json::json;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+pub const DEFAULT_PORT: u16 = 7391;
+
+#[derive(Clone)]
+struct AppState {
+    updates: watch::Receiver<Option<SharedEnvData>>,
+    token: Arc<str>,
+}
+
+/// Random token clients must present as `Authorization: Bearer <token>`
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Listen on `addr`, which must be a loopback address: the server hands out secrets
+pub async fn bind(addr: SocketAddr) -> Result<TcpListener> {
+    if !addr.ip().is_loopback() {
+        anyhow::bail!(
+            "Refusing to listen on {}: 'envsafe serve' only binds to loopback addresses",
+            addr.ip()
+        );
+    }
+    Ok(TcpListener::bind(addr).await?)
+}
+
+/// Routes serving the environment published on `updates`
+pub fn router(updates: watch::Receiver<Option<SharedEnvData>>, token: &str) -> Router {
+    let state = AppState {
+        updates,
+        token: Arc::from(token),
+    };
+
+    let v1 = Router::new()
+        .route("/v1/env", get(environment))
+        .route("/v1/env/:key", get(variable))
+        .route("/v1/watch", get(watch_versions))
+        .route_layer(middleware::from_fn_with_state(state.clone(), authorize));
+
+    Router::new()
+        .route("/healthz", get(health))
+        .merge(v1)
+        .with_state(state)
+}
+
+/// Serve until `shutdown` completes
+pub async fn serve(
+    listener: TcpListener,
+    updates: watch::Receiver<Option<SharedEnvData>>,
+    token: &str,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    axum::serve(listener, router(updates, token))
+        .with_graceful_shutdown(shutdown)
+        .await?;
+    Ok(())
+}
+
+async fn authorize(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match presented {
+        Some(token) if same_token(token.trim(), &state.token) => next.run(request).await,
+        _ => (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            Json(json!({ "error": "Missing or invalid bearer token" })),
+        )
+            .into_response(),
+    }
+}
+
+/// Compare without returning early, so timing does not reveal how much of a guess matched
+fn same_token(presented: &str, expected: &str) -> bool {
+    presented.len() == expected.len()
+        && presented
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn not_ready() -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({ "error": "Variables have not been fetched yet" })),
+    )
+        .into_response()
+}
+
+async fn health(State(state): State<AppState>) -> Response {
+    match state.updates.borrow().as_ref() {
+        Some(_) => Json(json!({ "status": "ok" })).into_response(),
+        None => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "status": "starting" })),
+        )
+            .into_response(),
+    }
+}
+
+async fn environment(State(state): State<AppState>) -> Response {
+    let snapshot = state.updates.borrow().clone();
+    match snapshot {
+        Some(data) => Json(data).into_response(),
+        None => not_ready(),
+    }
+}
+
+/// The value alone, as `text/plain`
+async fn variable(State(state): State<AppState>, Path(key): Path<String>) -> Response {
+    let value = match state.updates.borrow().as_ref() {
+        Some(data) => data.variables.get(&key).cloned(),
+        None => return not_ready(),
+    };
+    match value {
+        Some(value) => value.into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": format!("Variable '{}' is not set", key) })),
+        )
+            .into_response(),
+    }
+}
+
+/// Server-sent `version` events: the current version, then one per change
+async fn watch_versions(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let mut updates = state.updates;
+    updates.mark_changed();
+
+    let events = stream::unfold(updates, |mut updates| async move {
+        loop {
+            updates.changed().await.ok()?;
+            let snapshot = updates.borrow_and_update().clone();
+            if let Some(data) = snapshot {
+                let event = Event::default()
+                    .event("version")
+                    .id(data.version.to_string())
+                    .data(
+                        json!({
+                            "version": data.version,
+                            "last_updated": data.last_updated,
+                        })
+                        .to_string(),
+                    );
+                return Some((Ok(event), updates));
+            }
+        }
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn snapshot(version: u64) -> SharedEnvData {
+        SharedEnvData {
+            version,
+            project_id: "api".to_string(),
+            environment: "production".to_string(),
+            variables: HashMap::from([("PORT".to_string(), "8080".to_string())]),
+            last_updated: chrono::Utc::now().to_rfc3339(),
+            etag: None,
+        }
+    }
+
+    async fn start(updates: watch::Receiver<Option<SharedEnvData>>) -> String {
+        let listener = bind("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, updates, "secret", std::future::pending()));
+        url
+    }
+
+    #[tokio::test]
+    async fn test_refuses_non_loopback_address() {
+        assert!(bind("0.0.0.0:0".parse().unwrap()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_requires_bearer_token() {
+        let (_sender, updates) = watch::channel(Some(snapshot(1)));
+        let url = start(updates).await;
+        let client = reqwest::Client::new();
+
+        let response = client.get(format!("{}/v1/env", url)).send().await.unwrap();
+        assert_eq!(response.status(), 401);
+
+        let response = client
+            .get(format!("{}/v1/env", url))
+            .bearer_auth("guess")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 401);
+
+        // Health checks need no token
+        let response = client.get(format!("{}/healthz", url)).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_serves_environment_and_variables() {
+        let (sender, updates) = watch::channel(None);
+        let url = start(updates).await;
+        let client = reqwest::Client::new();
+        let get = |path: &str| {
+            client
+                .get(format!("{}{}", url, path))
+                .bearer_auth("secret")
+                .send()
+        };
+
+        assert_eq!(get("/v1/env").await.unwrap().status(), 503);
+        assert_eq!(get("/healthz").await.unwrap().status(), 503);
+
+        sender.send_replace(Some(snapshot(4)));
+
+        let document: serde_json::Value = get("/v1/env").await.unwrap().json().await.unwrap();
+        assert_eq!(document["version"], 4);
+        assert_eq!(document["variables"]["PORT"], "8080");
+
+        let response = get("/v1/env/PORT").await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), "8080");
+
+        assert_eq!(get("/v1/env/MISSING").await.unwrap().status(), 404);
+    }
+
+    /// Read the event stream until `needle` shows up
+    async fn read_until(response: &mut reqwest::Response, needle: &str) -> bool {
+        let mut received = String::new();
+        while !received.contains(needle) {
+            match tokio::time::timeout(Duration::from_secs(5), response.chunk()).await {
+                Ok(Ok(Some(chunk))) => received.push_str(&String::from_utf8_lossy(&chunk)),
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    #[tokio::test]
+    async fn test_watch_streams_versions() {
+        let (sender, updates) = watch::channel(Some(snapshot(1)));
+        let url = start(updates).await;
+
+        let mut response = reqwest::Client::new()
+            .get(format!("{}/v1/watch", url))
+            .bearer_auth("secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()[reqwest::header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+
+        assert!(read_until(&mut response, "\"version\":1").await);
+
+        sender.send_replace(Some(snapshot(2)));
+        assert!(read_until(&mut response, "\"version\":2").await);
+    }
+}
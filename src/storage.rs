@@ -1,142 +1,570 @@
 #![allow(dead_code)]
 
+use crate::config::{Config, ProjectConfig};
 use anyhow::Result;
+use envsafe_client::segment::{
+    create_or_open, current_user, open_segment, read_payload, region, short_hash, unlink,
+};
 use serde::{Deserialize, Serialize};
 use shared_memory::*;
 use std::collections::HashMap;
+use std::time::Duration;
 
-const SHMEM_SIZE: usize = 1024 * 1024; // 1MB
-const SHMEM_NAME: &str = "envsafe_env_vars";
+pub use envsafe_client::{codec, Client, SegmentKey, SessionKey, SharedEnvData, SEGMENT_ENV_VAR};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SharedEnvData {
-    pub version: u64,
-    pub project_id: String,
+/// Capacity of a new segment, grown as needed when a larger environment is written
+const INITIAL_CAPACITY: usize = 64 * 1024;
+const MAX_CAPACITY: usize = 64 * 1024 * 1024;
+const INDEX_CAPACITY: usize = 64 * 1024;
+
+/// Key in the workspace linked to the working directory, or the current workspace
+pub fn current_key(config: &Config, project: &str, environment: &str) -> Result<SegmentKey> {
+    Ok(SegmentKey::new(
+        &current_workspace(config)?,
+        project,
+        environment,
+    ))
+}
+
+/// The workspace linked to the working directory, or the current workspace
+pub fn current_workspace(config: &Config) -> Result<String> {
+    Ok(match ProjectConfig::load()? {
+        Some(local_config) => local_config.workspace_id,
+        None => config.current_workspace.clone().unwrap_or_default(),
+    })
+}
+
+/// What the index segment records about a cached environment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SegmentInfo {
+    pub segment: String,
+    pub workspace: String,
+    pub project: String,
     pub environment: String,
-    pub variables: HashMap<String, String>,
+    pub version: u64,
+    pub variables: usize,
     pub last_updated: String,
+    /// Processes keeping the segment up to date (`watch`, `agent`, `serve`), see [`Lease`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub holders: Vec<u32>,
 }
 
-pub struct EnvStorage {
-    shmem: Shmem,
+impl SegmentInfo {
+    fn new(key: &SegmentKey) -> Self {
+        Self {
+            segment: key.os_id(),
+            workspace: key.workspace.clone(),
+            project: key.project.clone(),
+            environment: key.environment.clone(),
+            version: 0,
+            variables: 0,
+            last_updated: String::new(),
+            holders: Vec::new(),
+        }
+    }
+
+    /// Holders that are still running
+    pub fn live_holders(&self) -> Vec<u32> {
+        self.holders
+            .iter()
+            .copied()
+            .filter(|pid| is_running(*pid))
+            .collect()
+    }
 }
 
-impl EnvStorage {
-    /// Create or open shared memory
-    pub fn new() -> Result<Self> {
-        let shmem = match ShmemConf::new().size(SHMEM_SIZE).os_id(SHMEM_NAME).create() {
-            Ok(m) => m,
-            Err(_) => {
-                // If creation fails (likely because it exists), try to open it
-                ShmemConf::new().os_id(SHMEM_NAME).open()?
-            }
-        };
+/// How a segment found in shared memory is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SegmentState {
+    /// A running process keeps it up to date
+    Held,
+    /// Written recently enough for `run` to use it
+    Idle,
+    /// Older than the cache max age and nobody refreshes it
+    Expired,
+    /// Not in the index, left behind by a crash or an older release
+    Unindexed,
+}
 
-        Ok(Self { shmem })
+/// A segment of the current user, with its size
+#[derive(Debug, Clone, Serialize)]
+pub struct SegmentUsage {
+    pub segment: String,
+    pub state: SegmentState,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub info: Option<SegmentInfo>,
+}
+
+impl SegmentState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SegmentState::Held => "held",
+            SegmentState::Idle => "idle",
+            SegmentState::Expired => "expired",
+            SegmentState::Unindexed => "unindexed",
+        }
     }
+}
 
-    /// Write environment variables to shared memory
-    pub fn write(&mut self, data: &SharedEnvData) -> Result<()> {
-        let json = serde_json::to_vec(data)?;
+impl SegmentUsage {
+    /// Nothing uses the segment any more: safe to purge
+    pub fn is_orphaned(&self) -> bool {
+        matches!(self.state, SegmentState::Expired | SegmentState::Unindexed)
+    }
+
+    /// Removed by `logout`: everything but the segments a running `watch`, `agent` or `serve`
+    /// holds, which would recreate them at once
+    pub fn cleared_on_logout(&self) -> bool {
+        self.state != SegmentState::Held
+    }
+
+    /// Removed when the directory switches away from `project` (its ID or slug) of `workspace`
+    pub fn cleared_on_switch(&self, workspace: &str, project: &[&str]) -> bool {
+        self.cleared_on_logout()
+            && self.info.as_ref().is_some_and(|info| {
+                info.workspace == workspace && project.contains(&info.project.as_str())
+            })
+    }
 
-        if json.len() > SHMEM_SIZE - 8 {
-            anyhow::bail!("Data too large for shared memory");
+    /// Removed when `project` (its ID or slug) of `workspace` is deleted, or only its
+    /// `environment` when given
+    pub fn cleared_on_delete(
+        &self,
+        workspace: &str,
+        project: &[&str],
+        environment: Option<&str>,
+    ) -> bool {
+        self.cleared_on_switch(workspace, project)
+            && match (environment, &self.info) {
+                (Some(environment), Some(info)) => info.environment == environment,
+                _ => true,
+            }
+    }
+
+    /// `project/environment`, or the segment name when it is not indexed
+    pub fn label(&self) -> String {
+        match &self.info {
+            Some(info) => format!("{}/{}", info.project, info.environment),
+            None => self.segment.clone(),
         }
+    }
+}
+
+/// Keeps a segment marked as maintained by this process until dropped, so purges leave it
+/// alone. Holders that exit without dropping it are ignored once they are gone.
+pub struct Lease {
+    user: String,
+    segment: String,
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        let _ = index::release(&self.user, &self.segment, std::process::id());
+    }
+}
+
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // Signal 0 only checks that the process exists; EPERM means it belongs to someone else
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
 
-        unsafe {
-            let ptr = self.shmem.as_ptr();
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    true
+}
 
-            // Write length as first 8 bytes
-            let len_bytes = (json.len() as u64).to_le_bytes();
-            std::ptr::copy_nonoverlapping(len_bytes.as_ptr(), ptr, 8);
+pub struct EnvStorage {
+    shmem: Shmem,
+    key: SegmentKey,
+}
+
+impl EnvStorage {
+    /// Create or open the segment of `key`
+    pub fn open(key: &SegmentKey) -> Result<Self> {
+        let shmem = create_or_open(&key.os_id(), INITIAL_CAPACITY)?;
+        Ok(Self {
+            shmem,
+            key: key.clone(),
+        })
+    }
 
-            // Write data
-            std::ptr::copy_nonoverlapping(json.as_ptr(), ptr.add(8), json.len());
+    /// Open the segment of `key` if something was cached for it
+    pub fn open_existing(key: &SegmentKey) -> Result<Option<Self>> {
+        match open_segment(&key.os_id())? {
+            Some(shmem) => Ok(Some(Self {
+                shmem,
+                key: key.clone(),
+            })),
+            None => Ok(None),
         }
+    }
 
-        Ok(())
+    pub fn segment(&self) -> String {
+        self.key.os_id()
+    }
+
+    /// Encrypt environment variables and write them to shared memory
+    pub fn write(&mut self, data: &SharedEnvData) -> Result<()> {
+        let sealed = SessionKey::current()?.seal(&codec::encode(data), &self.segment())?;
+        self.reserve(sealed.len())?;
+        region(&self.shmem).write(&sealed)?;
+
+        index::record(
+            &self.key.user,
+            SegmentInfo {
+                version: data.version,
+                variables: data.variables.len(),
+                last_updated: data.last_updated.clone(),
+                ..SegmentInfo::new(&self.key)
+            },
+        )
+    }
+
+    /// Mark the segment as kept up to date by this process until the lease is dropped
+    pub fn lease(&self) -> Result<Lease> {
+        index::acquire(&self.key, std::process::id())?;
+        Ok(Lease {
+            user: self.key.user.clone(),
+            segment: self.segment(),
+        })
     }
 
     /// Read environment variables from shared memory
     pub fn read(&self) -> Result<Option<SharedEnvData>> {
-        unsafe {
-            let ptr = self.shmem.as_ptr() as *const u8;
+        match self.payload()? {
+            Some(payload) => Ok(Some(codec::decode(&payload)?)),
+            None => Ok(None),
+        }
+    }
 
-            // Read length
-            let mut len_bytes = [0u8; 8];
-            std::ptr::copy_nonoverlapping(ptr, len_bytes.as_mut_ptr(), 8);
-            let len = u64::from_le_bytes(len_bytes) as usize;
+    /// Read a single variable without decoding the others
+    pub fn get(&self, name: &str) -> Result<Option<String>> {
+        match self.payload()? {
+            Some(payload) => codec::lookup(&payload, name),
+            None => Ok(None),
+        }
+    }
 
-            if len == 0 || len > SHMEM_SIZE - 8 {
-                return Ok(None);
-            }
+    fn payload(&self) -> Result<Option<Vec<u8>>> {
+        read_payload(&self.shmem, &self.segment())
+    }
 
-            // Read data
-            let mut buffer = vec![0u8; len];
-            std::ptr::copy_nonoverlapping(ptr.add(8), buffer.as_mut_ptr(), len);
+    /// Make sure the segment can hold `len` bytes, replacing it with a larger one if not.
+    /// Handles other processes hold on the old segment see it retired and reopen it by name.
+    fn reserve(&mut self, len: usize) -> Result<()> {
+        if len > MAX_CAPACITY {
+            anyhow::bail!(
+                "Data too large for shared memory ({} bytes, at most {})",
+                len,
+                MAX_CAPACITY
+            );
+        }
 
-            let data: SharedEnvData = serde_json::from_slice(&buffer)?;
-            Ok(Some(data))
+        let os_id = self.segment();
+        // Another writer may replace the segment concurrently, with a smaller capacity
+        for _ in 0..5 {
+            let current = region(&self.shmem);
+            if current.is_retired() {
+                self.shmem = create_or_open(&os_id, INITIAL_CAPACITY)?;
+                continue;
+            }
+            if len <= current.capacity() {
+                return Ok(());
+            }
+
+            current.retire();
+            unlink(&os_id)?;
+            let capacity = len.next_power_of_two().max(INITIAL_CAPACITY);
+            self.shmem = create_or_open(&os_id, capacity)?;
         }
+
+        anyhow::bail!("Shared memory segment {} keeps being replaced", os_id)
     }
 
     /// Clear shared memory
     pub fn clear(&mut self) -> Result<()> {
-        unsafe {
-            let ptr = self.shmem.as_ptr();
-            std::ptr::write_bytes(ptr, 0, SHMEM_SIZE);
-        }
-        Ok(())
+        region(&self.shmem).write(&[])?;
+        index::forget(&self.key.user, &self.segment())
     }
 
     /// Get current version
     pub fn get_version(&self) -> Result<u64> {
-        match self.read()? {
-            Some(data) => Ok(data.version),
+        match self.payload()? {
+            Some(payload) => codec::version(&payload),
             None => Ok(0),
         }
     }
+
+    /// Delete the segment of `key`. Returns `false` if nothing was cached for it.
+    pub fn remove(key: &SegmentKey) -> Result<bool> {
+        Self::remove_segment(&key.os_id())
+    }
+
+    /// Delete one of the current user's segments by name
+    pub fn remove_segment(os_id: &str) -> Result<bool> {
+        index::forget(&current_user(), os_id)?;
+        unlink(os_id)
+    }
+
+    /// Every segment of the current user, including those missing from the index. Segments
+    /// nobody holds are expired once older than `max_age`.
+    pub fn usage(max_age: Duration) -> Result<Vec<SegmentUsage>> {
+        let mut usage: Vec<SegmentUsage> = Self::list()?
+            .into_iter()
+            .map(|info| {
+                let state = if !info.live_holders().is_empty() {
+                    SegmentState::Held
+                } else {
+                    let age = chrono::DateTime::parse_from_rfc3339(&info.last_updated)
+                        .ok()
+                        .and_then(|updated| {
+                            (chrono::Utc::now() - updated.with_timezone(&chrono::Utc))
+                                .to_std()
+                                .ok()
+                        });
+                    match age {
+                        Some(age) if age > max_age => SegmentState::Expired,
+                        // Never written, or written in the future: leave it to its writer
+                        _ => SegmentState::Idle,
+                    }
+                };
+                SegmentUsage {
+                    segment: info.segment.clone(),
+                    state,
+                    size: segment_size(&info.segment),
+                    info: Some(info),
+                }
+            })
+            .collect();
+
+        for segment in unindexed_segments()? {
+            if !usage.iter().any(|u| u.segment == segment) {
+                usage.push(SegmentUsage {
+                    size: segment_size(&segment),
+                    segment,
+                    state: SegmentState::Unindexed,
+                    info: None,
+                });
+            }
+        }
+        Ok(usage)
+    }
+
+    /// Delete the segments `select` picks out of [`usage`](Self::usage); returns them
+    pub fn purge(
+        max_age: Duration,
+        select: impl Fn(&SegmentUsage) -> bool,
+    ) -> Result<Vec<SegmentUsage>> {
+        let mut purged = Vec::new();
+        for usage in Self::usage(max_age)? {
+            if select(&usage) {
+                Self::remove_segment(&usage.segment)?;
+                purged.push(usage);
+            }
+        }
+        Ok(purged)
+    }
+
+    /// The current user's cached environments, dropping entries whose segment is gone
+    pub fn list() -> Result<Vec<SegmentInfo>> {
+        let user = current_user();
+        let entries = index::entries(&user)?;
+        let (live, stale): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .partition(|entry| matches!(open_segment(&entry.segment), Ok(Some(_))));
+
+        for entry in stale {
+            index::forget(&user, &entry.segment)?;
+        }
+
+        Ok(live)
+    }
 }
 
 impl Drop for EnvStorage {
     fn drop(&mut self) {
-        // Don't clear on drop, other processes might be using it
+        // Don't clear on drop, other processes might be using it. Segments nobody holds a
+        // `Lease` on are removed by `envsafe cache purge`, `logout` and project switches.
     }
 }
 
-/// Utility functions for accessing env vars from shared memory
-pub mod access {
+/// Size of a segment in bytes, 0 if it can't be opened
+fn segment_size(os_id: &str) -> u64 {
+    match open_segment(os_id) {
+        Ok(Some(shmem)) => shmem.len() as u64,
+        _ => 0,
+    }
+}
+
+/// Names of the current user's environment segments in `/dev/shm`, indexed or not.
+/// Other systems don't expose a listing: only indexed segments are known there.
+#[cfg(target_os = "linux")]
+fn unindexed_segments() -> Result<Vec<String>> {
+    use std::os::unix::fs::MetadataExt;
+
+    let Ok(entries) = std::fs::read_dir("/dev/shm") else {
+        return Ok(Vec::new());
+    };
+    let uid = unsafe { libc::geteuid() };
+
+    let mut segments = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let owned = entry.metadata().map(|m| m.uid() == uid).unwrap_or(false);
+        if owned && is_environment_segment(&name) {
+            segments.push(name);
+        }
+    }
+    Ok(segments)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unindexed_segments() -> Result<Vec<String>> {
+    Ok(Vec::new())
+}
+
+/// Named like [`SegmentKey::os_id`]: `envsafe_` and 16 hex digits
+fn is_environment_segment(name: &str) -> bool {
+    name.strip_prefix("envsafe_")
+        .is_some_and(|hash| hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Registry of the segments a user has cached, kept in its own small segment
+mod index {
     use super::*;
 
-    /// Get all environment variables from shared memory
-    pub fn get_env_vars() -> Result<HashMap<String, String>> {
-        let storage = EnvStorage::new()?;
-        match storage.read()? {
-            Some(data) => Ok(data.variables),
-            None => Ok(HashMap::new()),
+    fn os_id(user: &str) -> String {
+        format!("envsafe_index_{}", short_hash(user))
+    }
+
+    pub fn entries(user: &str) -> Result<Vec<SegmentInfo>> {
+        let Some(shmem) = open_segment(&os_id(user))? else {
+            return Ok(Vec::new());
+        };
+        Ok(parse(region(&shmem).read().ok().flatten()))
+    }
+
+    pub fn record(user: &str, info: SegmentInfo) -> Result<()> {
+        let shmem = create_or_open(&os_id(user), INDEX_CAPACITY)?;
+        region(&shmem).update(|current| {
+            let mut entries = parse(current);
+            let holders = entries
+                .iter()
+                .position(|entry| entry.segment == info.segment)
+                .map(|i| entries.remove(i).holders)
+                .unwrap_or_default();
+            entries.push(SegmentInfo { holders, ..info });
+            Ok(serde_json::to_vec(&entries)?)
+        })
+    }
+
+    /// Add `pid` to the holders of `key`'s segment, dropping holders that have exited
+    pub fn acquire(key: &SegmentKey, pid: u32) -> Result<()> {
+        let shmem = create_or_open(&os_id(&key.user), INDEX_CAPACITY)?;
+        let segment = key.os_id();
+        region(&shmem).update(|current| {
+            let mut entries = parse(current);
+            let entry = match entries.iter().position(|entry| entry.segment == segment) {
+                Some(i) => &mut entries[i],
+                None => {
+                    entries.push(SegmentInfo::new(key));
+                    entries.last_mut().unwrap()
+                }
+            };
+            entry.holders = entry.live_holders();
+            if !entry.holders.contains(&pid) {
+                entry.holders.push(pid);
+            }
+            Ok(serde_json::to_vec(&entries)?)
+        })
+    }
+
+    pub fn release(user: &str, segment: &str, pid: u32) -> Result<()> {
+        let Some(shmem) = open_segment(&os_id(user))? else {
+            return Ok(());
+        };
+        region(&shmem).update(|current| {
+            let mut entries = parse(current);
+            if let Some(entry) = entries.iter_mut().find(|entry| entry.segment == segment) {
+                entry.holders.retain(|holder| *holder != pid);
+            }
+            Ok(serde_json::to_vec(&entries)?)
+        })
+    }
+
+    pub fn forget(user: &str, segment: &str) -> Result<()> {
+        let Some(mut shmem) = open_segment(&os_id(user))? else {
+            return Ok(());
+        };
+
+        let mut empty = false;
+        region(&shmem).update(|current| {
+            let mut entries = parse(current);
+            entries.retain(|entry| entry.segment != segment);
+            empty = entries.is_empty();
+            Ok(serde_json::to_vec(&entries)?)
+        })?;
+
+        if empty {
+            // Nothing left to list: unlink the index when the handle is dropped
+            shmem.set_owner(true);
         }
+        Ok(())
+    }
+
+    /// A corrupt index is treated as empty; it is rebuilt by the next write
+    fn parse(payload: Option<Vec<u8>>) -> Vec<SegmentInfo> {
+        payload
+            .and_then(|buffer| serde_json::from_slice(&buffer).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// One-off reads of an environment's cache. Processes reading repeatedly should keep an
+/// [`envsafe_client::Client`] instead of reopening the segment on every call.
+pub mod access {
+    use super::*;
+
+    /// Get all environment variables cached for an environment
+    pub fn get_env_vars(key: &SegmentKey) -> Result<HashMap<String, String>> {
+        Client::for_key(key).all()
     }
 
     /// Get a specific environment variable
-    pub fn get_env(key: &str) -> Result<Option<String>> {
-        let vars = get_env_vars()?;
-        Ok(vars.get(key).cloned())
+    pub fn get_env(key: &SegmentKey, name: &str) -> Result<Option<String>> {
+        Client::for_key(key).get(name)
     }
 
     /// Check if shared memory has been updated
-    pub fn check_version() -> Result<u64> {
-        let storage = EnvStorage::new()?;
-        storage.get_version()
+    pub fn check_version(key: &SegmentKey) -> Result<u64> {
+        Client::for_key(key).version()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Mutex;
+
+    // Tests share the user's index segment
+    static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+    fn test_key(project: &str) -> SegmentKey {
+        SegmentKey::new("test-workspace", project, "development")
+    }
 
     #[test]
     fn test_read_write() {
-        let mut storage = EnvStorage::new().unwrap();
+        let _guard = INDEX_LOCK.lock().unwrap();
+        let key = test_key("test-project");
+        let mut storage = EnvStorage::open(&key).unwrap();
 
         let mut vars = HashMap::new();
         vars.insert("TEST_KEY".to_string(), "test_value".to_string());
@@ -147,6 +575,7 @@ mod tests {
             environment: "development".to_string(),
             variables: vars.clone(),
             last_updated: chrono::Utc::now().to_rfc3339(),
+            etag: None,
         };
 
         storage.write(&data).unwrap();
@@ -156,5 +585,326 @@ mod tests {
         assert_eq!(read_data.variables.get("TEST_KEY").unwrap(), "test_value");
 
         storage.clear().unwrap();
+        assert!(EnvStorage::remove(&key).unwrap());
+    }
+
+    #[test]
+    fn test_segments_are_namespaced() {
+        let _guard = INDEX_LOCK.lock().unwrap();
+        let a = test_key("namespaced-a");
+        let b = test_key("namespaced-b");
+        assert_ne!(a.os_id(), b.os_id());
+        assert_eq!(a.os_id(), test_key("namespaced-a").os_id());
+
+        for (key, value) in [(&a, "a"), (&b, "b")] {
+            let mut vars = HashMap::new();
+            vars.insert("NAME".to_string(), value.to_string());
+            let data = SharedEnvData {
+                version: 1,
+                project_id: key.project.clone(),
+                environment: key.environment.clone(),
+                variables: vars,
+                last_updated: chrono::Utc::now().to_rfc3339(),
+                etag: None,
+            };
+            EnvStorage::open(key).unwrap().write(&data).unwrap();
+        }
+
+        // Written by a handle that has since been dropped
+        assert_eq!(access::get_env(&a, "NAME").unwrap().as_deref(), Some("a"));
+        assert_eq!(access::get_env(&b, "NAME").unwrap().as_deref(), Some("b"));
+
+        let listed = EnvStorage::list().unwrap();
+        assert!(listed.iter().any(|entry| entry.segment == a.os_id()));
+
+        assert!(EnvStorage::remove(&a).unwrap());
+        assert!(EnvStorage::remove(&b).unwrap());
+        assert!(EnvStorage::open_existing(&a).unwrap().is_none());
+        assert!(!EnvStorage::list()
+            .unwrap()
+            .iter()
+            .any(|entry| entry.segment == a.os_id()));
+    }
+
+    #[test]
+    fn test_segment_grows_for_large_environments() {
+        let _guard = INDEX_LOCK.lock().unwrap();
+        let key = test_key("growing");
+        let mut writer = EnvStorage::open(&key).unwrap();
+        let reader = EnvStorage::open_existing(&key).unwrap().unwrap();
+        let initial = writer.shmem.len();
+
+        let variables: HashMap<String, String> = (0..2_000)
+            .map(|i| (format!("KEY_{}", i), "v".repeat(100)))
+            .collect();
+        let data = SharedEnvData {
+            version: 1,
+            project_id: key.project.clone(),
+            environment: key.environment.clone(),
+            variables,
+            last_updated: chrono::Utc::now().to_rfc3339(),
+            etag: None,
+        };
+        writer.write(&data).unwrap();
+        assert!(writer.shmem.len() > initial);
+
+        // A handle opened before the segment was replaced follows it
+        assert_eq!(reader.read().unwrap().unwrap().variables.len(), 2_000);
+        assert_eq!(reader.get_version().unwrap(), 1);
+        assert_eq!(
+            access::get_env(&key, "KEY_1999").unwrap().unwrap(),
+            "v".repeat(100)
+        );
+        assert_eq!(access::get_env(&key, "MISSING").unwrap(), None);
+
+        assert!(EnvStorage::remove(&key).unwrap());
+    }
+
+    #[test]
+    fn test_snapshot_age() {
+        let mut data = SharedEnvData {
+            version: 1,
+            project_id: "p".to_string(),
+            environment: "development".to_string(),
+            variables: HashMap::new(),
+            last_updated: (chrono::Utc::now() - chrono::Duration::minutes(10)).to_rfc3339(),
+            etag: None,
+        };
+        let age = data.age().unwrap();
+        assert!(age >= std::time::Duration::from_secs(600));
+        assert!(age < std::time::Duration::from_secs(660));
+
+        data.last_updated = "last week".to_string();
+        assert!(data.age().is_none());
+    }
+
+    #[test]
+    fn test_payload_is_encrypted() {
+        let _guard = INDEX_LOCK.lock().unwrap();
+        let key = test_key("encrypted");
+        let mut storage = EnvStorage::open(&key).unwrap();
+
+        let mut vars = HashMap::new();
+        vars.insert("API_SECRET".to_string(), "plaintext-value".to_string());
+        let data = SharedEnvData {
+            version: 1,
+            project_id: key.project.clone(),
+            environment: key.environment.clone(),
+            variables: vars,
+            last_updated: chrono::Utc::now().to_rfc3339(),
+            etag: None,
+        };
+        storage.write(&data).unwrap();
+
+        let raw = region(&storage.shmem).read().unwrap().unwrap();
+        assert!(!raw.windows(15).any(|w| w == b"plaintext-value"));
+        assert_eq!(
+            storage.read().unwrap().unwrap().variables["API_SECRET"],
+            "plaintext-value"
+        );
+
+        // Garbage in the segment reads as nothing cached
+        region(&storage.shmem).write(b"not sealed").unwrap();
+        assert!(storage.read().unwrap().is_none());
+
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let path = format!("/dev/shm/{}", key.os_id());
+            let mode = std::fs::metadata(path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        assert!(EnvStorage::remove(&key).unwrap());
+    }
+
+    fn state_of(key: &SegmentKey, max_age: Duration) -> Option<SegmentState> {
+        EnvStorage::usage(max_age)
+            .unwrap()
+            .into_iter()
+            .find(|usage| usage.segment == key.os_id())
+            .map(|usage| usage.state)
+    }
+
+    #[test]
+    fn test_lease_protects_segment_from_purge() {
+        let _guard = INDEX_LOCK.lock().unwrap();
+        let key = test_key("leased");
+        let max_age = Duration::from_secs(60);
+        let mut storage = EnvStorage::open(&key).unwrap();
+        let lease = storage.lease().unwrap();
+
+        let data = SharedEnvData {
+            version: 1,
+            project_id: key.project.clone(),
+            environment: key.environment.clone(),
+            variables: HashMap::new(),
+            last_updated: (chrono::Utc::now() - chrono::Duration::hours(2)).to_rfc3339(),
+            etag: None,
+        };
+        // Writing keeps the holders recorded by the lease
+        storage.write(&data).unwrap();
+        assert_eq!(state_of(&key, max_age), Some(SegmentState::Held));
+
+        let purged = EnvStorage::purge(max_age, |usage| {
+            usage.segment == key.os_id() && usage.state != SegmentState::Held
+        })
+        .unwrap();
+        assert!(purged.is_empty());
+
+        drop(lease);
+        assert_eq!(state_of(&key, max_age), Some(SegmentState::Expired));
+        assert_eq!(
+            state_of(&key, Duration::from_secs(3 * 3600)),
+            Some(SegmentState::Idle)
+        );
+
+        let purged = EnvStorage::purge(max_age, |usage| {
+            usage.segment == key.os_id() && usage.is_orphaned()
+        })
+        .unwrap();
+        assert_eq!(purged.len(), 1);
+        assert!(purged[0].size > 0);
+        assert!(EnvStorage::open_existing(&key).unwrap().is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exited_holders_are_ignored() {
+        let _guard = INDEX_LOCK.lock().unwrap();
+        let key = test_key("exited-holder");
+        let storage = EnvStorage::open(&key).unwrap();
+
+        // Far above any pid the system hands out
+        index::acquire(&key, i32::MAX as u32).unwrap();
+        assert_eq!(
+            state_of(&key, Duration::from_secs(60)),
+            Some(SegmentState::Idle)
+        );
+
+        let _lease = storage.lease().unwrap();
+        let info = EnvStorage::list()
+            .unwrap()
+            .into_iter()
+            .find(|entry| entry.segment == key.os_id())
+            .unwrap();
+        assert_eq!(info.holders, vec![std::process::id()]);
+
+        assert!(EnvStorage::remove(&key).unwrap());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_unindexed_segments_are_orphaned() {
+        let _guard = INDEX_LOCK.lock().unwrap();
+        let key = test_key("unindexed");
+        // Created without going through the index, like a segment of a crashed writer
+        drop(create_or_open(&key.os_id(), INITIAL_CAPACITY).unwrap());
+
+        let usage = EnvStorage::usage(Duration::from_secs(60))
+            .unwrap()
+            .into_iter()
+            .find(|usage| usage.segment == key.os_id())
+            .unwrap();
+        assert_eq!(usage.state, SegmentState::Unindexed);
+        assert!(usage.is_orphaned());
+        assert_eq!(usage.label(), key.os_id());
+
+        assert!(EnvStorage::remove(&key).unwrap());
+    }
+
+    #[test]
+    fn test_logout_and_switch_keep_held_segments() {
+        let _guard = INDEX_LOCK.lock().unwrap();
+        let max_age = Duration::from_secs(60);
+        let held = test_key("switch-held");
+        let idle = test_key("switch-idle");
+        let other = test_key("switch-other");
+        let segments = [held.os_id(), idle.os_id(), other.os_id()];
+
+        let cache = |key: &SegmentKey| {
+            let mut storage = EnvStorage::open(key).unwrap();
+            storage
+                .write(&SharedEnvData {
+                    version: 1,
+                    project_id: key.project.clone(),
+                    environment: key.environment.clone(),
+                    variables: HashMap::new(),
+                    last_updated: chrono::Utc::now().to_rfc3339(),
+                    etag: None,
+                })
+                .unwrap();
+            storage
+        };
+        let held_storage = cache(&held);
+        let _lease = held_storage.lease().unwrap();
+        drop(cache(&idle));
+        drop(cache(&other));
+
+        // Switching away from two projects only drops the one nothing watches
+        let purged = EnvStorage::purge(max_age, |usage| {
+            usage.cleared_on_switch("test-workspace", &["switch-held", "switch-idle"])
+        })
+        .unwrap();
+        let purged: Vec<&str> = purged.iter().map(|u| u.segment.as_str()).collect();
+        assert_eq!(purged, vec![idle.os_id().as_str()]);
+
+        // Logout drops the rest, except what is still watched
+        let purged = EnvStorage::purge(max_age, |usage| {
+            usage.cleared_on_logout() && segments.contains(&usage.segment)
+        })
+        .unwrap();
+        let purged: Vec<&str> = purged.iter().map(|u| u.segment.as_str()).collect();
+        assert_eq!(purged, vec![other.os_id().as_str()]);
+        assert!(EnvStorage::open_existing(&held).unwrap().is_some());
+
+        drop(held_storage);
+        assert!(EnvStorage::remove(&held).unwrap());
+    }
+
+    #[test]
+    fn test_delete_clears_only_the_deleted_target() {
+        let _guard = INDEX_LOCK.lock().unwrap();
+        let max_age = Duration::from_secs(60);
+        let development = test_key("delete-target");
+        let staging = SegmentKey::new("test-workspace", "delete-target", "staging");
+
+        for key in [&development, &staging] {
+            EnvStorage::open(key)
+                .unwrap()
+                .write(&SharedEnvData {
+                    version: 1,
+                    project_id: key.project.clone(),
+                    environment: key.environment.clone(),
+                    variables: HashMap::new(),
+                    last_updated: chrono::Utc::now().to_rfc3339(),
+                    etag: None,
+                })
+                .unwrap();
+        }
+
+        // Deleting one environment leaves the project's other environments cached
+        let purged = EnvStorage::purge(max_age, |usage| {
+            usage.cleared_on_delete("test-workspace", &["delete-target"], Some("staging"))
+        })
+        .unwrap();
+        let purged: Vec<&str> = purged.iter().map(|u| u.segment.as_str()).collect();
+        assert_eq!(purged, vec![staging.os_id().as_str()]);
+
+        // Deleting the project drops the rest
+        let purged = EnvStorage::purge(max_age, |usage| {
+            usage.cleared_on_delete("test-workspace", &["delete-target"], None)
+        })
+        .unwrap();
+        let purged: Vec<&str> = purged.iter().map(|u| u.segment.as_str()).collect();
+        assert_eq!(purged, vec![development.os_id().as_str()]);
+    }
+
+    #[test]
+    fn test_environment_segment_names() {
+        assert!(is_environment_segment(&test_key("named").os_id()));
+        assert!(!is_environment_segment("envsafe_index_0123456789abcdef"));
+        assert!(!is_environment_segment("envsafe_client_test_getters_42"));
+        assert!(!is_environment_segment("other_0123456789abcdef"));
     }
 }
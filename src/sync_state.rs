@@ -0,0 +1,215 @@
+#![allow(dead_code)]
+
+use crate::config::Config;
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+/// What the remote environment looked like the last time this machine synced with it.
+///
+/// Only value hashes are kept, so the file never contains secrets.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncState {
+    /// Server version (ETag) of the environment at sync time
+    pub version: Option<String>,
+    pub synced_at: String,
+    pub hashes: HashMap<String, String>,
+}
+
+impl SyncState {
+    pub fn from_vars(vars: &HashMap<String, String>, version: Option<String>) -> Self {
+        Self {
+            version,
+            synced_at: Utc::now().to_rfc3339(),
+            hashes: vars
+                .iter()
+                .map(|(k, v)| (k.clone(), hash_value(k, v)))
+                .collect(),
+        }
+    }
+
+    pub fn load(project: &str, environment: &str) -> Result<Option<Self>> {
+        let path = Self::path(project, environment)?;
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    pub fn save(&self, project: &str, environment: &str) -> Result<()> {
+        let path = Self::path(project, environment)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        }
+
+        Ok(())
+    }
+
+    /// Forget the sync state of `environment`, or of every environment of `project`
+    pub fn remove(project: &str, environment: Option<&str>) -> Result<()> {
+        let dir = Config::config_dir()?.join("state");
+        if !dir.exists() {
+            return Ok(());
+        }
+
+        let project = sanitize(project);
+        let environment = environment.map(sanitize);
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let Some((p, e)) = stem.split_once("__") else {
+                continue;
+            };
+            if p == project && environment.as_deref().is_none_or(|env| env == e) {
+                fs::remove_file(&path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether `key` held `value` at sync time
+    pub fn matches(&self, key: &str, value: &str) -> bool {
+        self.hashes.get(key) == Some(&hash_value(key, value))
+    }
+
+    /// Keys added, changed or removed in `vars` since the sync
+    pub fn changed_keys(&self, vars: &HashMap<String, String>) -> BTreeSet<String> {
+        let mut changed: BTreeSet<String> = vars
+            .iter()
+            .filter(|(k, v)| !self.matches(k, v))
+            .map(|(k, _)| k.clone())
+            .collect();
+
+        changed.extend(
+            self.hashes
+                .keys()
+                .filter(|k| !vars.contains_key(*k))
+                .cloned(),
+        );
+
+        changed
+    }
+
+    /// Re-apply the local edits made since this sync on top of a newer remote state.
+    ///
+    /// Keys edited locally win; keys deleted locally are removed when `delete_missing` is set.
+    pub fn rebase(
+        &self,
+        local: &HashMap<String, String>,
+        remote: &HashMap<String, String>,
+        delete_missing: bool,
+    ) -> HashMap<String, String> {
+        let mut rebased = remote.clone();
+
+        for (key, value) in local {
+            if !self.matches(key, value) {
+                rebased.insert(key.clone(), value.clone());
+            }
+        }
+
+        if delete_missing {
+            for key in self.hashes.keys() {
+                if !local.contains_key(key) {
+                    rebased.remove(key);
+                }
+            }
+        }
+
+        rebased
+    }
+
+    fn path(project: &str, environment: &str) -> Result<PathBuf> {
+        let name = format!("{}__{}.json", sanitize(project), sanitize(environment));
+        Ok(Config::config_dir()?.join("state").join(name))
+    }
+}
+
+fn hash_value(key: &str, value: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(value.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_changed_keys() {
+        let state = SyncState::from_vars(&map(&[("A", "1"), ("B", "2"), ("C", "3")]), None);
+        let now = map(&[("A", "1"), ("B", "changed"), ("D", "4")]);
+
+        let changed: Vec<String> = state.changed_keys(&now).into_iter().collect();
+        assert_eq!(changed, vec!["B", "C", "D"]);
+    }
+
+    #[test]
+    fn test_rebase_keeps_remote_changes() {
+        let base = map(&[("A", "1"), ("B", "2"), ("C", "3")]);
+        let state = SyncState::from_vars(&base, Some("v1".to_string()));
+
+        // Locally: A edited, C deleted. Remotely: B edited, D added.
+        let local = map(&[("A", "local"), ("B", "2")]);
+        let remote = map(&[("A", "1"), ("B", "remote"), ("C", "3"), ("D", "4")]);
+
+        let merged = state.rebase(&local, &remote, false);
+        assert_eq!(merged.get("A").unwrap(), "local");
+        assert_eq!(merged.get("B").unwrap(), "remote");
+        assert_eq!(merged.get("C").unwrap(), "3");
+        assert_eq!(merged.get("D").unwrap(), "4");
+
+        let replaced = state.rebase(&local, &remote, true);
+        assert!(!replaced.contains_key("C"));
+        assert_eq!(replaced.get("D").unwrap(), "4");
+    }
+
+    #[test]
+    fn test_state_does_not_store_values() {
+        let state = SyncState::from_vars(&map(&[("API_KEY", "super-secret")]), None);
+        let json = serde_json::to_string(&state).unwrap();
+
+        assert!(!json.contains("super-secret"));
+        assert!(state.matches("API_KEY", "super-secret"));
+        assert!(!state.matches("API_KEY", "other"));
+    }
+}
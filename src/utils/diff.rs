@@ -0,0 +1,177 @@
+#![allow(dead_code)]
+
+use crate::status;
+use colored::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+const MASK: &str = "••••••••";
+
+/// Key-level difference between two sets of environment variables
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EnvDiff {
+    pub added: BTreeMap<String, String>,
+    pub removed: BTreeMap<String, String>,
+    pub changed: BTreeMap<String, (String, String)>,
+    pub unchanged: usize,
+}
+
+impl EnvDiff {
+    /// Compare `current` (what exists now) with `incoming` (what would replace it)
+    pub fn compute(current: &HashMap<String, String>, incoming: &HashMap<String, String>) -> Self {
+        let mut diff = EnvDiff::default();
+
+        for (key, new_value) in incoming {
+            match current.get(key) {
+                None => {
+                    diff.added.insert(key.clone(), new_value.clone());
+                }
+                Some(old_value) if old_value != new_value => {
+                    diff.changed
+                        .insert(key.clone(), (old_value.clone(), new_value.clone()));
+                }
+                Some(_) => diff.unchanged += 1,
+            }
+        }
+
+        for (key, old_value) in current {
+            if !incoming.contains_key(key) {
+                diff.removed.insert(key.clone(), old_value.clone());
+            }
+        }
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Print a colored, line-per-key view of the diff
+    pub fn print(&self, show_values: bool) {
+        if self.is_empty() {
+            status!("{}", "  No changes".bright_black());
+            return;
+        }
+
+        for (key, value) in &self.added {
+            status!(
+                "  {} {} = {}",
+                "+".green(),
+                key.green(),
+                display_value(value, show_values).bright_black()
+            );
+        }
+
+        for (key, (old, new)) in &self.changed {
+            if show_values {
+                status!(
+                    "  {} {}: {} → {}",
+                    "~".yellow(),
+                    key.yellow(),
+                    old.red(),
+                    new.green()
+                );
+            } else {
+                status!(
+                    "  {} {}: {}",
+                    "~".yellow(),
+                    key.yellow(),
+                    "(value changed)".bright_black()
+                );
+            }
+        }
+
+        for (key, value) in &self.removed {
+            status!(
+                "  {} {} = {}",
+                "-".red(),
+                key.red(),
+                display_value(value, show_values).bright_black()
+            );
+        }
+    }
+
+    /// One-line summary, e.g. "2 added, 1 changed, 0 removed, 5 unchanged"
+    pub fn summary(&self) -> String {
+        format!(
+            "{} added, {} changed, {} removed, {} unchanged",
+            self.added.len(),
+            self.changed.len(),
+            self.removed.len(),
+            self.unchanged
+        )
+    }
+
+    /// Changed key names without their values, for machine-readable output
+    pub fn key_summary(&self) -> DiffSummary {
+        DiffSummary {
+            added: self.added.keys().cloned().collect(),
+            changed: self.changed.keys().cloned().collect(),
+            removed: self.removed.keys().cloned().collect(),
+            unchanged: self.unchanged,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffSummary {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+    pub unchanged: usize,
+}
+
+/// Hide a secret value unless the user explicitly asked to reveal it
+pub fn display_value(value: &str, show_values: bool) -> String {
+    if show_values {
+        value.to_string()
+    } else {
+        MASK.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_compute_diff() {
+        let current = map(&[("KEEP", "1"), ("CHANGE", "old"), ("REMOVE", "x")]);
+        let incoming = map(&[("KEEP", "1"), ("CHANGE", "new"), ("ADD", "y")]);
+
+        let diff = EnvDiff::compute(&current, &incoming);
+
+        assert_eq!(diff.added.get("ADD").unwrap(), "y");
+        assert_eq!(diff.removed.get("REMOVE").unwrap(), "x");
+        assert_eq!(
+            diff.changed.get("CHANGE").unwrap(),
+            &("old".to_string(), "new".to_string())
+        );
+        assert_eq!(diff.unchanged, 1);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.summary(), "1 added, 1 changed, 1 removed, 1 unchanged");
+    }
+
+    #[test]
+    fn test_identical_maps_have_no_diff() {
+        let vars = map(&[("A", "1"), ("B", "2")]);
+        let diff = EnvDiff::compute(&vars, &vars);
+
+        assert!(diff.is_empty());
+        assert_eq!(diff.unchanged, 2);
+    }
+
+    #[test]
+    fn test_display_value_masks_by_default() {
+        assert_eq!(display_value("secret", false), MASK);
+        assert_eq!(display_value("secret", true), "secret");
+    }
+}
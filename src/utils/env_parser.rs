@@ -1,7 +1,50 @@
 #![allow(dead_code)]
 
 use anyhow::Result;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Layout of a local variables file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum FileFormat {
+    /// `KEY=value` lines
+    #[default]
+    Dotenv,
+    /// A flat JSON object
+    Json,
+    /// A flat YAML mapping
+    Yaml,
+}
+
+impl FileFormat {
+    /// The format a file name implies, if its extension names one
+    pub fn from_extension(path: &str) -> Option<Self> {
+        match std::path::Path::new(path).extension()?.to_str()? {
+            "json" => Some(FileFormat::Json),
+            "yaml" | "yml" => Some(FileFormat::Yaml),
+            _ => None,
+        }
+    }
+
+    pub fn parse(&self, content: &str) -> Result<HashMap<String, String>> {
+        match self {
+            FileFormat::Dotenv => parse_env_file(content),
+            FileFormat::Json => Ok(serde_json::from_str(content)?),
+            FileFormat::Yaml => Ok(serde_yaml::from_str(content)?),
+        }
+    }
+
+    /// Render variables sorted by key; dotenv output has no header comments
+    pub fn render(&self, vars: &HashMap<String, String>) -> Result<String> {
+        let sorted: BTreeMap<&String, &String> = vars.iter().collect();
+        match self {
+            FileFormat::Dotenv => Ok(format_env_file(vars)),
+            FileFormat::Json => Ok(serde_json::to_string_pretty(&sorted)?),
+            FileFormat::Yaml => Ok(serde_yaml::to_string(&sorted)?.trim_end().to_string()),
+        }
+    }
+}
 
 pub fn parse_env_file(content: &str) -> Result<HashMap<String, String>> {
     let mut vars = HashMap::new();
@@ -35,6 +78,17 @@ pub fn parse_env_file(content: &str) -> Result<HashMap<String, String>> {
     Ok(vars)
 }
 
+/// Variable names follow the usual shell rules: letters, digits and underscores, not starting with a digit
+pub fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
 pub fn format_env_file(vars: &HashMap<String, String>) -> String {
     let mut lines: Vec<String> = vars
         .iter()
@@ -72,6 +126,46 @@ DEBUG=true
         assert_eq!(vars.get("DEBUG").unwrap(), "true");
     }
 
+    #[test]
+    fn test_is_valid_key() {
+        assert!(is_valid_key("DATABASE_URL"));
+        assert!(is_valid_key("_PRIVATE"));
+        assert!(!is_valid_key("1KEY"));
+        assert!(!is_valid_key("MY-KEY"));
+        assert!(!is_valid_key(""));
+    }
+
+    #[test]
+    fn test_file_format_round_trip() {
+        let mut vars = HashMap::new();
+        vars.insert("API_URL".to_string(), "https://api.example.com".to_string());
+        vars.insert("DEBUG".to_string(), "true".to_string());
+
+        for format in [FileFormat::Dotenv, FileFormat::Json, FileFormat::Yaml] {
+            let rendered = format.render(&vars).unwrap();
+            assert_eq!(format.parse(&rendered).unwrap(), vars);
+        }
+
+        assert_eq!(
+            FileFormat::Yaml.render(&vars).unwrap(),
+            "API_URL: https://api.example.com\nDEBUG: 'true'"
+        );
+    }
+
+    #[test]
+    fn test_file_format_from_extension() {
+        assert_eq!(
+            FileFormat::from_extension("env.json"),
+            Some(FileFormat::Json)
+        );
+        assert_eq!(
+            FileFormat::from_extension("a/env.yml"),
+            Some(FileFormat::Yaml)
+        );
+        assert_eq!(FileFormat::from_extension(".env"), None);
+        assert_eq!(FileFormat::from_extension(".env.local"), None);
+    }
+
     #[test]
     fn test_format_env_file() {
         let mut vars = HashMap::new();
@@ -67,6 +67,10 @@ pub struct WatchTranslations {
 pub struct RunTranslations {
     pub executing: &'static str,
     pub using_cached: &'static str,
+    pub fetched: &'static str,
+    pub from_agent: &'static str,
+    pub cache_stale: &'static str,
+    pub cache_outdated: &'static str,
     pub loaded_vars: &'static str,
     pub success: &'static str,
     pub failure: &'static str,
@@ -140,6 +144,10 @@ pub fn get_translations(lang: &str) -> Translations {
             run: RunTranslations {
                 executing: "🚀 Exécution de la commande avec injection...",
                 using_cached: "✓ Utilisation du cache (Shared Memory)",
+                fetched: "✓ Variables récupérées depuis EnvSafe",
+                from_agent: "✓ Variables fournies par l'agent EnvSafe",
+                cache_stale: "Le cache date de {}, récupération depuis EnvSafe",
+                cache_outdated: "Le cache n'est plus à jour, récupération depuis EnvSafe",
                 loaded_vars: "✓ {} variables chargées",
                 success: "✓ Commande terminée avec succès",
                 failure: "La commande a échoué avec le statut : {}",
@@ -207,6 +215,10 @@ pub fn get_translations(lang: &str) -> Translations {
             run: RunTranslations {
                 executing: "🚀 Running command with injected environment...",
                 using_cached: "✓ Using cached variables from shared memory",
+                fetched: "✓ Fetched variables from EnvSafe",
+                from_agent: "✓ Got variables from the EnvSafe agent",
+                cache_stale: "Cache is {} old, fetching from EnvSafe",
+                cache_outdated: "Cache is out of date with the server, fetching from EnvSafe",
                 loaded_vars: "✓ Loaded {} variables",
                 success: "✓ Command completed successfully",
                 failure: "Command failed with status: {}",
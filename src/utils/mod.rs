@@ -1,2 +1,10 @@
-pub mod i18n;
+pub mod diff;
+pub mod duration;
 pub mod env_parser;
+pub mod git;
+pub mod i18n;
+pub mod output;
+pub mod pattern;
+pub mod prompt;
+pub mod suggest;
+pub mod target;
@@ -0,0 +1,316 @@
+#![allow(dead_code)]
+
+use anyhow::Result;
+use serde::Serialize;
+use std::fmt;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+/// How commands write their results to stdout
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable output with colors and icons
+    #[default]
+    Text,
+    /// Plain aligned columns, one record per line
+    Table,
+    /// Pretty-printed JSON document
+    Json,
+    /// YAML document
+    Yaml,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Settings {
+    format: OutputFormat,
+    quiet: bool,
+    plain: bool,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Set the output mode for the whole process; only the first call has an effect.
+///
+/// Also decides once whether colors are used, from `--plain`, `NO_COLOR`,
+/// `CLICOLOR_FORCE` and whether the stream carrying decoration is a terminal: stdout, or
+/// stderr when stdout carries a JSON/YAML document.
+pub fn init(format: OutputFormat, quiet: bool, plain: bool) {
+    let _ = SETTINGS.set(Settings {
+        format,
+        quiet,
+        plain,
+    });
+
+    let decoration_is_tty = if is_machine() {
+        std::io::stderr().is_terminal()
+    } else {
+        std::io::stdout().is_terminal()
+    };
+    let colors = color_enabled(
+        plain,
+        std::env::var("NO_COLOR").ok().as_deref(),
+        std::env::var("CLICOLOR_FORCE").ok().as_deref(),
+        decoration_is_tty,
+    );
+    colored::control::set_override(colors);
+}
+
+/// `--plain` always wins, then `CLICOLOR_FORCE`, then `NO_COLOR`, then TTY detection
+fn color_enabled(
+    plain: bool,
+    no_color: Option<&str>,
+    clicolor_force: Option<&str>,
+    is_tty: bool,
+) -> bool {
+    if plain {
+        return false;
+    }
+    if clicolor_force.is_some_and(|v| !v.is_empty() && v != "0") {
+        return true;
+    }
+    if no_color.is_some_and(|v| !v.is_empty()) {
+        return false;
+    }
+    is_tty
+}
+
+fn settings() -> Settings {
+    SETTINGS.get().copied().unwrap_or_default()
+}
+
+pub fn format() -> OutputFormat {
+    settings().format
+}
+
+/// Whether results must be emitted as a structured document (JSON or YAML)
+pub fn is_machine() -> bool {
+    matches!(format(), OutputFormat::Json | OutputFormat::Yaml)
+}
+
+pub fn is_quiet() -> bool {
+    settings().quiet
+}
+
+/// Accessible mode: no colors and no emoji, for screen readers and log files
+pub fn is_plain() -> bool {
+    settings().plain
+}
+
+/// Write a command's result in the selected structured format.
+///
+/// Text and table callers render their own output; this falls back to JSON for them.
+pub fn emit<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", render(value, format())?);
+    Ok(())
+}
+
+/// Write one document of a stream, for commands that report as they go: a JSON line, or a
+/// YAML document starting with `---`
+pub fn emit_event<T: Serialize>(value: &T) -> Result<()> {
+    match format() {
+        OutputFormat::Yaml => println!("---\n{}", render(value, OutputFormat::Yaml)?),
+        _ => println!("{}", serde_json::to_string(value)?),
+    }
+    Ok(())
+}
+
+/// In json/yaml mode, report that a confirmation was declined and nothing was changed
+pub fn cancelled() -> Result<()> {
+    if is_machine() {
+        emit(&serde_json::json!({ "cancelled": true }))?;
+    }
+    Ok(())
+}
+
+fn render<T: Serialize>(value: &T, format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Yaml => Ok(serde_yaml::to_string(value)?.trim_end().to_string()),
+        _ => Ok(serde_json::to_string_pretty(value)?),
+    }
+}
+
+/// Print decoration and progress messages.
+///
+/// They go to stdout in text mode, to stderr when stdout carries a JSON/YAML document,
+/// and nowhere with `--quiet`.
+pub fn status_line(args: fmt::Arguments) {
+    if is_quiet() {
+        return;
+    }
+
+    let line = if is_plain() {
+        strip_emoji(&args.to_string())
+    } else {
+        args.to_string()
+    };
+
+    if is_machine() {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+/// Remove pictographic icons, spelling out the ones that carry meaning
+pub fn strip_emoji(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut skip_spaces = false;
+
+    for c in text.chars() {
+        if skip_spaces && c == ' ' {
+            continue;
+        }
+        skip_spaces = false;
+
+        match c {
+            '⚠' => {
+                plain.push_str("Warning: ");
+                skip_spaces = true;
+            }
+            'ℹ' => {
+                plain.push_str("Note: ");
+                skip_spaces = true;
+            }
+            c if is_emoji(c) => skip_spaces = true,
+            c => plain.push(c),
+        }
+    }
+
+    plain
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0xFE0F | 0x200D
+    )
+}
+
+/// `println!` for decoration: see [`status_line`]
+#[macro_export]
+macro_rules! status {
+    () => {
+        $crate::utils::output::status_line(format_args!(""))
+    };
+    ($($arg:tt)*) => {
+        $crate::utils::output::status_line(format_args!($($arg)*))
+    };
+}
+
+/// Print rows as plain aligned columns under an upper-case header
+pub fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    if !is_quiet() {
+        let header: Vec<String> = headers.iter().map(|h| h.to_uppercase()).collect();
+        println!("{}", format_row(&header, &widths));
+    }
+    for row in rows {
+        println!("{}", format_row(row, &widths));
+    }
+}
+
+/// `512 B`, `64.0 KB`, `1.5 MB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+fn format_row(cells: &[String], widths: &[usize]) -> String {
+    cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+        .collect::<Vec<_>>()
+        .join("  ")
+        .trim_end()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Sample {
+        name: String,
+        count: usize,
+    }
+
+    #[test]
+    fn test_render_json_and_yaml() {
+        let sample = Sample {
+            name: "api".to_string(),
+            count: 2,
+        };
+
+        let json = render(&sample, OutputFormat::Json).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            serde_json::json!({ "name": "api", "count": 2 })
+        );
+
+        let yaml = render(&sample, OutputFormat::Yaml).unwrap();
+        assert_eq!(yaml, "name: api\ncount: 2");
+    }
+
+    #[test]
+    fn test_format_row_aligns_columns() {
+        let row = vec!["api".to_string(), "2".to_string()];
+        assert_eq!(format_row(&row, &[6, 3]), "api     2");
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(64 * 1024), "64.0 KB");
+        assert_eq!(format_size(3 * 1024 * 1024 / 2), "1.5 MB");
+    }
+
+    #[test]
+    fn test_strip_emoji() {
+        assert_eq!(
+            strip_emoji("📤 Pushing variables..."),
+            "Pushing variables..."
+        );
+        assert_eq!(strip_emoji("✓ Pushed to staging"), "Pushed to staging");
+        assert_eq!(
+            strip_emoji("⚠️  2 key(s) will be deleted"),
+            "Warning: 2 key(s) will be deleted"
+        );
+        assert_eq!(
+            strip_emoji("  Project: api • dev → prod"),
+            "  Project: api • dev → prod"
+        );
+    }
+
+    #[test]
+    fn test_color_enabled() {
+        assert!(color_enabled(false, None, None, true));
+        assert!(!color_enabled(false, None, None, false));
+        assert!(!color_enabled(false, Some("1"), None, true));
+        assert!(color_enabled(false, Some(""), None, true));
+        assert!(color_enabled(false, Some("1"), Some("1"), false));
+        assert!(!color_enabled(false, None, Some("0"), false));
+        assert!(!color_enabled(true, None, Some("1"), true));
+    }
+
+    #[test]
+    fn test_default_is_text() {
+        assert_eq!(OutputFormat::default(), OutputFormat::Text);
+    }
+}
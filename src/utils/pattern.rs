@@ -0,0 +1,91 @@
+#![allow(dead_code)]
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Match `text` against a glob where `*` is any run of characters and `?` any single one
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it was tried at, for backtracking
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Which variable keys a directory works with, as glob patterns
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyFilter {
+    /// Only keys matching one of these patterns (all keys when empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Keys matching one of these patterns are always left out
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+impl KeyFilter {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn allows(&self, key: &str) -> bool {
+        let included = self.include.is_empty() || self.include.iter().any(|p| glob_match(p, key));
+        included && !self.exclude.iter().any(|p| glob_match(p, key))
+    }
+
+    /// Drop the variables this filter does not allow
+    pub fn apply(&self, vars: &mut HashMap<String, String>) {
+        if !self.is_empty() {
+            vars.retain(|key, _| self.allows(key));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("main", "main"));
+        assert!(glob_match("release/*", "release/1.2"));
+        assert!(glob_match("*_KEY", "STRIPE_SECRET_KEY"));
+        assert!(glob_match("feature/*/ui", "feature/login/ui"));
+        assert!(glob_match("v?", "v2"));
+        assert!(!glob_match("release/*", "hotfix/1.2"));
+        assert!(!glob_match("main", "main2"));
+    }
+
+    #[test]
+    fn test_key_filter() {
+        let filter = KeyFilter {
+            include: vec!["NEXT_PUBLIC_*".to_string(), "API_*".to_string()],
+            exclude: vec!["*_SECRET".to_string()],
+        };
+
+        assert!(filter.allows("NEXT_PUBLIC_URL"));
+        assert!(filter.allows("API_URL"));
+        assert!(!filter.allows("API_SECRET"));
+        assert!(!filter.allows("DATABASE_URL"));
+        assert!(KeyFilter::default().allows("ANYTHING"));
+    }
+}
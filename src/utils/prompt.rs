@@ -0,0 +1,50 @@
+#![allow(dead_code)]
+
+use anyhow::Result;
+use dialoguer::Confirm;
+use std::io::IsTerminal;
+
+/// Environments that require an explicit confirmation before being modified
+const PROTECTED_ENVIRONMENTS: &[&str] = &["production", "prod"];
+
+pub fn is_protected_environment(env_name: &str) -> bool {
+    PROTECTED_ENVIRONMENTS
+        .iter()
+        .any(|e| e.eq_ignore_ascii_case(env_name))
+}
+
+/// Whether the user can answer prompts: stdin and stderr (where prompts draw) are terminals
+pub fn is_interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+/// Fail fast with a hint instead of waiting for input that will never come
+pub fn require_interactive(what: &str, hint: &str) -> Result<()> {
+    if !is_interactive() {
+        anyhow::bail!(
+            "Cannot ask for {} without an interactive terminal. {}",
+            what,
+            hint
+        );
+    }
+    Ok(())
+}
+
+/// Ask for a yes/no confirmation, skipped when `assume_yes` is set
+pub fn confirm(prompt: &str, assume_yes: bool) -> Result<bool> {
+    if assume_yes {
+        return Ok(true);
+    }
+
+    require_interactive(
+        &format!("confirmation ({})", prompt),
+        "Re-run with --yes to proceed",
+    )?;
+
+    let confirmed = Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()?;
+
+    Ok(confirmed)
+}
@@ -0,0 +1,100 @@
+#![allow(dead_code)]
+
+/// Edit distance between two strings (insertions, deletions and substitutions)
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b_chars.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b_chars.len() + 1];
+        for (j, cb) in b_chars.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current[j + 1] = (previous[j] + cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b_chars.len()]
+}
+
+/// Closest candidate to `input`, if one is close enough to be a plausible typo
+pub fn did_you_mean<'a, I>(input: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let input = input.to_lowercase();
+    let threshold = (input.chars().count() / 3).max(2);
+
+    candidates
+        .into_iter()
+        .map(|c| {
+            let lower = c.to_lowercase();
+            // Prefixes like "prod" for "production" count as close
+            let distance = if lower.starts_with(&input) {
+                0
+            } else {
+                levenshtein(&input, &lower)
+            };
+            (distance, c)
+        })
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, c)| c)
+}
+
+/// Error for an unknown name, with a suggestion when one candidate is close
+pub fn not_found(kind: &str, query: &str, candidates: &[&str]) -> anyhow::Error {
+    match did_you_mean(query, candidates.iter().copied()) {
+        Some(suggestion) => anyhow::anyhow!(
+            "{} '{}' not found. Did you mean '{}'?",
+            kind,
+            query,
+            suggestion
+        ),
+        None => anyhow::anyhow!(
+            "{} '{}' not found. Available: {}",
+            kind,
+            query,
+            candidates.join(", ")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("production", "production"), 0);
+        assert_eq!(levenshtein("prodution", "production"), 1);
+        assert_eq!(levenshtein("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_did_you_mean() {
+        let envs = ["development", "staging", "production"];
+
+        assert_eq!(did_you_mean("prodution", envs), Some("production"));
+        assert_eq!(did_you_mean("stagng", envs), Some("staging"));
+        assert_eq!(did_you_mean("prod", envs), Some("production"));
+        assert_eq!(did_you_mean("qa", envs), None);
+    }
+
+    #[test]
+    fn test_not_found_message() {
+        let error = not_found("Project", "paymnets", &["payments", "web"]);
+        assert_eq!(
+            error.to_string(),
+            "Project 'paymnets' not found. Did you mean 'payments'?"
+        );
+
+        let error = not_found("Workspace", "acme", &["personal"]);
+        assert_eq!(
+            error.to_string(),
+            "Workspace 'acme' not found. Available: personal"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+#![allow(dead_code)]
+
+use crate::api::{ApiClient, ApiError};
+use crate::config::{Config, ProjectConfig, DEFAULT_ENVIRONMENT};
+use crate::utils::suggest::did_you_mean;
+use anyhow::Result;
+use std::fmt;
+
+/// A remote environment, written `<project>/<env>` or just `<env>` for the current project
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteTarget {
+    pub project: String,
+    pub environment: String,
+}
+
+impl RemoteTarget {
+    pub fn parse(spec: &str) -> Result<Self> {
+        if let Some((project, environment)) = spec.split_once('/') {
+            if project.is_empty() || environment.is_empty() {
+                anyhow::bail!("Invalid target '{}'. Expected <project>/<env>", spec);
+            }
+            return Ok(Self {
+                project: project.to_string(),
+                environment: resolve_alias(environment),
+            });
+        }
+
+        let local_config = ProjectConfig::load()?.ok_or_else(|| {
+            anyhow::anyhow!(
+                "No project configured for '{}'. Use <project>/<env> or run 'envsafe init'",
+                spec
+            )
+        })?;
+        let project = local_config.project_slug.unwrap_or(local_config.project_id);
+
+        Ok(Self {
+            project,
+            environment: resolve_alias(spec),
+        })
+    }
+
+    /// Use the explicit project if given, otherwise the one linked in `.envsafe`
+    pub fn resolve(project: Option<String>, environment: Option<String>) -> Result<Self> {
+        // The directory's default environment only applies to the directory's project
+        let environment = match (environment, &project) {
+            (Some(environment), _) => environment,
+            (None, Some(_)) => DEFAULT_ENVIRONMENT.to_string(),
+            (None, None) => ProjectConfig::load()?
+                .map(|local_config| local_config.environment())
+                .unwrap_or_else(|| DEFAULT_ENVIRONMENT.to_string()),
+        };
+
+        Ok(Self {
+            project: Self::resolve_project(project)?,
+            environment,
+        })
+    }
+
+    /// Project identifier used by the API, from the argument or the local `.envsafe`
+    pub fn resolve_project(project: Option<String>) -> Result<String> {
+        if let Some(proj) = project {
+            Ok(proj)
+        } else if let Some(local_config) = ProjectConfig::load()? {
+            Ok(local_config.project_slug.unwrap_or(local_config.project_id))
+        } else {
+            anyhow::bail!("No project specified. Run 'envsafe init' or provide project name");
+        }
+    }
+
+    /// Fail early when the environment does not exist in the project
+    pub async fn validate(&self, api_client: &ApiClient, token: &str) -> Result<()> {
+        validate_environment(api_client, token, &self.project, &self.environment).await
+    }
+}
+
+fn resolve_alias(environment: &str) -> String {
+    Config::load()
+        .unwrap_or_default()
+        .resolve_environment(environment)
+}
+
+/// Check `env_name` against the project's environments and suggest the closest one on a typo.
+///
+/// Servers too old to list environments skip validation and remain the judge; any other
+/// failure (authentication, network) is returned.
+pub async fn validate_environment(
+    api_client: &ApiClient,
+    token: &str,
+    project: &str,
+    env_name: &str,
+) -> Result<()> {
+    let environments = match api_client.get_environments(token, project).await {
+        Ok(environments) => environments,
+        Err(e) if matches!(e.downcast_ref::<ApiError>(), Some(ApiError::Unsupported(_))) => {
+            return Ok(())
+        }
+        Err(e) => return Err(e),
+    };
+
+    if environments.is_empty() || environments.iter().any(|e| e.name == env_name) {
+        return Ok(());
+    }
+
+    let names: Vec<&str> = environments.iter().map(|e| e.name.as_str()).collect();
+    let hint = match did_you_mean(env_name, names.iter().copied()) {
+        Some(suggestion) => format!(" Did you mean '{}'?", suggestion),
+        None => String::new(),
+    };
+
+    anyhow::bail!(
+        "Environment '{}' not found in project {}.{} Available: {}",
+        env_name,
+        project,
+        hint,
+        names.join(", ")
+    )
+}
+
+impl fmt::Display for RemoteTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.project, self.environment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_project_and_env() {
+        let target = RemoteTarget::parse("my-api/staging").unwrap();
+        assert_eq!(target.project, "my-api");
+        assert_eq!(target.environment, "staging");
+        assert_eq!(target.to_string(), "my-api/staging");
+    }
+
+    #[test]
+    fn test_parse_rejects_empty_parts() {
+        assert!(RemoteTarget::parse("/staging").is_err());
+        assert!(RemoteTarget::parse("my-api/").is_err());
+    }
+}
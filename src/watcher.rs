@@ -1,8 +1,11 @@
 #![allow(deprecated)]
 
 use crate::api::{ApiClient, EnvVariable};
-use crate::config::Config;
-use crate::storage::{EnvStorage, SharedEnvData};
+use crate::config::{Config, DEFAULT_FILE};
+use crate::status;
+use crate::storage::{EnvStorage, Lease, SegmentKey, SharedEnvData};
+use crate::utils::env_parser::FileFormat;
+use crate::utils::pattern::KeyFilter;
 use anyhow::Result;
 use chrono::Utc;
 use colored::*;
@@ -10,33 +13,73 @@ use notify::event::{DataChange, ModifyKind};
 use notify::{Event, EventKind, RecursiveMode, Watcher};
 use std::collections::HashMap;
 use std::path::Path;
-use tokio::sync::mpsc;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
 
 pub struct EnvWatcher {
     api_client: ApiClient,
     config: Config,
     storage: EnvStorage,
+    /// Local file kept in sync, if any
+    file_path: Option<String>,
+    format: FileFormat,
+    filter: KeyFilter,
+    updates: Option<watch::Sender<Option<SharedEnvData>>>,
+    /// Whether a snapshot was fetched since the watcher was created
+    fetched: bool,
+    /// Keeps `cache purge` away from the segment while it is being watched
+    _lease: Lease,
 }
 
 impl EnvWatcher {
-    pub fn new(api_client: ApiClient, config: Config) -> Result<Self> {
-        let storage = EnvStorage::new()?;
+    /// Watch the environment whose shared memory segment is `key`
+    pub fn new(api_client: ApiClient, config: Config, key: &SegmentKey) -> Result<Self> {
+        let storage = EnvStorage::open(key)?;
+        let lease = storage.lease()?;
         Ok(Self {
             api_client,
             config,
             storage,
+            file_path: Some(DEFAULT_FILE.to_string()),
+            format: FileFormat::default(),
+            filter: KeyFilter::default(),
+            updates: None,
+            fetched: false,
+            _lease: lease,
         })
     }
 
+    /// Keep `file_path` in sync instead of `.env`, with only the keys `filter` allows
+    pub fn with_file(mut self, file_path: String, format: FileFormat, filter: KeyFilter) -> Self {
+        self.file_path = Some(file_path);
+        self.format = format;
+        self.filter = filter;
+        self
+    }
+
+    /// Only keep shared memory in sync, don't write a local file
+    pub fn without_file(mut self) -> Self {
+        self.file_path = None;
+        self
+    }
+
+    /// Publish every snapshot written to shared memory on `sender` as well
+    pub fn with_updates(mut self, sender: watch::Sender<Option<SharedEnvData>>) -> Self {
+        self.updates = Some(sender);
+        self
+    }
+
     /// Watch for changes from remote (WebSocket) and update local shared memory
     pub async fn watch_remote(&mut self, project_id: &str, environment: &str) -> Result<()> {
-        println!("{}", "🔄 Starting hot reload watcher...".cyan());
-        println!("{}", format!("  Project: {}", project_id).bright_black());
-        println!(
+        status!("{}", "🔄 Starting hot reload watcher...".cyan());
+        status!("{}", format!("  Project: {}", project_id).bright_black());
+        status!(
             "{}",
             format!("  Environment: {}", environment).bright_black()
         );
-        println!("{}", "  Watching for remote changes...".bright_black());
+        status!("{}", "  Watching for remote changes...".bright_black());
 
         let token = self.config.get_token()?;
         let ws_url = if let Some(ws_url) = &self.config.ws_url {
@@ -54,7 +97,7 @@ impl EnvWatcher {
 
         // Connect to WebSocket
         let (ws_stream, _) = tokio_tungstenite::connect_async(&ws_url).await?;
-        println!("{}", "✓ WebSocket connected".green());
+        status!("{}", "✓ WebSocket connected".green());
 
         use futures_util::StreamExt;
         let (_, mut read) = ws_stream.split();
@@ -71,7 +114,7 @@ impl EnvWatcher {
                 Ok(msg) => {
                     if let Ok(text) = msg.to_text() {
                         if text == "update" {
-                            println!("{}", "📥 Remote change detected, updating...".yellow());
+                            status!("{}", "📥 Remote change detected, updating...".yellow());
                             self.fetch_and_update(
                                 project_id,
                                 environment,
@@ -92,6 +135,27 @@ impl EnvWatcher {
         Ok(())
     }
 
+    /// `watch_remote` forever, reconnecting with a growing delay when the connection fails.
+    ///
+    /// If the first attempt fails before any snapshot was fetched, its error is returned
+    /// instead, so callers can report it rather than wait on retries.
+    pub async fn keep_watching(&mut self, project_id: &str, environment: &str) -> Result<()> {
+        let mut delay = Duration::from_secs(1);
+        loop {
+            match self.watch_remote(project_id, environment).await {
+                // The connection was up then closed: reconnect promptly
+                Ok(()) => delay = Duration::from_secs(1),
+                Err(e) if !self.fetched => return Err(e),
+                Err(e) => eprintln!(
+                    "{}",
+                    format!("Watching {}/{} failed: {}", project_id, environment, e).red()
+                ),
+            }
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    }
+
     /// Watch local .env file for changes and sync to remote
     #[allow(dead_code)]
     pub async fn watch_local(
@@ -100,8 +164,8 @@ impl EnvWatcher {
         environment: &str,
         file_path: &str,
     ) -> Result<()> {
-        println!("{}", "📂 Watching local file for changes...".cyan());
-        println!("{}", format!("  File: {}", file_path).bright_black());
+        status!("{}", "📂 Watching local file for changes...".cyan());
+        status!("{}", format!("  File: {}", file_path).bright_black());
 
         let (tx, mut rx) = mpsc::channel(100);
 
@@ -118,12 +182,12 @@ impl EnvWatcher {
 
         watcher.watch(Path::new(file_path), RecursiveMode::NonRecursive)?;
 
-        println!("{}", "✓ File watcher started".green());
+        status!("{}", "✓ File watcher started".green());
 
         let token = self.config.get_token()?;
 
         while let Some(_event) = rx.recv().await {
-            println!(
+            status!(
                 "{}",
                 "📤 Local change detected, syncing to remote...".yellow()
             );
@@ -147,7 +211,7 @@ impl EnvWatcher {
                         .await
                     {
                         Ok(_) => {
-                            println!("{}", "✓ Successfully synced to remote".green());
+                            status!("{}", "✓ Successfully synced to remote".green());
 
                             // Update shared memory
                             let vars_map: HashMap<String, String> =
@@ -160,6 +224,7 @@ impl EnvWatcher {
                                 environment: environment.to_string(),
                                 variables: vars_map,
                                 last_updated: Utc::now().to_rfc3339(),
+                                etag: None,
                             };
 
                             self.storage.write(&data)?;
@@ -190,11 +255,12 @@ impl EnvWatcher {
             .get_environment(token, project_id, environment)
             .await?;
 
-        let vars_map: HashMap<String, String> = env
+        let mut vars_map: HashMap<String, String> = env
             .variables
             .iter()
             .map(|v| (v.key.clone(), v.value.clone()))
             .collect();
+        self.filter.apply(&mut vars_map);
 
         *current_version += 1;
 
@@ -204,14 +270,21 @@ impl EnvWatcher {
             environment: environment.to_string(),
             variables: vars_map.clone(),
             last_updated: Utc::now().to_rfc3339(),
+            etag: env.version.clone(),
         };
 
         self.storage.write(&data)?;
+        self.fetched = true;
+        if let Some(updates) = &self.updates {
+            updates.send_replace(Some(data));
+        }
 
         // Update local .env file
-        self.update_local_file(project_id, environment, &vars_map)?;
+        if let Some(file_path) = &self.file_path {
+            self.update_local_file(file_path, project_id, environment, &vars_map)?;
+        }
 
-        println!(
+        status!(
             "{}",
             format!(
                 "✓ Updated {} variables (v{})",
@@ -226,14 +299,26 @@ impl EnvWatcher {
 
     fn update_local_file(
         &self,
+        file_path: &str,
         project_id: &str,
         environment: &str,
         remote_vars: &HashMap<String, String>,
     ) -> Result<()> {
-        let file_path = ".env";
         let path = Path::new(file_path);
         let mut local_only_vars: Vec<(String, String)> = Vec::new();
 
+        // Structured formats cannot hold comments, so remote values are merged into the file's keys
+        if self.format != FileFormat::Dotenv {
+            let mut vars = if path.exists() {
+                self.format.parse(&std::fs::read_to_string(path)?)?
+            } else {
+                HashMap::new()
+            };
+            vars.extend(remote_vars.iter().map(|(k, v)| (k.clone(), v.clone())));
+            std::fs::write(path, self.format.render(&vars)?)?;
+            return Ok(());
+        }
+
         if path.exists() {
             let content = std::fs::read_to_string(path)?;
             for line in content.lines() {
@@ -1,6 +1,7 @@
 use envsafe_cli::api::{
-    ApiClient, EnvVariable, EnvironmentResponse, Project, ProjectsResponse, User, Workspace,
-    WorkspacesResponse,
+    ApiClient, AuditQuery, AuditResponse, EnvVariable, EnvironmentResponse, EnvironmentsResponse,
+    HistoryResponse, MembersResponse, Project, ProjectUpdate, ProjectsResponse, SnapshotPoint,
+    SnapshotResponse, User, VariableResponse, Workspace, WorkspacesResponse,
 };
 
 #[test]
@@ -114,6 +115,7 @@ fn test_env_variable_creation() {
 }
 
 #[test]
+#[allow(clippy::assertions_on_constants)]
 fn test_api_client_creation() {
     let _client = ApiClient::new("https://api.example.com".to_string());
 
@@ -154,3 +156,169 @@ fn test_project_missing_optional_fields() {
     assert!(project.environments.is_none());
     assert!(project.updated_at.is_none());
 }
+
+#[test]
+fn test_variable_response_deserialization() {
+    let json = r#"{
+        "key": "API_KEY",
+        "value": "secret123",
+        "updatedAt": "2024-01-01T00:00:00Z"
+    }"#;
+
+    let variable: VariableResponse = serde_json::from_str(json).unwrap();
+    assert_eq!(variable.key, "API_KEY");
+    assert_eq!(variable.value, "secret123");
+    assert_eq!(
+        variable.updated_at,
+        Some("2024-01-01T00:00:00Z".to_string())
+    );
+}
+
+#[test]
+fn test_history_response_deserialization() {
+    let json = r#"{
+        "entries": [
+            {
+                "version": 12,
+                "key": "API_KEY",
+                "action": "updated",
+                "author": "alice@example.com",
+                "createdAt": "2024-01-01T00:00:00Z"
+            },
+            {
+                "version": 11,
+                "key": "DEBUG",
+                "action": "created",
+                "createdAt": "2023-12-31T00:00:00Z"
+            }
+        ],
+        "count": 2
+    }"#;
+
+    let response: HistoryResponse = serde_json::from_str(json).unwrap();
+    assert_eq!(response.count, 2);
+    assert_eq!(response.entries[0].version, 12);
+    assert_eq!(response.entries[0].action, "updated");
+    assert_eq!(
+        response.entries[0].author,
+        Some("alice@example.com".to_string())
+    );
+    assert!(response.entries[1].author.is_none());
+}
+
+#[test]
+fn test_snapshot_response_deserialization() {
+    let json = r#"{
+        "version": 7,
+        "createdAt": "2024-01-01T00:00:00Z",
+        "variables": { "API_KEY": "old-secret" }
+    }"#;
+
+    let snapshot: SnapshotResponse = serde_json::from_str(json).unwrap();
+    assert_eq!(snapshot.version, 7);
+    assert_eq!(snapshot.variables.get("API_KEY").unwrap(), "old-secret");
+}
+
+#[test]
+fn test_snapshot_point_parsing() {
+    assert_eq!(
+        "42".parse::<SnapshotPoint>().unwrap(),
+        SnapshotPoint::Version(42)
+    );
+    assert!(matches!(
+        "2024-01-01T12:00:00Z".parse::<SnapshotPoint>().unwrap(),
+        SnapshotPoint::Timestamp(_)
+    ));
+    assert!(matches!(
+        "2024-01-01".parse::<SnapshotPoint>().unwrap(),
+        SnapshotPoint::Timestamp(_)
+    ));
+    assert!("yesterday".parse::<SnapshotPoint>().is_err());
+}
+
+#[test]
+fn test_audit_response_deserialization() {
+    let json = r#"{
+        "entries": [
+            {
+                "id": "evt1",
+                "action": "read",
+                "user": "alice@example.com",
+                "environment": "production",
+                "key": "API_KEY",
+                "ipAddress": "10.0.0.1",
+                "createdAt": "2024-01-01T00:00:00Z"
+            },
+            {
+                "id": "evt2",
+                "action": "update",
+                "createdAt": "2024-01-02T00:00:00Z"
+            }
+        ],
+        "nextPage": 2
+    }"#;
+
+    let response: AuditResponse = serde_json::from_str(json).unwrap();
+    assert_eq!(response.entries.len(), 2);
+    assert_eq!(response.next_page, Some(2));
+    assert_eq!(response.entries[0].ip_address, Some("10.0.0.1".to_string()));
+    assert!(response.entries[1].user.is_none());
+}
+
+#[test]
+fn test_audit_query_skips_empty_filters() {
+    let query = AuditQuery {
+        environment: Some("production".to_string()),
+        per_page: Some(50),
+        ..Default::default()
+    };
+
+    let json = serde_json::to_value(&query).unwrap();
+    assert_eq!(json["environment"], "production");
+    assert_eq!(json["perPage"], 50);
+    assert!(json.get("user").is_none());
+}
+
+#[test]
+fn test_environments_response_deserialization() {
+    let json = r#"{
+        "environments": [
+            { "id": "env1", "name": "development", "variableCount": 12, "updatedAt": "2024-01-01T00:00:00Z" },
+            { "name": "production" }
+        ],
+        "count": 2
+    }"#;
+
+    let response: EnvironmentsResponse = serde_json::from_str(json).unwrap();
+    assert_eq!(response.count, 2);
+    assert_eq!(response.environments[0].name, "development");
+    assert_eq!(response.environments[0].variable_count, Some(12));
+    assert!(response.environments[1].id.is_none());
+}
+
+#[test]
+fn test_members_response_deserialization() {
+    let json = r#"{
+        "members": [
+            { "id": "u1", "name": "Alice", "email": "alice@example.com", "role": "owner", "joinedAt": "2024-01-01T00:00:00Z" },
+            { "id": "u2", "email": "bob@example.com", "role": "member" }
+        ],
+        "count": 2
+    }"#;
+
+    let response: MembersResponse = serde_json::from_str(json).unwrap();
+    assert_eq!(response.members.len(), 2);
+    assert_eq!(response.members[0].role, "owner");
+    assert!(response.members[1].name.is_none());
+}
+
+#[test]
+fn test_project_update_only_sends_changed_fields() {
+    let update = ProjectUpdate {
+        archived: Some(true),
+        ..Default::default()
+    };
+
+    let json = serde_json::to_value(&update).unwrap();
+    assert_eq!(json, serde_json::json!({ "archived": true }));
+}
@@ -1,3 +1,6 @@
+// The help tests pass `&[...]` to `args`
+#![allow(clippy::needless_borrows_for_generic_args)]
+
 use assert_cmd::Command;
 use predicates::prelude::*;
 
@@ -176,3 +179,565 @@ fn test_rotate_help() {
         .success()
         .stdout(predicate::str::contains("Manage secret rotation"));
 }
+
+#[test]
+fn test_push_dry_run_flag() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["push", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--dry-run"))
+        .stdout(predicate::str::contains("--show-values"));
+}
+
+#[test]
+fn test_pull_dry_run_flag() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["pull", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--dry-run"))
+        .stdout(predicate::str::contains("--yes"));
+}
+
+#[test]
+fn test_diff_help() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["diff", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Compare variables"));
+}
+
+#[test]
+fn test_diff_local_files_exit_code() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let left = dir.path().join("left.env");
+    let right = dir.path().join("right.env");
+    std::fs::write(&left, "A=1\nB=2\n").unwrap();
+    std::fs::write(&right, "A=1\nB=3\nC=4\n").unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["diff", "--json"])
+        .arg(&left)
+        .arg(&right)
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("\"only_in_right\""))
+        .stdout(predicate::str::contains("\"C\""));
+
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .arg("diff")
+        .arg(&left)
+        .arg(&left)
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_promote_help() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["promote", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Copy variables"));
+}
+
+#[test]
+fn test_promote_requires_keys_or_all() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["promote", "--from", "staging", "--to", "production"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--keys"));
+}
+
+#[test]
+fn test_get_help() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["get", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Print the value of a single variable",
+        ));
+}
+
+#[test]
+fn test_set_help() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["set", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--stdin"));
+}
+
+#[test]
+fn test_unset_requires_key() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .arg("unset")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("required"));
+}
+
+#[test]
+fn test_push_mode_flag() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["push", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--mode"))
+        .stdout(predicate::str::contains("replace"))
+        .stdout(predicate::str::contains("--force"));
+}
+
+#[test]
+fn test_push_rejects_unknown_mode() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["push", "--mode", "overwrite"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value"));
+}
+
+#[test]
+fn test_history_help() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["history", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Show past versions"));
+}
+
+#[test]
+fn test_rollback_rejects_invalid_point() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["rollback", "--to", "yesterday"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid point in time"));
+}
+
+#[test]
+fn test_audit_help() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["audit", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Show who read or changed secrets"))
+        .stdout(predicate::str::contains("--csv"));
+}
+
+#[test]
+fn test_audit_rejects_invalid_since() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["audit", "--since", "last-week"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid time"));
+}
+
+#[test]
+fn test_env_help() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["env", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Manage the environments of a project",
+        ))
+        .stdout(predicate::str::contains("rename"))
+        .stdout(predicate::str::contains("clone"))
+        .stdout(predicate::str::contains("alias"));
+}
+
+#[test]
+fn test_env_alias_remove_requires_name() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["env", "alias", "--remove"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_project_help() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["project", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("describe"))
+        .stdout(predicate::str::contains("archive"))
+        .stdout(predicate::str::contains("delete"));
+}
+
+#[test]
+fn test_workspace_members_help() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["workspace", "members", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--json"));
+}
+
+#[test]
+fn test_output_rejects_unknown_format() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["whoami", "--output", "xml"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("json"));
+}
+
+#[test]
+fn test_config_show_json_output() {
+    let home = tempfile::TempDir::new().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["config", "--show", "--output", "json"])
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["token_set"], false);
+    assert_eq!(document["language"], "en");
+}
+
+#[test]
+fn test_config_show_quiet_has_no_heading() {
+    let home = tempfile::TempDir::new().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["config", "--show", "--quiet"])
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("API URL"))
+        .stdout(predicate::str::contains("Configuration").not());
+}
+
+#[test]
+fn test_prompt_fails_fast_without_terminal() {
+    let home = tempfile::TempDir::new().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .arg("lang")
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .timeout(std::time::Duration::from_secs(10))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("without an interactive terminal"))
+        .stderr(predicate::str::contains("envsafe lang <en|fr>"));
+}
+
+#[test]
+fn test_plain_output_has_no_emoji() {
+    let home = tempfile::TempDir::new().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["config", "--show", "--plain"])
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .env("CLICOLOR_FORCE", "1")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Configuration"))
+        .stdout(predicate::str::contains("\u{1b}[").not());
+}
+
+#[test]
+fn test_clicolor_force_enables_colors() {
+    let home = tempfile::TempDir::new().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["config", "--show"])
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .env("CLICOLOR_FORCE", "1")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\u{1b}["));
+}
+
+#[test]
+fn test_init_help_lists_non_interactive_flags() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["init", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--workspace"))
+        .stdout(predicate::str::contains("--project"))
+        .stdout(predicate::str::contains("--create"));
+}
+
+#[test]
+fn test_init_create_requires_project() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["init", "--create"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--project"));
+}
+
+#[test]
+fn test_pull_rejects_unknown_format() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["pull", "--format", "toml"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value"));
+}
+
+#[test]
+fn test_pull_all_services_conflicts_with_project() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["pull", "api", "--all-services"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--all-services"));
+}
+
+#[test]
+fn test_run_help_lists_service() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["run", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--service"));
+}
+
+#[test]
+fn test_cache_help() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["cache", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("list"))
+        .stdout(predicate::str::contains("clear"))
+        .stdout(predicate::str::contains("inspect"));
+}
+
+#[test]
+fn test_cache_clear_all_conflicts_with_target() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["cache", "clear", "api/dev", "--all"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--all"));
+}
+
+#[test]
+fn test_run_no_cache_conflicts_with_refresh() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["run", "--no-cache", "--refresh", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--refresh"));
+}
+
+#[test]
+fn test_run_rejects_invalid_max_age() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["run", "--max-age", "soon", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid duration"));
+}
+
+#[test]
+fn test_config_cache_max_age() {
+    let home = tempfile::TempDir::new().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["config", "--cache-max-age", "15m"])
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .assert()
+        .success();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["config", "--show", "--output", "json"])
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .output()
+        .unwrap();
+
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["cache_max_age"], 900);
+}
+
+#[test]
+fn test_agent_help() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["agent", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("start"))
+        .stdout(predicate::str::contains("status"))
+        .stdout(predicate::str::contains("stop"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_agent_status_without_agent() {
+    let dir = tempfile::TempDir::new().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["agent", "status", "--output", "json"])
+        .env("ENVSAFE_AGENT_SOCKET", dir.path().join("agent.sock"))
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["running"], false);
+}
+
+#[test]
+fn test_serve_help() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["serve", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--token-file"))
+        .stdout(predicate::str::contains("--port"));
+}
+
+#[test]
+fn test_serve_refuses_non_loopback_address() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["serve", "--bind", "0.0.0.0", "--port", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("loopback"));
+}
+
+#[test]
+fn test_cache_purge_help() {
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["cache", "purge", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--all"));
+}
+
+#[test]
+fn test_doctor_json() {
+    let home = tempfile::TempDir::new().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["doctor", "--output", "json"])
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .env("ENVSAFE_AGENT_SOCKET", home.path().join("agent.sock"))
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let checks = document["checks"].as_array().unwrap();
+    let login = checks.iter().find(|c| c["name"] == "login").unwrap();
+    assert_eq!(login["ok"], false);
+    assert!(checks.iter().any(|c| c["name"] == "orphaned segments"));
+    assert!(document["segments"].is_array());
+}
+
+#[test]
+fn test_diff_exit_codes_follow_diff() {
+    let home = tempfile::TempDir::new().unwrap();
+    let file = home.path().join("local.env");
+    std::fs::write(&file, "A=1\n").unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["diff", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 on errors"));
+
+    // A failure is not mistaken for a difference
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .args(["diff", "web/production"])
+        .arg(&file)
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Error"));
+}
+
+#[test]
+fn test_pull_legacy_output_file_is_migrated() {
+    let home = tempfile::TempDir::new().unwrap();
+
+    // Not logged in, but the old flag is understood rather than rejected as a format
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .args(["pull", "web", "--output", ".env.local"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Use 'pull --file .env.local'"))
+        .stderr(predicate::str::contains("invalid value").not());
+}
+
+#[test]
+fn test_setup_commands_emit_documents() {
+    let home = tempfile::TempDir::new().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .args(["lang", "fr", "--output", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"language\": \"fr\""));
+
+    // Settings changes report the resulting configuration, not only --show
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .args(["config", "--cache-max-age", "10m", "--output", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"cache_max_age\": 600"));
+}
+
+#[test]
+fn test_table_output_rejected_without_table_rendering() {
+    let home = tempfile::TempDir::new().unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .args(["lang", "fr", "--output", "table"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "--output table is only supported by",
+        ));
+}
+
+#[test]
+fn test_diff_structured_files_and_quiet() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let left = dir.path().join("left.json");
+    let right = dir.path().join("right.yaml");
+    std::fs::write(&left, r#"{"A": "1", "B": "2"}"#).unwrap();
+    std::fs::write(&right, "A: '1'\nB: '3'\n").unwrap();
+
+    // Parsed by extension, not as dotenv; --quiet keeps the differences themselves
+    Command::new(env!("CARGO_BIN_EXE_envsafe"))
+        .args(["diff", "--quiet"])
+        .arg(&left)
+        .arg(&right)
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("Changed:"))
+        .stdout(predicate::str::contains("B:"))
+        .stdout(predicate::str::contains("Comparing").not());
+}
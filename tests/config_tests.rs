@@ -1,4 +1,6 @@
+use envsafe_cli::api::Project;
 use envsafe_cli::config::{Config, ProjectConfig};
+use envsafe_cli::utils::env_parser::FileFormat;
 use std::fs;
 use tempfile::TempDir;
 
@@ -32,6 +34,7 @@ fn test_project_config_serialization() {
         project_id: "project456".to_string(),
         project_name: "Test Project".to_string(),
         project_slug: Some("test-project".to_string()),
+        ..Default::default()
     };
 
     // Save config
@@ -62,3 +65,233 @@ fn test_project_config_backward_compatibility() {
     assert_eq!(config.project_id, "proj1");
     assert_eq!(config.project_slug, None);
 }
+
+#[test]
+fn test_config_environment_aliases() {
+    let mut config = Config::default();
+    assert_eq!(config.resolve_environment("dev"), "development");
+    assert_eq!(config.resolve_environment("prod"), "production");
+    assert_eq!(config.resolve_environment("qa"), "qa");
+
+    config
+        .env_aliases
+        .insert("qa".to_string(), "quality-assurance".to_string());
+    config
+        .env_aliases
+        .insert("prod".to_string(), "live".to_string());
+    assert_eq!(config.resolve_environment("qa"), "quality-assurance");
+    assert_eq!(config.resolve_environment("prod"), "live");
+}
+
+#[test]
+fn test_config_without_aliases_still_loads() {
+    let mut value = serde_json::to_value(Config::default()).unwrap();
+    value.as_object_mut().unwrap().remove("env_aliases");
+
+    let config: Config = serde_json::from_value(value).unwrap();
+    assert!(config.env_aliases.is_empty());
+}
+
+#[test]
+fn test_project_config_default_environment() {
+    let json = r#"{
+        "workspace_id": "ws1",
+        "project_id": "proj1",
+        "project_name": "Project",
+        "default_environment": "staging"
+    }"#;
+
+    let config: ProjectConfig = serde_json::from_str(json).unwrap();
+    assert_eq!(config.environment(), "staging");
+
+    let config = ProjectConfig::default();
+    assert_eq!(config.environment(), "development");
+    assert!(!serde_json::to_string(&config)
+        .unwrap()
+        .contains("default_environment"));
+}
+
+#[test]
+fn test_project_config_branch_environments() {
+    let json = r#"{
+        "workspace_id": "ws1",
+        "project_id": "proj1",
+        "project_name": "Project",
+        "branches": {
+            "main": "production",
+            "release/*": "staging",
+            "release/hotfix-*": "hotfix"
+        }
+    }"#;
+
+    let config: ProjectConfig = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        config.branch_environment("main"),
+        Some("production".to_string())
+    );
+    assert_eq!(
+        config.branch_environment("release/2.1"),
+        Some("staging".to_string())
+    );
+    assert_eq!(
+        config.branch_environment("release/hotfix-login"),
+        Some("hotfix".to_string())
+    );
+    assert_eq!(config.branch_environment("feature/login"), None);
+}
+
+#[test]
+fn test_project_config_local_settings() {
+    let json = r#"{
+        "workspace_id": "ws1",
+        "project_id": "proj1",
+        "project_name": "Project",
+        "output_file": "config/env.json",
+        "format": "json",
+        "include": ["NEXT_PUBLIC_*"],
+        "exclude": ["*_SECRET"]
+    }"#;
+
+    let config: ProjectConfig = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        ProjectConfig::local_file(Some(&config), None, None),
+        ("config/env.json".to_string(), FileFormat::Json)
+    );
+    assert_eq!(
+        ProjectConfig::local_file(Some(&config), Some(".env.local".to_string()), None),
+        (".env.local".to_string(), FileFormat::Json)
+    );
+    assert_eq!(
+        ProjectConfig::local_file(None, None, None),
+        (".env".to_string(), FileFormat::Dotenv)
+    );
+    assert!(config.filter.allows("NEXT_PUBLIC_API_URL"));
+    assert!(!config.filter.allows("NEXT_PUBLIC_SECRET"));
+
+    let saved = serde_json::to_string(&ProjectConfig::default()).unwrap();
+    assert!(!saved.contains("include"));
+    assert!(!saved.contains("branches"));
+}
+
+#[test]
+fn test_project_config_found_in_parent_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).unwrap();
+    fs::create_dir_all(root.join("src/handlers")).unwrap();
+    fs::write(
+        root.join(".envsafe"),
+        r#"{ "workspace_id": "ws1", "project_id": "proj1", "project_name": "Web" }"#,
+    )
+    .unwrap();
+
+    let config = ProjectConfig::find_from(&root.join("src/handlers"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(config.project_id, "proj1");
+    assert_eq!(
+        ProjectConfig::local_file(Some(&config), None, None).0,
+        "../../.env"
+    );
+
+    // The search stops at the repository root
+    let nested = root.join("vendor/lib");
+    fs::create_dir_all(nested.join(".git")).unwrap();
+    assert!(ProjectConfig::find_from(&nested).unwrap().is_none());
+}
+
+#[test]
+fn test_project_config_monorepo_services() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir(root.join(".git")).unwrap();
+    fs::create_dir_all(root.join("services/api/src")).unwrap();
+    fs::write(
+        root.join(".envsafe"),
+        r#"{
+            "workspace_id": "ws1",
+            "project_id": "proj1",
+            "project_name": "Platform",
+            "default_environment": "staging",
+            "services": {
+                "api": { "path": "services/api", "project": "payments-api", "environment": "qa" },
+                "web": { "path": "apps/web", "output_file": ".env.local" }
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let api = ProjectConfig::find_from(&root.join("services/api/src"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(api.service.as_deref(), Some("api"));
+    assert_eq!(api.project_slug.as_deref(), Some("payments-api"));
+    assert_eq!(api.environment(), "qa");
+    assert_eq!(
+        ProjectConfig::local_file(Some(&api), None, None).0,
+        "../.env"
+    );
+    assert!(api.save().is_err());
+
+    let root_config = ProjectConfig::find_from(root).unwrap().unwrap();
+    assert!(root_config.service.is_none());
+
+    let web = root_config.for_service("web").unwrap();
+    assert_eq!(web.project_id, "proj1");
+    assert_eq!(web.environment(), "staging");
+    assert_eq!(
+        ProjectConfig::local_file(Some(&web), None, None).0,
+        "apps/web/.env.local"
+    );
+
+    let err = root_config.for_service("wbe").unwrap_err().to_string();
+    assert!(err.contains("Did you mean 'web'"));
+}
+
+#[test]
+fn test_project_config_relink_keeps_other_settings() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+
+    // A new link goes to the directory's .envsafe
+    let config = ProjectConfig::load_in(dir).unwrap();
+    assert_eq!(
+        config.source.as_deref(),
+        Some(dir.join(".envsafe").as_path())
+    );
+
+    fs::write(
+        dir.join(".envsafe"),
+        r#"{
+            "workspace_id": "ws1",
+            "project_id": "proj1",
+            "project_name": "Web",
+            "default_environment": "staging",
+            "output_file": ".env.local",
+            "format": "json",
+            "include": ["API_*"],
+            "branches": { "main": "production" },
+            "services": { "api": { "path": "services/api" } }
+        }"#,
+    )
+    .unwrap();
+
+    // What `envsafe select` does when switching project
+    let project: Project =
+        serde_json::from_str(r#"{ "id": "proj2", "name": "Payments", "slug": "payments" }"#)
+            .unwrap();
+    let mut config = ProjectConfig::load_in(dir).unwrap();
+    config.link("ws2", &project);
+    config.save().unwrap();
+
+    let saved = ProjectConfig::load_in(dir).unwrap();
+    assert_eq!(saved.workspace_id, "ws2");
+    assert_eq!(saved.project_id, "proj2");
+    assert_eq!(saved.project_slug.as_deref(), Some("payments"));
+    assert_eq!(saved.default_environment.as_deref(), Some("staging"));
+    assert_eq!(saved.output_file.as_deref(), Some(".env.local"));
+    assert_eq!(saved.format, Some(FileFormat::Json));
+    assert_eq!(saved.filter.include, vec!["API_*".to_string()]);
+    assert_eq!(saved.branches["main"], "production");
+    assert!(saved.services.contains_key("api"));
+}
@@ -0,0 +1,115 @@
+use envsafe_cli::storage::{EnvStorage, SegmentKey, SharedEnvData};
+use std::collections::HashMap;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const ROLE_VAR: &str = "ENVSAFE_STRESS_ROLE";
+const PROJECT_VAR: &str = "ENVSAFE_STRESS_PROJECT";
+const WRITES: u64 = 300;
+
+fn stress_key(project: &str) -> SegmentKey {
+    SegmentKey::new("stress-workspace", project, "development")
+}
+
+/// Every variable carries the version, so a torn read shows up as mixed values
+fn snapshot(key: &SegmentKey, version: u64) -> SharedEnvData {
+    let variables: HashMap<String, String> = (0..50 + version % 200)
+        .map(|i| {
+            (
+                format!("KEY_{}", i),
+                format!("{}-{}", version, "x".repeat(64)),
+            )
+        })
+        .collect();
+
+    SharedEnvData {
+        version,
+        project_id: key.project.clone(),
+        environment: key.environment.clone(),
+        variables,
+        last_updated: chrono::Utc::now().to_rfc3339(),
+        etag: None,
+    }
+}
+
+/// Body of the child processes spawned by the stress test; does nothing when run normally
+#[test]
+fn shm_stress_worker() {
+    let (Ok(role), Ok(project)) = (std::env::var(ROLE_VAR), std::env::var(PROJECT_VAR)) else {
+        return;
+    };
+    let key = stress_key(&project);
+
+    match role.as_str() {
+        "writer" => {
+            let mut storage = EnvStorage::open(&key).unwrap();
+            for version in 1..=WRITES {
+                storage.write(&snapshot(&key, version)).unwrap();
+            }
+        }
+        "reader" => {
+            let deadline = Instant::now() + Duration::from_secs(20);
+            let mut seen = 0;
+            while Instant::now() < deadline {
+                let Some(storage) = EnvStorage::open_existing(&key).unwrap() else {
+                    continue;
+                };
+                let Some(data) = storage.read().unwrap() else {
+                    continue;
+                };
+
+                let prefix = format!("{}-", data.version);
+                assert!(
+                    data.variables.values().all(|v| v.starts_with(&prefix)),
+                    "torn read at version {}",
+                    data.version
+                );
+                assert_eq!(data.variables.len() as u64, 50 + data.version % 200);
+
+                seen += 1;
+                if data.version == WRITES && seen > 100 {
+                    break;
+                }
+            }
+        }
+        other => panic!("unknown role {}", other),
+    }
+}
+
+#[test]
+fn test_concurrent_processes_never_see_torn_writes() {
+    let project = format!("stress-{}", std::process::id());
+    let key = stress_key(&project);
+
+    // Create the segment up front so readers have something to open
+    EnvStorage::open(&key)
+        .unwrap()
+        .write(&snapshot(&key, 0))
+        .unwrap();
+
+    let spawn = |role: &str| {
+        Command::new(std::env::current_exe().unwrap())
+            .args(["shm_stress_worker", "--exact", "--test-threads=1"])
+            .env(ROLE_VAR, role)
+            .env(PROJECT_VAR, &project)
+            .spawn()
+            .unwrap()
+    };
+
+    let children: Vec<_> = ["writer", "writer", "reader", "reader", "reader"]
+        .iter()
+        .map(|role| spawn(role))
+        .collect();
+
+    let results: Vec<bool> = children
+        .into_iter()
+        .map(|mut child| child.wait().unwrap().success())
+        .collect();
+
+    EnvStorage::remove(&key).unwrap();
+    assert!(
+        results.iter().all(|ok| *ok),
+        "a worker failed: {:?}",
+        results
+    );
+}